@@ -0,0 +1,217 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use eframe::egui::{self, text::LayoutJob, FontId, RichText, TextFormat};
+use regex::RegexBuilder;
+
+use crate::link_graph::context_around;
+use crate::links::Link;
+use crate::search::{self, append_highlighted, Highlight};
+use crate::{links, tags, Language, MdReader};
+
+// Слишком короткие названия находятся повсюду — для них упоминания не ищем
+const MENTION_MIN_TITLE_LEN: usize = 3;
+// Как часто панель проверяет, закончился ли поиск
+const WORKER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A sentence of another note that refers to the open one; `highlight` is inside `text`.
+pub struct Mention {
+    pub text: String,
+    pub highlight: Range<usize>,
+    /// Where the link or the title is in the other note.
+    pub source_range: Range<usize>,
+}
+
+type Mentions = Vec<(PathBuf, Vec<Mention>)>;
+
+/// What the backlinks panel shows. Notes are read on a background thread; until it is done the panel
+/// keeps the previous results for the same note.
+#[derive(Default)]
+pub struct Backlinks {
+    /// The note and link graph generation the results, or the search still running, are for.
+    key: Option<(PathBuf, u64)>,
+    linked: Mentions,
+    unlinked: Mentions,
+    worker: Option<Receiver<(Mentions, Mentions)>>,
+}
+
+/// The names a note goes by in plain text: its first line (as in the sidebar) and its file name.
+fn note_titles(path: &Path, content: &str) -> Vec<String> {
+    let heading = tags::body(content).lines().next().unwrap_or("").trim_start_matches(['#', ' ']).trim().to_string();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let mut titles = vec![heading, stem];
+    titles.retain(|title| title.chars().count() >= MENTION_MIN_TITLE_LEN);
+    titles.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    titles
+}
+
+/// Places in `content` where one of `titles` appears as whole words outside of code.
+fn find_mentions(content: &str, titles: &[String]) -> Vec<Mention> {
+    let pattern = titles.iter().map(|title| regex::escape(title)).collect::<Vec<_>>().join("|");
+    let Ok(regex) = RegexBuilder::new(&format!(r"\b(?:{})\b", pattern)).case_insensitive(true).build() else {
+        return Vec::new();
+    };
+    let code = links::code_ranges(content);
+    regex.find_iter(content)
+        .filter(|m| !code.iter().any(|range| range.contains(&m.start())))
+        .map(|m| {
+            let (text, highlight) = context_around(content, m.range());
+            Mention { text, highlight, source_range: m.range() }
+        })
+        .collect()
+}
+
+/// Sentences around the links of `linking` notes, and mentions of `titles` in the other `notes`.
+fn find_backlinks(note: &Path, titles: &[String], linking: Vec<(PathBuf, Vec<Link>)>, notes: Vec<PathBuf>) -> (Mentions, Mentions) {
+    let linked: Mentions = linking.into_iter()
+        .filter_map(|(source, found)| {
+            let content = fs::read_to_string(&source).ok()?;
+            let mentions = found.iter()
+                .map(|link| {
+                    let (text, highlight) = context_around(&content, link.range.clone());
+                    Mention { text, highlight, source_range: link.range.clone() }
+                })
+                .collect();
+            Some((source, mentions))
+        })
+        .collect();
+
+    // Непривязанные упоминания ищем только в заметках, которые ещё не ссылаются на эту
+    let mut unlinked: Mentions = Vec::new();
+    if !titles.is_empty() {
+        for source in notes {
+            if source == note || linked.iter().any(|(linked_source, _)| *linked_source == source) {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&source) else { continue };
+            let mentions = find_mentions(&content, titles);
+            if !mentions.is_empty() {
+                unlinked.push((source, mentions));
+            }
+        }
+        unlinked.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    (linked, unlinked)
+}
+
+impl MdReader {
+    /// Picks up finished results and starts a new search once the note or the link graph changed.
+    fn refresh_backlinks(&mut self, ctx: &egui::Context, note: &Path) {
+        let backlinks = &mut self.backlinks;
+        if let Some(worker) = &backlinks.worker {
+            match worker.try_recv() {
+                Ok((linked, unlinked)) => {
+                    backlinks.linked = linked;
+                    backlinks.unlinked = unlinked;
+                    backlinks.worker = None;
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint_after(WORKER_POLL_INTERVAL),
+                Err(TryRecvError::Disconnected) => backlinks.worker = None,
+            }
+        }
+        let key = (note.to_path_buf(), self.link_graph.generation);
+        if backlinks.key.as_ref() == Some(&key) {
+            return;
+        }
+        // Результаты другой заметки не показываем даже пока ищем
+        if backlinks.key.as_ref().is_none_or(|(previous, _)| previous != note) {
+            backlinks.linked.clear();
+            backlinks.unlinked.clear();
+        }
+
+        let note = note.to_path_buf();
+        let titles = note_titles(&note, &self.file_content);
+        let linking = self.link_graph.backlinks(&self.root_dir, &note);
+        let notes: Vec<PathBuf> = self.link_graph.notes().cloned().collect();
+        let (sender, receiver) = mpsc::channel();
+        let repaint = ctx.clone();
+        thread::spawn(move || {
+            let _ = sender.send(find_backlinks(&note, &titles, linking, notes));
+            repaint.request_repaint();
+        });
+        backlinks.worker = Some(receiver);
+        backlinks.key = Some(key);
+    }
+
+    fn render_mentions(&self, ui: &mut egui::Ui, groups: &Mentions, to_open: &mut Option<(PathBuf, Option<Range<usize>>)>) {
+        let format = TextFormat {
+            font_id: FontId::proportional(13.0),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+        let highlight = search::match_color();
+
+        for (source, mentions) in groups {
+            let relative = links::relative_path(&self.root_dir, source);
+            let name = source.file_stem().unwrap_or_default().to_string_lossy();
+            if ui.link(RichText::new(format!("📄 {}", name)).strong()).on_hover_text(relative).clicked() {
+                *to_open = Some((source.clone(), None));
+            }
+            for mention in mentions {
+                let mut job = LayoutJob::default();
+                job.wrap.max_width = ui.available_width();
+                append_highlighted(&mut job, &mention.text, &[(mention.highlight.clone(), highlight)], format.clone());
+                let response = ui.add(egui::Label::new(job).sense(egui::Sense::click()));
+                if response.clicked() {
+                    *to_open = Some((source.clone(), Some(mention.source_range.clone())));
+                }
+            }
+            ui.add_space(6.0);
+        }
+    }
+
+    /// Right-hand panel with the notes linking to the open one and the notes mentioning its title.
+    pub(crate) fn render_backlinks_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_backlinks || self.edit_mode {
+            return;
+        }
+        let Some(note) = self.selected_file.clone() else { return };
+        self.refresh_backlinks(ctx, &note);
+        let backlinks = &self.backlinks;
+
+        let (title, linked_text, unlinked_text, nothing) = match self.current_language {
+            Language::EN => ("Backlinks", "Linked mentions", "Unlinked mentions", "None"),
+            Language::RU => ("Обратные ссылки", "Ссылки", "Упоминания без ссылки", "Нет"),
+        };
+
+        let mut to_open = None;
+        egui::SidePanel::right("backlinks")
+            .resizable(true)
+            .default_width(260.0)
+            .show(ctx, |ui| {
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    ui.heading(title);
+                    if backlinks.worker.is_some() {
+                        ui.spinner();
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().id_source("backlinks_scroll").show(ui, |ui| {
+                    for (header, groups) in [(linked_text, &backlinks.linked), (unlinked_text, &backlinks.unlinked)] {
+                        let count: usize = groups.iter().map(|(_, mentions)| mentions.len()).sum();
+                        egui::CollapsingHeader::new(RichText::new(format!("{} ({})", header, count)).strong())
+                            .id_source(header)
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                if groups.is_empty() {
+                                    ui.label(RichText::new(nothing).weak());
+                                }
+                                self.render_mentions(ui, groups, &mut to_open);
+                            });
+                    }
+                });
+            });
+
+        if let Some((path, range)) = to_open {
+            self.load_file(&path);
+            if let Some(range) = range {
+                self.highlight = Some(Highlight { ranges: vec![range], focused: 0, scroll_pending: true });
+            }
+        }
+    }
+}
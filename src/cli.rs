@@ -0,0 +1,314 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::export::{self, ExportFormat, ExportOptions};
+use crate::health::{self, Fix, Issue, IssueKind};
+use crate::index::SearchIndex;
+use crate::links;
+use crate::pdf::{PageSize, PdfOptions};
+use crate::search::{self, SearchOptions};
+use crate::sort::SortMode;
+use crate::{tui, workspace, Language};
+
+// Коды выхода как у grep: 1 — ничего не нашлось или есть битые ссылки, 2 — ошибка
+const EXIT_OK: i32 = 0;
+const EXIT_NOT_FOUND: i32 = 1;
+const EXIT_BROKEN_LINKS: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+/// Arguments after the subcommand: positional ones, `--name value` options and `--flags`.
+struct Arguments {
+    positional: Vec<String>,
+    values: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Arguments {
+    /// `values` are the options that take a value, `flags` the ones that don't; anything else is an error.
+    fn parse(args: &[String], values: &[&str], flags: &[&str], language: Language) -> Result<Self, String> {
+        let mut parsed = Arguments { positional: Vec::new(), values: Vec::new(), flags: Vec::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let Some(option) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            let (name, inline_value) = match option.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (option, None),
+            };
+            if values.contains(&name) {
+                let value = inline_value.or_else(|| args.next().cloned()).ok_or_else(|| match language {
+                    Language::EN => format!("--{} needs a value", name),
+                    Language::RU => format!("Для --{} нужно указать значение", name),
+                })?;
+                parsed.values.push((name.to_string(), value));
+            } else if flags.contains(&name) && inline_value.is_none() {
+                parsed.flags.push(name.to_string());
+            } else {
+                return Err(match language {
+                    Language::EN => format!("Unknown option: {}", arg),
+                    Language::RU => format!("Неизвестный параметр: {}", arg),
+                });
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values.iter().rev().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    /// The workspace folder: `--root` or the current folder, like the window opens.
+    fn root(&self, language: Language) -> Result<PathBuf, String> {
+        let root = match self.value("root") {
+            Some(root) => absolute(Path::new(root)),
+            None => std::env::current_dir().map_err(|e| e.to_string())?,
+        };
+        if !root.is_dir() {
+            return Err(match language {
+                Language::EN => format!("Not a folder: {}", root.display()),
+                Language::RU => format!("Это не папка: {}", root.display()),
+            });
+        }
+        Ok(root)
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    links::normalize(&std::env::current_dir().unwrap_or_default().join(path))
+}
+
+fn usage(language: Language) -> &'static str {
+    match language {
+        Language::EN => "Usage:
+  mdreader                       open the notes in the current folder
+  mdreader --tui                 read the notes in the terminal, e.g. over SSH
+  mdreader export <note.md|folder> [--format html|pdf|epub|docx|odt] [--output <file>]
+                  [--page-size a4|a5|letter|legal] [--margin <mm>] [--dark]
+  mdreader search <query> [--regex] [--case-sensitive] [--whole-word]
+  mdreader check [--all]          report broken links; --all adds orphan notes and empty categories
+  mdreader index                  bring the search index up to date
+
+Every command takes --root <folder>, the notes folder; the current folder by default.
+",
+        Language::RU => "Использование:
+  mdreader                       открыть заметки из текущей папки
+  mdreader --tui                 читать заметки в терминале, например по SSH
+  mdreader export <заметка.md|папка> [--format html|pdf|epub|docx|odt] [--output <файл>]
+                  [--page-size a4|a5|letter|legal] [--margin <мм>] [--dark]
+  mdreader search <запрос> [--regex] [--case-sensitive] [--whole-word]
+  mdreader check [--all]          битые ссылки; с --all ещё заметки без ссылок и пустые категории
+  mdreader index                  обновить поисковый индекс
+
+Все команды принимают --root <папка> — папку с заметками, по умолчанию текущую.
+",
+    }
+}
+
+fn unexpected_arguments(language: Language) -> String {
+    match language {
+        Language::EN => "Unexpected arguments, see mdreader help".to_string(),
+        Language::RU => "Лишние или недостающие аргументы, см. mdreader help".to_string(),
+    }
+}
+
+/// Runs a subcommand without opening the window. `None` means there is none and the app should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    let language = Language::from_system();
+    if rest.iter().any(|arg| arg == "--help") {
+        print!("{}", usage(language));
+        return Some(EXIT_OK);
+    }
+    let result = match command.as_str() {
+        "export" => export(rest, language),
+        "search" => search(rest, language),
+        "check" => check(rest, language),
+        "index" => index(rest, language),
+        "--tui" => tui(rest, language),
+        "help" | "--help" | "-h" => {
+            print!("{}", usage(language));
+            Ok(EXIT_OK)
+        }
+        _ => Err(match language {
+            Language::EN => format!("Unknown command: {}\n\n{}", command, usage(language)),
+            Language::RU => format!("Неизвестная команда: {}\n\n{}", command, usage(language)),
+        }),
+    };
+    Some(result.unwrap_or_else(|e| {
+        eprintln!("mdreader: {}", e);
+        EXIT_ERROR
+    }))
+}
+
+fn export(args: &[String], language: Language) -> Result<i32, String> {
+    let args = Arguments::parse(args, &["root", "format", "output", "page-size", "margin"], &["dark"], language)?;
+    let [path] = args.positional.as_slice() else { return Err(unexpected_arguments(language)) };
+    let path = absolute(Path::new(path));
+    let root = args.root(language)?;
+
+    // Без --format формат берём из расширения --output
+    let format_name = args.value("format")
+        .map(str::to_string)
+        .or_else(|| args.value("output").and_then(|output| Some(Path::new(output).extension()?.to_string_lossy().to_string())))
+        .unwrap_or_else(|| ExportFormat::Html.extension().to_string());
+    let format = ExportFormat::ALL.into_iter()
+        .find(|format| format.extension().eq_ignore_ascii_case(&format_name))
+        .ok_or_else(|| match language {
+            Language::EN => format!("Unknown format: {}", format_name),
+            Language::RU => format!("Неизвестный формат: {}", format_name),
+        })?;
+
+    let mut pdf = PdfOptions::default();
+    if let Some(name) = args.value("page-size") {
+        pdf.page_size = PageSize::ALL.into_iter()
+            .find(|size| size.label().eq_ignore_ascii_case(name))
+            .ok_or_else(|| match language {
+                Language::EN => format!("Unknown page size: {}", name),
+                Language::RU => format!("Неизвестный размер страницы: {}", name),
+            })?;
+    }
+    if let Some(margin) = args.value("margin") {
+        pdf.margin = margin.parse::<f32>().ok().filter(|margin| (0.0..=60.0).contains(margin)).ok_or_else(|| match language {
+            Language::EN => format!("Margins are millimeters from 0 to 60, not {}", margin),
+            Language::RU => format!("Поля задаются в миллиметрах от 0 до 60, а не {}", margin),
+        })?;
+    }
+
+    let (book, title, notes) = if path.is_dir() {
+        if !format.supports_books() {
+            return Err(match language {
+                Language::EN => format!("Only pdf and epub can export a whole folder, not {}", format.extension()),
+                Language::RU => format!("Целую папку можно экспортировать только в pdf и epub, а не в {}", format.extension()),
+            });
+        }
+        let categories = workspace::scan_categories(&root, SortMode::Manual);
+        if workspace::find_category(&categories, &path).is_none() {
+            return Err(match language {
+                Language::EN => format!("{} is not a category of {}", path.display(), root.display()),
+                Language::RU => format!("{} не категория в {}", path.display(), root.display()),
+            });
+        }
+        let (title, notes) = export::book_contents(&categories, &path)?;
+        (Some(path.as_path()), title, notes)
+    } else {
+        let content = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        (None, export::note_title(&path, &content), vec![(path.clone(), content)])
+    };
+
+    let options = ExportOptions { format, pdf, dark_mode: args.flag("dark"), language };
+    let bytes = export::export_notes(&root, book, &title, &notes, &options)?;
+    let target = args.value("output").map_or_else(|| path.with_extension(format.extension()), |output| absolute(Path::new(output)));
+    fs::write(&target, bytes).map_err(|e| format!("{}: {}", target.display(), e))?;
+    match language {
+        Language::EN => println!("Saved to {}", target.display()),
+        Language::RU => println!("Сохранено в {}", target.display()),
+    }
+    Ok(EXIT_OK)
+}
+
+fn search(args: &[String], language: Language) -> Result<i32, String> {
+    let args = Arguments::parse(args, &["root"], &["regex", "case-sensitive", "whole-word"], language)?;
+    // Запрос из нескольких слов можно не брать в кавычки
+    let query = args.positional.join(" ");
+    if query.trim().is_empty() {
+        return Err(unexpected_arguments(language));
+    }
+    let root = args.root(language)?;
+    let options = SearchOptions {
+        case_sensitive: args.flag("case-sensitive"),
+        whole_word: args.flag("whole-word"),
+        regex: args.flag("regex"),
+    };
+
+    let mut index = SearchIndex::load(&root);
+    index.sync(&root);
+    index.save_if_dirty(&root);
+    let (results, _) = search::search_workspace(&root, &index, &query, options, language)?;
+    for result in &results {
+        let path = links::relative_path(&root, &result.path);
+        // Заметки, найденные только по метаданным, выводим без строк
+        if result.lines.is_empty() {
+            println!("{}", path);
+        }
+        for line in &result.lines {
+            println!("{}:{}: {}", path, line.line, line.snippet.trim());
+        }
+    }
+    Ok(if results.is_empty() { EXIT_NOT_FOUND } else { EXIT_OK })
+}
+
+fn check(args: &[String], language: Language) -> Result<i32, String> {
+    let args = Arguments::parse(args, &["root"], &["all"], language)?;
+    if !args.positional.is_empty() {
+        return Err(unexpected_arguments(language));
+    }
+    let root = args.root(language)?;
+    let categories = workspace::scan_categories(&root, SortMode::Manual);
+    let issues: Vec<Issue> = health::check_notes(&root, &categories)
+        .into_iter()
+        .filter(|issue| args.flag("all") || issue.kind.is_broken_link())
+        .collect();
+
+    for kind in IssueKind::ALL {
+        let group: Vec<&Issue> = issues.iter().filter(|issue| issue.kind == kind).collect();
+        if group.is_empty() {
+            continue;
+        }
+        println!("{} ({})", kind.label(language), group.len());
+        for issue in group {
+            let mut line = format!("  {}", links::relative_path(&root, &issue.path));
+            if issue.range.is_some() {
+                line = format!("{}:{}", line, issue.line);
+            }
+            if !issue.detail.is_empty() {
+                line = format!("{}  {}", line, issue.detail);
+            }
+            if let Some(Fix::Replace { new, .. }) = &issue.fix {
+                line = format!("{}  → {}", line, new);
+            }
+            println!("{}", line);
+        }
+    }
+
+    match (issues.len(), language) {
+        (0, Language::EN) => println!("No problems found"),
+        (0, Language::RU) => println!("Проблем не найдено"),
+        (count, Language::EN) => println!("Problems found: {}", count),
+        (count, Language::RU) => println!("Найдено проблем: {}", count),
+    }
+    // Заметки без ссылок и пустые категории проверку не проваливают
+    let broken = issues.iter().filter(|issue| issue.kind.is_broken_link()).count();
+    Ok(if broken == 0 { EXIT_OK } else { EXIT_BROKEN_LINKS })
+}
+
+fn index(args: &[String], language: Language) -> Result<i32, String> {
+    let args = Arguments::parse(args, &["root"], &[], language)?;
+    if !args.positional.is_empty() {
+        return Err(unexpected_arguments(language));
+    }
+    let root = args.root(language)?;
+    let mut index = SearchIndex::load(&root);
+    index.sync(&root);
+    index.save(&root).map_err(|e| e.to_string())?;
+    match language {
+        Language::EN => println!("Notes in the index: {}", index.note_count()),
+        Language::RU => println!("Заметок в индексе: {}", index.note_count()),
+    }
+    Ok(EXIT_OK)
+}
+
+fn tui(args: &[String], language: Language) -> Result<i32, String> {
+    let args = Arguments::parse(args, &["root"], &[], language)?;
+    if !args.positional.is_empty() {
+        return Err(unexpected_arguments(language));
+    }
+    let root = args.root(language)?;
+    tui::run(root, language).map_err(|e| e.to_string())?;
+    Ok(EXIT_OK)
+}
@@ -0,0 +1,229 @@
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use eframe::egui::{self, text_selection::LabelSelectionState, Id};
+use pulldown_cmark::{Event, Parser, Tag};
+
+use crate::export::{self, HtmlOptions};
+use crate::health::workspace_files;
+use crate::links::{self, NoteIndex};
+use crate::publish::wiki_links_to_markdown;
+use crate::{markdown_options, tags, MdReader};
+
+/// One read-mode label: its text and which bytes of the note each piece of it shows.
+#[derive(Clone)]
+pub struct SourceMap {
+    pub text: String,
+    /// Range in `text` and the range of the note it came from.
+    pub chunks: Vec<(Range<usize>, Range<usize>)>,
+}
+
+/// The read view of the note as drawn in the frame where copying happened.
+#[derive(Clone, Default)]
+struct RenderedNote {
+    content: Arc<str>,
+    labels: Arc<Vec<SourceMap>>,
+}
+
+fn rendered_note_id() -> Id {
+    Id::new("rendered_note_source")
+}
+
+/// Remembers what the read view drew, for the copy hook at the end of this frame.
+pub fn remember_rendered(ctx: &egui::Context, content: &str, labels: Vec<SourceMap>) {
+    let rendered = RenderedNote { content: content.into(), labels: Arc::new(labels) };
+    ctx.data_mut(|data| data.insert_temp(rendered_note_id(), rendered));
+}
+
+/// Replaces text copied from a selection in read mode with the markdown it was rendered from.
+/// Runs after egui's own label selection, which puts the rendered text on the clipboard.
+pub fn install_source_copy(ctx: &egui::Context) {
+    ctx.on_end_frame("markdown source copy", Arc::new(|ctx| {
+        let Some(rendered) = ctx.data_mut(|data| data.remove_temp::<RenderedNote>(rendered_note_id())) else { return };
+        if !LabelSelectionState::load(ctx).has_selection() {
+            return;
+        }
+        let copied = ctx.output(|output| output.copied_text.clone());
+        if copied.is_empty() {
+            return;
+        }
+        if let Some(source) = selection_source(&rendered.content, &rendered.labels, &copied) {
+            ctx.output_mut(|output| output.copied_text = source);
+        }
+    }));
+}
+
+/// Note offset for an offset inside a label; pieces whose text differs in length from the source
+/// (escapes, entities) map to their start or end.
+fn source_offset(map: &SourceMap, offset: usize, at_end: bool) -> Option<usize> {
+    // Начало выделения относится к куску справа от границы, конец — к куску слева
+    let inside = |text: &Range<usize>| if at_end { text.start < offset && offset <= text.end } else { text.start <= offset && offset < text.end };
+    if let Some((text, source)) = map.chunks.iter().find(|(text, _)| inside(text)) {
+        if text.len() == source.len() {
+            return Some(source.start + offset - text.start);
+        }
+        return Some(if at_end { source.end } else { source.start });
+    }
+    // Смещение попало на маркер списка или перенос строки — берём соседний кусок
+    if at_end {
+        map.chunks.iter().rev().find(|(text, _)| text.end <= offset).map(|(_, source)| source.end)
+    } else {
+        map.chunks.iter().find(|(text, _)| text.start >= offset).map(|(_, source)| source.start)
+    }
+}
+
+/// Where the copied text lies in the labels, as (label, byte offset) of its first character and of the end of its last one.
+/// egui does not say where the selection is, so the copied text is looked up in the labels, ignoring whitespace
+/// (egui puts its own line breaks between labels). Text that shows up more than once can't be placed.
+fn copied_range(labels: &[SourceMap], copied: &str) -> Option<[(usize, usize); 2]> {
+    // Видимые символы всех меток подряд; для каждого байта — откуда он взят
+    let mut visible = String::new();
+    let mut origins = Vec::new();
+    for (label, map) in labels.iter().enumerate() {
+        for (offset, c) in map.text.char_indices().filter(|(_, c)| !c.is_whitespace()) {
+            visible.push(c);
+            origins.extend(std::iter::repeat_n((label, offset), c.len_utf8()));
+        }
+    }
+    let needle: String = copied.chars().filter(|c| !c.is_whitespace()).collect();
+    let first = needle.chars().next()?;
+    let start = visible.find(&needle)?;
+    if visible[start + first.len_utf8()..].contains(&needle) {
+        return None;
+    }
+    let (last_label, last_offset) = origins[start + needle.len() - 1];
+    let last_len = labels[last_label].text[last_offset..].chars().next()?.len_utf8();
+    Some([origins[start], (last_label, last_offset + last_len)])
+}
+
+/// The markdown behind the label selection. A selection that starts or ends at a label's edge takes
+/// whole lines, so heading and list markers come along. `None` keeps the text egui copied.
+fn selection_source(content: &str, labels: &[SourceMap], copied: &str) -> Option<String> {
+    let [(first_label, first_offset), (last_label, last_end)] = copied_range(labels, copied)?;
+    let first = &labels[first_label];
+    let last = &labels[last_label];
+
+    let mut source_start = source_offset(first, first_offset, false)?;
+    let mut source_end = source_offset(last, last_end, true)?;
+    if first.text[..first_offset].trim().is_empty() {
+        source_start = content[..source_start].rfind('\n').map_or(0, |i| i + 1);
+    }
+    if last.text[last_end..].trim().is_empty() {
+        // Кусок кода заканчивается переводом строки — за ним уже следующая строка, её не берём
+        if content[..source_end].ends_with('\n') {
+            source_end -= 1;
+        } else {
+            source_end = content[source_end..].find('\n').map_or(content.len(), |i| source_end + i);
+        }
+    }
+    let source = content.get(source_start..source_end)?;
+    Some(source.trim_end_matches('\r').to_string())
+}
+
+/// Readable text of a note for apps that only take plain text: blank lines between blocks, bullets for list items.
+fn plain_text(content: &str) -> String {
+    let mut text = String::new();
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    for event in Parser::new_ext(tags::body(content), markdown_options()) {
+        match event {
+            Event::Start(Tag::Item) => {
+                text.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        text.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => text.push_str("• "),
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                lists.push(start);
+            }
+            Event::End(Tag::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    text.push('\n');
+                }
+            }
+            Event::End(Tag::Item) if !text.ends_with('\n') => text.push('\n'),
+            Event::End(Tag::Paragraph) | Event::End(Tag::Heading(..)) | Event::End(Tag::CodeBlock(_)) | Event::Rule
+                if lists.is_empty() =>
+            {
+                text.push_str(if text.ends_with('\n') { "\n" } else { "\n\n" });
+            }
+            Event::Text(part) | Event::Code(part) => text.push_str(&part),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            _ => {}
+        }
+    }
+    text.trim_end().to_string()
+}
+
+impl MdReader {
+    /// Puts the rendered note on the clipboard as HTML, with plain text for apps that don't take HTML.
+    pub(crate) fn copy_as_rich_text(&mut self) {
+        let Some(note) = &self.selected_file else { return };
+        let folder = note.parent().unwrap_or(&self.root_dir);
+        let index = NoteIndex::new(&links::markdown_files(&self.root_dir));
+        let attachments: Vec<PathBuf> = workspace_files(folder).into_iter()
+            .filter(|file| file.extension().is_none_or(|ext| ext != "md"))
+            .collect();
+        let markdown = wiki_links_to_markdown(&self.root_dir, folder, note, &self.file_content, &index, &attachments);
+        // Письма и чаты обычно светлые, поэтому код подсвечиваем светлой темой независимо от темы приложения
+        let html = export::render_html_body(note, &markdown, &HtmlOptions {
+            dark_mode: false,
+            inline_images: true,
+            rewrite_link: None,
+            rewrite_image: None,
+        });
+        let text = plain_text(&markdown);
+
+        // В X11 содержимое буфера живёт, пока жив его владелец, поэтому держим один экземпляр на всё время работы
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => {
+                    eprintln!("Ошибка доступа к буферу обмена: {}", e);
+                    return;
+                }
+            }
+        }
+        if let Some(Err(e)) = self.clipboard.as_mut().map(|clipboard| clipboard.set_html(html, Some(text))) {
+            eprintln!("Ошибка копирования в буфер обмена: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(text: &str, chunks: &[(Range<usize>, Range<usize>)]) -> SourceMap {
+        SourceMap { text: text.to_string(), chunks: chunks.to_vec() }
+    }
+
+    #[test]
+    fn copies_markdown_of_a_partial_selection() {
+        let content = "Some **bold** text\n";
+        let labels = [label("Some bold text", &[(0..5, 0..5), (5..9, 7..11), (9..14, 13..18)])];
+        assert_eq!(selection_source(content, &labels, "bold tex").as_deref(), Some("bold** tex"));
+        assert_eq!(selection_source(content, &labels, "Some bold text").as_deref(), Some("Some **bold** text"));
+    }
+
+    #[test]
+    fn whole_labels_take_their_markers() {
+        let content = "# Title\n\n- item\n";
+        let labels = [label("Title", &[(0..5, 2..7)]), label("• item", &[(4..8, 11..15)])];
+        assert_eq!(selection_source(content, &labels, "Title\n• item").as_deref(), Some("# Title\n\n- item"));
+    }
+
+    #[test]
+    fn repeated_text_keeps_what_egui_copied() {
+        let labels = [label("same", &[(0..4, 0..4)]), label("same", &[(0..4, 6..10)])];
+        assert_eq!(selection_source("same\n\nsame\n", &labels, "same"), None);
+    }
+}
@@ -0,0 +1,305 @@
+use std::cmp::Reverse;
+
+use eframe::egui::{self, text::LayoutJob, Color32, FontId, Key, KeyboardShortcut, Modifiers, TextFormat};
+
+use crate::fuzzy::fuzzy_match;
+use crate::{Language, MdReader, SidebarTab};
+
+const RESULTS_LIMIT: usize = 20;
+
+/// Every app action. New features add a variant here instead of another top-bar button,
+/// which makes them available in the palette and gives them an optional shortcut.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Command {
+    ShowCommands,
+    QuickOpen,
+    NewNote,
+    NewCategory,
+    ToggleEditMode,
+    RenameNote,
+    CloseTab,
+    FindInNote,
+    SearchNotes,
+    ReplaceInNotes,
+    ShowTrash,
+    ToggleBacklinks,
+    ShowGraph,
+    CheckHealth,
+    ExportNote,
+    CopyRichText,
+    PublishSite,
+    ToggleTheme,
+    ToggleLanguage,
+}
+
+impl Command {
+    pub const ALL: [Command; 19] = [
+        Command::ShowCommands,
+        Command::QuickOpen,
+        Command::NewNote,
+        Command::NewCategory,
+        Command::ToggleEditMode,
+        Command::RenameNote,
+        Command::CloseTab,
+        Command::FindInNote,
+        Command::SearchNotes,
+        Command::ReplaceInNotes,
+        Command::ShowTrash,
+        Command::ToggleBacklinks,
+        Command::ShowGraph,
+        Command::CheckHealth,
+        Command::ExportNote,
+        Command::CopyRichText,
+        Command::PublishSite,
+        Command::ToggleTheme,
+        Command::ToggleLanguage,
+    ];
+
+    pub fn label(self, language: Language) -> &'static str {
+        match (self, language) {
+            (Command::ShowCommands, Language::EN) => "Show all commands",
+            (Command::ShowCommands, Language::RU) => "Показать все команды",
+            (Command::QuickOpen, Language::EN) => "Go to note",
+            (Command::QuickOpen, Language::RU) => "Перейти к заметке",
+            (Command::NewNote, Language::EN) => "Create note",
+            (Command::NewNote, Language::RU) => "Создать заметку",
+            (Command::NewCategory, Language::EN) => "Create category",
+            (Command::NewCategory, Language::RU) => "Создать категорию",
+            (Command::ToggleEditMode, Language::EN) => "Toggle edit/read mode",
+            (Command::ToggleEditMode, Language::RU) => "Переключить режим редактирования/чтения",
+            (Command::RenameNote, Language::EN) => "Rename note",
+            (Command::RenameNote, Language::RU) => "Переименовать заметку",
+            (Command::CloseTab, Language::EN) => "Close tab",
+            (Command::CloseTab, Language::RU) => "Закрыть вкладку",
+            (Command::FindInNote, Language::EN) => "Find in note",
+            (Command::FindInNote, Language::RU) => "Найти в заметке",
+            (Command::SearchNotes, Language::EN) => "Search in all notes",
+            (Command::SearchNotes, Language::RU) => "Поиск по всем заметкам",
+            (Command::ReplaceInNotes, Language::EN) => "Find and replace in notes",
+            (Command::ReplaceInNotes, Language::RU) => "Найти и заменить в заметках",
+            (Command::ShowTrash, Language::EN) => "Show trash",
+            (Command::ShowTrash, Language::RU) => "Показать корзину",
+            (Command::ToggleBacklinks, Language::EN) => "Show/hide backlinks",
+            (Command::ToggleBacklinks, Language::RU) => "Показать/скрыть обратные ссылки",
+            (Command::ShowGraph, Language::EN) => "Show note graph",
+            (Command::ShowGraph, Language::RU) => "Показать граф заметок",
+            (Command::CheckHealth, Language::EN) => "Check notes for broken links",
+            (Command::CheckHealth, Language::RU) => "Проверить заметки на битые ссылки",
+            (Command::ExportNote, Language::EN) => "Export note…",
+            (Command::ExportNote, Language::RU) => "Экспортировать заметку…",
+            (Command::CopyRichText, Language::EN) => "Copy as rich text",
+            (Command::CopyRichText, Language::RU) => "Копировать как форматированный текст",
+            (Command::PublishSite, Language::EN) => "Publish as website…",
+            (Command::PublishSite, Language::RU) => "Опубликовать как сайт…",
+            (Command::ToggleTheme, Language::EN) => "Toggle light/dark theme",
+            (Command::ToggleTheme, Language::RU) => "Переключить светлую/тёмную тему",
+            (Command::ToggleLanguage, Language::EN) => "Switch language to Russian",
+            (Command::ToggleLanguage, Language::RU) => "Переключить язык на английский",
+        }
+    }
+
+    pub fn shortcut(self) -> Option<KeyboardShortcut> {
+        let command_shift = Modifiers::COMMAND | Modifiers::SHIFT;
+        match self {
+            Command::ShowCommands => Some(KeyboardShortcut::new(command_shift, Key::P)),
+            Command::QuickOpen => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::P)),
+            Command::ToggleEditMode => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::E)),
+            Command::RenameNote => Some(KeyboardShortcut::new(Modifiers::NONE, Key::F2)),
+            Command::CloseTab => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::W)),
+            Command::FindInNote => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::F)),
+            Command::SearchNotes => Some(KeyboardShortcut::new(command_shift, Key::F)),
+            Command::ReplaceInNotes => Some(KeyboardShortcut::new(command_shift, Key::H)),
+            Command::ToggleBacklinks => Some(KeyboardShortcut::new(command_shift, Key::B)),
+            Command::ShowGraph => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::G)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CommandPalette {
+    query: String,
+    selected: usize,
+}
+
+impl MdReader {
+    /// Commands that make no sense right now (e.g. renaming with no note open) are hidden and ignored.
+    fn command_enabled(&self, command: Command) -> bool {
+        match command {
+            Command::RenameNote => self.selected_file.is_some() && self.rename_dialog.is_none(),
+            Command::CloseTab | Command::FindInNote | Command::ExportNote | Command::CopyRichText => self.selected_file.is_some(),
+            _ => true,
+        }
+    }
+
+    pub(crate) fn run_command(&mut self, command: Command) {
+        if !self.command_enabled(command) {
+            return;
+        }
+        match command {
+            Command::ShowCommands => {
+                self.quick_open = None;
+                self.command_palette = match self.command_palette {
+                    Some(_) => None,
+                    None => Some(CommandPalette::default()),
+                };
+            }
+            Command::QuickOpen => {
+                self.command_palette = None;
+                self.toggle_quick_open();
+            }
+            Command::NewNote => self.show_new_file_dialog = true,
+            Command::NewCategory => self.show_new_category_dialog = true,
+            Command::ToggleEditMode => self.edit_mode = !self.edit_mode,
+            Command::RenameNote => {
+                if let Some(path) = self.selected_file.clone() {
+                    self.start_rename(&path, false);
+                }
+            }
+            Command::CloseTab => {
+                if let Some(index) = self.tabs.iter().position(|tab| Some(tab) == self.selected_file.as_ref()) {
+                    self.close_tab(index);
+                }
+            }
+            Command::FindInNote => self.open_find_bar(),
+            Command::SearchNotes => {
+                self.sidebar_tab = SidebarTab::Search;
+                self.search.focus_query = true;
+            }
+            Command::ReplaceInNotes => self.replace.open = true,
+            Command::ShowTrash => self.show_trash = true,
+            Command::ToggleBacklinks => self.show_backlinks = !self.show_backlinks,
+            Command::ShowGraph => self.graph_view.open = !self.graph_view.open,
+            Command::CheckHealth => self.open_health_report(),
+            Command::ExportNote => self.open_export_dialog(None),
+            Command::CopyRichText => self.copy_as_rich_text(),
+            Command::PublishSite => self.open_publish_dialog(None),
+            Command::ToggleTheme => self.toggle_theme(),
+            Command::ToggleLanguage => self.toggle_language(),
+        }
+    }
+
+    /// Runs every command whose shortcut was pressed this frame.
+    pub(crate) fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // consume_shortcut не различает лишний Shift, поэтому сначала проверяем сочетания с Shift
+        let mut shortcuts: Vec<(Command, KeyboardShortcut)> = Command::ALL.iter()
+            .filter_map(|command| Some((*command, command.shortcut()?)))
+            .collect();
+        shortcuts.sort_by_key(|(_, shortcut)| Reverse(shortcut.modifiers.shift));
+
+        for (command, shortcut) in shortcuts {
+            if ctx.input_mut(|i| i.consume_shortcut(&shortcut)) {
+                self.run_command(command);
+            }
+        }
+    }
+
+    pub(crate) fn render_command_palette(&mut self, ctx: &egui::Context) {
+        let Some(mut state) = self.command_palette.take() else { return };
+        let hint = match self.current_language {
+            Language::EN => "Type a command…",
+            Language::RU => "Введите команду…",
+        };
+
+        let mut candidates: Vec<(Command, i32, Vec<usize>)> = Command::ALL.iter()
+            .filter(|command| **command != Command::ShowCommands && self.command_enabled(**command))
+            .filter_map(|command| {
+                let (score, matches) = fuzzy_match(&state.query, command.label(self.current_language))?;
+                Some((*command, score, matches))
+            })
+            .collect();
+        // Без запроса оставляем порядок реестра
+        if !state.query.is_empty() {
+            candidates.sort_by_key(|(_, score, _)| Reverse(*score));
+        }
+        candidates.truncate(RESULTS_LIMIT);
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| (
+            i.consume_key(Modifiers::NONE, Key::ArrowUp),
+            i.consume_key(Modifiers::NONE, Key::ArrowDown),
+            i.consume_key(Modifiers::NONE, Key::Enter),
+            i.consume_key(Modifiers::NONE, Key::Escape),
+        ));
+        if escape {
+            return;
+        }
+        if down && state.selected + 1 < candidates.len() {
+            state.selected += 1;
+        }
+        if up {
+            state.selected = state.selected.saturating_sub(1);
+        }
+        state.selected = state.selected.min(candidates.len().saturating_sub(1));
+        if enter {
+            if let Some((command, _, _)) = candidates.get(state.selected) {
+                self.run_command(*command);
+                return;
+            }
+        }
+
+        let mut to_run = None;
+        egui::Area::new("command_palette")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(520.0);
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut state.query)
+                            .hint_text(hint)
+                            .desired_width(f32::INFINITY)
+                    );
+                    response.request_focus();
+                    if response.changed() {
+                        state.selected = 0;
+                    }
+
+                    let format = TextFormat {
+                        font_id: FontId::proportional(16.0),
+                        color: ui.visuals().text_color(),
+                        ..Default::default()
+                    };
+                    let matched_format = TextFormat {
+                        color: ui.visuals().selection.bg_fill,
+                        ..format.clone()
+                    };
+
+                    for (index, (command, _, matches)) in candidates.iter().enumerate() {
+                        let mut job = LayoutJob::default();
+                        for (char_index, c) in command.label(self.current_language).chars().enumerate() {
+                            let format = if matches.contains(&char_index) { &matched_format } else { &format };
+                            job.append(c.encode_utf8(&mut [0; 4]), 0.0, format.clone());
+                        }
+
+                        let response = ui.horizontal(|ui| {
+                            let response = ui.selectable_label(index == state.selected, job);
+                            if let Some(shortcut) = command.shortcut() {
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    ui.weak(ctx.format_shortcut(&shortcut));
+                                });
+                            }
+                            response
+                        }).inner;
+                        if index == state.selected && (up || down) {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            to_run = Some(*command);
+                        }
+                    }
+                    if candidates.is_empty() {
+                        let nothing_found = match self.current_language {
+                            Language::EN => "No matching commands",
+                            Language::RU => "Подходящих команд нет",
+                        };
+                        ui.colored_label(Color32::GRAY, nothing_found);
+                    }
+                });
+            });
+
+        match to_run {
+            Some(command) => self.run_command(command),
+            None => self.command_palette = Some(state),
+        }
+    }
+}
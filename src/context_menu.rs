@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use eframe::egui;
+
+use crate::{links, Category, FileEntry, Language, MdReader, SidebarAction};
+
+impl MdReader {
+    pub(crate) fn file_context_menu(&mut self, ui: &mut egui::Ui, file: &FileEntry) {
+        let labels = match self.current_language {
+            Language::EN => ["Open", "Open in new tab", "Rename", "Duplicate", "Delete", "Copy path", "Copy markdown link", "Reveal in file manager"],
+            Language::RU => ["Открыть", "Открыть в новой вкладке", "Переименовать", "Дублировать", "Удалить", "Копировать путь", "Копировать ссылку markdown", "Показать в файловом менеджере"],
+        };
+        let [open, open_new_tab, rename, duplicate, delete, copy_path, copy_link, reveal] = labels;
+
+        if ui.button(open).clicked() {
+            self.load_file(&file.path);
+            ui.close_menu();
+        }
+        if ui.button(open_new_tab).clicked() {
+            self.open_in_new_tab(&file.path);
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button(rename).clicked() {
+            self.start_rename(&file.path, false);
+            ui.close_menu();
+        }
+        if ui.button(duplicate).clicked() {
+            self.pending_action = Some(SidebarAction::Duplicate(file.path.clone()));
+            ui.close_menu();
+        }
+        if ui.button(delete).clicked() {
+            self.pending_action = Some(SidebarAction::Delete(file.path.clone()));
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button(copy_path).clicked() {
+            ui.ctx().output_mut(|o| o.copied_text = file.path.to_string_lossy().to_string());
+            ui.close_menu();
+        }
+        if ui.button(copy_link).clicked() {
+            let link = self.markdown_link_to(file);
+            ui.ctx().output_mut(|o| o.copied_text = link);
+            ui.close_menu();
+        }
+        if ui.button(reveal).clicked() {
+            reveal_in_file_manager(&file.path);
+            ui.close_menu();
+        }
+    }
+
+    pub(crate) fn category_context_menu(&mut self, ui: &mut egui::Ui, category: &mut Category) {
+        let labels = match self.current_language {
+            Language::EN => ["New note here", "New subcategory", "Rename", "Delete", "Collapse all", "Export as book…", "Publish as website…"],
+            Language::RU => ["Новая заметка здесь", "Новая подкатегория", "Переименовать", "Удалить", "Свернуть все", "Экспортировать книгой…", "Опубликовать как сайт…"],
+        };
+        let [new_note, new_subcategory, rename, delete, collapse_all, export_book, publish] = labels;
+
+        if ui.button(new_note).clicked() {
+            self.current_dir = category.path.clone();
+            self.show_new_file_dialog = true;
+            ui.close_menu();
+        }
+        if ui.button(new_subcategory).clicked() {
+            self.current_dir = category.path.clone();
+            self.show_new_category_dialog = true;
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button(rename).clicked() {
+            self.start_rename(&category.path, true);
+            ui.close_menu();
+        }
+        if ui.button(delete).clicked() {
+            self.pending_action = Some(SidebarAction::Delete(category.path.clone()));
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button(collapse_all).clicked() {
+            collapse_recursively(category);
+            ui.close_menu();
+        }
+        if ui.button(export_book).clicked() {
+            self.open_export_dialog(Some(category.path.clone()));
+            ui.close_menu();
+        }
+        if ui.button(publish).clicked() {
+            self.open_publish_dialog(Some(category.path.clone()));
+            ui.close_menu();
+        }
+    }
+
+    /// `[Title](path)` relative to the open note, or to the workspace root when nothing is open.
+    fn markdown_link_to(&self, file: &FileEntry) -> String {
+        let base = self.selected_file.as_ref()
+            .and_then(|selected| selected.parent())
+            .unwrap_or(&self.root_dir);
+        let target = links::relative_path(base, &file.path).replace(' ', "%20");
+        format!("[{}]({})", file.name.trim(), target)
+    }
+
+    /// Copies a note next to the original as "name (copy).md" and opens the copy.
+    pub(crate) fn duplicate_file(&mut self, path: &Path) {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let mut copy = path.with_file_name(format!("{} (copy).md", stem));
+        let mut counter = 2;
+        while copy.exists() {
+            copy = path.with_file_name(format!("{} (copy {}).md", stem, counter));
+            counter += 1;
+        }
+
+        match fs::copy(path, &copy) {
+            Ok(_) => {
+                self.refresh_tree();
+                self.load_file(&copy);
+            }
+            Err(e) => eprintln!("Ошибка копирования файла: {}", e),
+        }
+    }
+}
+
+fn collapse_recursively(category: &mut Category) {
+    category.is_expanded = false;
+    for subcategory in &mut category.subcategories {
+        collapse_recursively(subcategory);
+    }
+}
+
+fn reveal_in_file_manager(path: &Path) {
+    let result = if cfg!(target_os = "windows") {
+        Command::new("explorer").arg(format!("/select,{}", path.display())).spawn()
+    } else if cfg!(target_os = "macos") {
+        Command::new("open").arg("-R").arg(path).spawn()
+    } else {
+        // xdg-open не умеет выделять файл, поэтому открываем папку
+        let dir: PathBuf = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Command::new("xdg-open").arg(dir).spawn()
+    };
+    if let Err(e) = result {
+        eprintln!("Не удалось открыть файловый менеджер: {}", e);
+    }
+}
@@ -0,0 +1,272 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use pulldown_cmark::{Event, Parser, Tag};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::export::{self, HtmlOptions};
+use crate::health::{self, workspace_files};
+use crate::links::{self, NoteIndex};
+use crate::publish::{escaped, wiki_links_to_markdown};
+use crate::query::{civil_from_days, front_matter_fields, yaml_fields};
+use crate::tags;
+
+// Метаданные книги для категории целиком; скрытый файл, чтобы он не попадал в дерево заметок
+const METADATA_FILE_NAME: &str = ".book.yml";
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#;
+
+// Читалки подставляют свои шрифты и цвета, поэтому задаём только самое необходимое
+const STYLESHEET: &str = r#"body { line-height: 1.5; }
+h1, h2, h3, h4, h5, h6 { line-height: 1.25; page-break-after: avoid; }
+code { font-family: monospace; font-size: 0.9em; }
+pre { white-space: pre-wrap; font-size: 0.85em; padding: 0.5em; }
+blockquote { border-left: 3px solid #888; margin-left: 0; padding-left: 1em; font-style: italic; }
+img { max-width: 100%; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #888; padding: 0.2em 0.5em; }
+"#;
+
+/// Title, author and language of a book.
+pub struct BookMetadata {
+    pub title: String,
+    pub author: Option<String>,
+    pub language: String,
+}
+
+impl BookMetadata {
+    /// Fields from the category's metadata file win over the first note's front matter;
+    /// `title` and `language` are used when neither has them.
+    pub fn load(source: &Path, notes: &[(PathBuf, String)], title: &str, language: &str) -> Self {
+        let mut fields = fs::read_to_string(source.join(METADATA_FILE_NAME))
+            .map(|text| yaml_fields(&text))
+            .unwrap_or_default();
+        if let Some((_, content)) = notes.first() {
+            fields.extend(front_matter_fields(content));
+        }
+        let field = |keys: &[&str]| {
+            fields.iter()
+                .find(|(key, value)| keys.contains(&key.as_str()) && !value.is_empty())
+                .map(|(_, value)| value.clone())
+        };
+        Self {
+            title: field(&["title"]).unwrap_or_else(|| title.to_string()),
+            author: field(&["author", "creator"]),
+            language: field(&["language", "lang"]).unwrap_or_else(|| language.to_string()),
+        }
+    }
+}
+
+/// A stable `urn:uuid:` for the book, so e-readers treat a re-export as the same book.
+fn book_identifier(source: &Path, title: &str) -> String {
+    let source = source.to_string_lossy();
+    let hash = |salt: u8| crate::stable_hash([salt].into_iter().chain(source.bytes()).chain([0]).chain(title.bytes()));
+    let hex = format!("{:016x}{:016x}", hash(0), hash(1));
+    format!("urn:uuid:{}-{}-4{}-a{}-{}", &hex[..8], &hex[8..12], &hex[13..16], &hex[17..20], &hex[20..32])
+}
+
+/// The `dcterms:modified` timestamp EPUB requires, in UTC.
+fn modified_timestamp() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let time = seconds % 86_400;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3600, time % 3600 / 60, time % 60)
+}
+
+fn chapter_file(index: usize) -> String {
+    format!("chapter-{:03}.xhtml", index + 1)
+}
+
+fn heading_levels(content: &str) -> Vec<usize> {
+    Parser::new(tags::body(content))
+        .filter_map(|event| match event {
+            Event::Start(Tag::Heading(level, ..)) => Some(level as usize),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A link to another note of the book as a link to its chapter; other links stay as they are.
+fn chapter_link(root: &Path, note: &Path, destination: &str, chapters: &HashMap<PathBuf, String>) -> Option<String> {
+    if links::is_external(destination) || destination.starts_with('#') {
+        return None;
+    }
+    let (target, anchor) = match destination.split_once('#') {
+        Some((target, anchor)) => (target, format!("#{}", anchor)),
+        None => (destination, String::new()),
+    };
+    let target = links::percent_decode(target);
+    let base = if target.starts_with('/') { root.to_path_buf() } else { note.parent()?.to_path_buf() };
+    let resolved = links::normalize(&base.join(target.trim_start_matches('/')));
+    chapters.get(&resolved).map(|chapter| format!("{}{}", chapter, anchor))
+}
+
+/// The navigation document: every heading of every chapter as a nested list.
+fn nav_document(entries: &[(usize, usize, String, String)], metadata: &BookMetadata, contents_title: &str) -> String {
+    let mut list = String::from("<ol>\n");
+    let mut levels: Vec<usize> = Vec::new();
+    let mut previous_depth = 0;
+    for (i, (chapter, level, title, target)) in entries.iter().enumerate() {
+        // Каждая глава начинается с верхнего уровня, даже если в ней только ## заголовки
+        if i > 0 && entries[i - 1].0 != *chapter {
+            levels.clear();
+        }
+        // Глубина считается от ближайшего заголовка уровнем выше, поэтому пропуск уровня не ломает вложенность
+        while levels.last().is_some_and(|&last| last >= *level) {
+            levels.pop();
+        }
+        let depth = levels.len();
+        levels.push(*level);
+        if i > 0 {
+            if depth > previous_depth {
+                list.push_str("\n<ol>\n");
+            } else {
+                list.push_str("</li>\n");
+                list.push_str(&"</ol>\n</li>\n".repeat(previous_depth - depth));
+            }
+        }
+        list.push_str(&format!("<li><a href=\"{}\">{}</a>", escaped(target), escaped(title)));
+        previous_depth = depth;
+    }
+    if !entries.is_empty() {
+        list.push_str("</li>\n");
+        list.push_str(&"</ol>\n</li>\n".repeat(previous_depth));
+    }
+    list.push_str("</ol>\n");
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"{lang}\" lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\"/>\n<title>{title}</title>\n</head>\n<body>\n<nav epub:type=\"toc\" id=\"toc\">\n<h1>{contents}</h1>\n{list}</nav>\n</body>\n</html>\n",
+        lang = escaped(&metadata.language),
+        title = escaped(&metadata.title),
+        contents = escaped(contents_title),
+        list = list,
+    )
+}
+
+fn chapter_document(title: &str, language: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"{lang}\" lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\"/>\n<title>{title}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        lang = escaped(language),
+        title = escaped(title),
+        body = body,
+    )
+}
+
+fn package_document(metadata: &BookMetadata, identifier: &str, chapters: usize, images: &[(PathBuf, String)]) -> String {
+    let mut manifest = String::from("<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n");
+    let mut spine = String::new();
+    for i in 0..chapters {
+        manifest.push_str(&format!("<item id=\"chapter-{0}\" href=\"{1}\" media-type=\"application/xhtml+xml\"/>\n", i + 1, chapter_file(i)));
+        spine.push_str(&format!("<itemref idref=\"chapter-{}\"/>\n", i + 1));
+    }
+    for (i, (path, name)) in images.iter().enumerate() {
+        let mime = export::mime_type(path).unwrap_or("application/octet-stream");
+        manifest.push_str(&format!("<item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>\n", i + 1, escaped(name), mime));
+    }
+    let author = metadata.author.as_ref()
+        .map(|author| format!("<dc:creator>{}</dc:creator>\n", escaped(author)))
+        .unwrap_or_default();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{lang}\">\n<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n<dc:identifier id=\"book-id\">{identifier}</dc:identifier>\n<dc:title>{title}</dc:title>\n{author}<dc:language>{lang}</dc:language>\n<meta property=\"dcterms:modified\">{modified}</meta>\n</metadata>\n<manifest>\n{manifest}</manifest>\n<spine>\n{spine}</spine>\n</package>\n",
+        lang = escaped(&metadata.language),
+        identifier = identifier,
+        title = escaped(&metadata.title),
+        author = author,
+        modified = modified_timestamp(),
+        manifest = manifest,
+        spine = spine,
+    )
+}
+
+/// Packages the notes as an EPUB3 book, one chapter per note in the given order.
+/// `source` is the exported category (or the note's folder) and bounds which wiki links stay links.
+pub fn notes_to_epub(root: &Path, source: &Path, notes: &[(PathBuf, String)], metadata: &BookMetadata, contents_title: &str) -> Result<Vec<u8>, String> {
+    let chapters: HashMap<PathBuf, String> = notes.iter()
+        .enumerate()
+        .map(|(i, (path, _))| (path.clone(), chapter_file(i)))
+        .collect();
+    let index = NoteIndex::new(&links::markdown_files(root));
+    let attachments: Vec<PathBuf> = workspace_files(source).into_iter()
+        .filter(|file| file.extension().is_none_or(|ext| ext != "md"))
+        .collect();
+    // Картинки собираем по ходу рендера: путь на диске → имя внутри пакета
+    let images: RefCell<Vec<(PathBuf, String)>> = RefCell::new(Vec::new());
+
+    let mut documents = Vec::new();
+    let mut nav_entries = Vec::new();
+    for (i, (note, content)) in notes.iter().enumerate() {
+        let markdown = wiki_links_to_markdown(root, source, note, content, &index, &attachments);
+        let rewrite_link = |destination: &str| chapter_link(root, note, destination, &chapters);
+        let rewrite_image = |destination: &str| {
+            if destination.is_empty() || links::is_external(destination) || destination.starts_with("data:") {
+                return None;
+            }
+            let path = links::normalize(&note.parent()?.join(links::percent_decode(destination)));
+            let extension = path.extension()?.to_string_lossy().to_lowercase();
+            export::mime_type(&path)?;
+            if !path.is_file() {
+                return None;
+            }
+            let mut images = images.borrow_mut();
+            if let Some((_, name)) = images.iter().find(|(known, _)| *known == path) {
+                return Some(name.clone());
+            }
+            let name = format!("images/image-{}.{}", images.len() + 1, extension);
+            images.push((path, name.clone()));
+            Some(name)
+        };
+        let body = export::render_html_body(note, &markdown, &HtmlOptions {
+            dark_mode: false,
+            inline_images: false,
+            rewrite_link: Some(&rewrite_link),
+            rewrite_image: Some(&rewrite_image),
+        });
+
+        let title = export::note_title(note, content);
+        let chapter = chapter_file(i);
+        let headings = health::headings(content);
+        if headings.is_empty() {
+            nav_entries.push((i, 1, title.clone(), chapter.clone()));
+        }
+        for (level, (text, slug)) in heading_levels(content).into_iter().zip(headings) {
+            nav_entries.push((i, level, text.trim().to_string(), format!("{}#{}", chapter, slug)));
+        }
+        documents.push((chapter, chapter_document(&title, &metadata.language, &body)));
+    }
+
+    let images = images.into_inner();
+    let identifier = book_identifier(source, &metadata.title);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut add = |name: &str, bytes: &[u8], options: SimpleFileOptions| -> Result<(), String> {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(bytes).map_err(|e| e.to_string())
+    };
+    // По спецификации mimetype идёт первым и без сжатия
+    add("mimetype", b"application/epub+zip", stored)?;
+    add("META-INF/container.xml", CONTAINER.as_bytes(), deflated)?;
+    add("OEBPS/content.opf", package_document(metadata, &identifier, documents.len(), &images).as_bytes(), deflated)?;
+    add("OEBPS/nav.xhtml", nav_document(&nav_entries, metadata, contents_title).as_bytes(), deflated)?;
+    add("OEBPS/style.css", STYLESHEET.as_bytes(), deflated)?;
+    for (name, document) in &documents {
+        add(&format!("OEBPS/{}", name), document.as_bytes(), deflated)?;
+    }
+    for (path, name) in &images {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        add(&format!("OEBPS/{}", name), &bytes, stored)?;
+    }
+    zip.finish().map(Cursor::into_inner).map_err(|e| e.to_string())
+}
@@ -0,0 +1,479 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use base64::Engine;
+use eframe::egui::{self, Color32, RichText};
+use pulldown_cmark::escape::escape_html;
+use pulldown_cmark::{html, CodeBlockKind, Event, Parser, Tag};
+use syntect::highlighting::ThemeSet;
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use crate::epub::{self, BookMetadata};
+use crate::office;
+use crate::pdf::{self, PageSize, PdfOptions};
+use crate::{collect_category_paths, health, links, markdown_options, tags, workspace, Category, Language, MdReader};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+    Epub,
+    Docx,
+    Odt,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 5] = [ExportFormat::Html, ExportFormat::Pdf, ExportFormat::Epub, ExportFormat::Docx, ExportFormat::Odt];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Html => "html",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Epub => "epub",
+            ExportFormat::Docx => "docx",
+            ExportFormat::Odt => "odt",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Html => "HTML",
+            ExportFormat::Pdf => "PDF",
+            ExportFormat::Epub => "EPUB",
+            ExportFormat::Docx => "Word (DOCX)",
+            ExportFormat::Odt => "OpenDocument (ODT)",
+        }
+    }
+
+    /// Whether a whole category can be exported as one book.
+    pub fn supports_books(self) -> bool {
+        matches!(self, ExportFormat::Pdf | ExportFormat::Epub)
+    }
+}
+
+/// Everything an export needs besides the notes, so it runs the same from the dialog and the command line.
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub pdf: PdfOptions,
+    pub dark_mode: bool,
+    pub language: Language,
+}
+
+pub struct ExportDialog {
+    format: ExportFormat,
+    /// Category exported as a book; `None` exports the open note.
+    book: Option<PathBuf>,
+    pdf: PdfOptions,
+    path: String,
+    error: Option<String>,
+    saved: Option<PathBuf>,
+}
+
+/// Colors of the app theme, so exported notes look like they do on screen.
+pub struct Palette {
+    pub background: Color32,
+    pub surface: Color32,
+    pub text: Color32,
+    pub muted: Color32,
+    pub accent: Color32,
+    pub border: Color32,
+}
+
+impl Palette {
+    pub fn new(dark_mode: bool) -> Self {
+        if dark_mode {
+            Self {
+                background: Color32::from_rgb(17, 23, 43),
+                surface: Color32::from_rgb(27, 33, 56),
+                text: Color32::from_rgb(220, 220, 240),
+                muted: Color32::from_rgb(150, 150, 170),
+                accent: Color32::from_rgb(145, 85, 253),
+                border: Color32::from_rgb(47, 53, 76),
+            }
+        } else {
+            Self {
+                background: Color32::from_rgb(250, 250, 255),
+                surface: Color32::from_rgb(238, 238, 245),
+                text: Color32::from_rgb(33, 33, 43),
+                muted: Color32::from_rgb(100, 100, 115),
+                accent: Color32::from_rgb(71, 130, 218),
+                border: Color32::from_rgb(224, 224, 234),
+            }
+        }
+    }
+}
+
+fn css_color(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+pub(crate) fn syntax_set() -> &'static SyntaxSet {
+    static SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+pub(crate) fn theme_set() -> &'static ThemeSet {
+    static SET: OnceLock<ThemeSet> = OnceLock::new();
+    SET.get_or_init(ThemeSet::load_defaults)
+}
+
+/// A code block as a `<pre>` with inline colors; unknown languages stay plain.
+pub fn highlight_code(code: &str, language: &str, dark_mode: bool) -> String {
+    let syntaxes = syntax_set();
+    let syntax = syntaxes.find_syntax_by_token(language)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let theme = &theme_set().themes[if dark_mode { "base16-ocean.dark" } else { "InspiredGitHub" }];
+    highlighted_html_for_string(code, syntaxes, syntax, theme).unwrap_or_else(|_| {
+        let mut escaped = String::new();
+        let _ = escape_html(&mut escaped, code);
+        format!("<pre><code>{}</code></pre>", escaped)
+    })
+}
+
+pub(crate) fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    Some(match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        _ => return None,
+    })
+}
+
+/// A local image referenced from `note` as a `data:` URI; remote and missing images give `None`.
+pub fn image_data_uri(note: &Path, destination: &str) -> Option<String> {
+    if destination.is_empty() || links::is_external(destination) || destination.starts_with("data:") {
+        return None;
+    }
+    let path = links::normalize(&note.parent()?.join(links::percent_decode(destination)));
+    let mime = mime_type(&path)?;
+    let bytes = fs::read(&path).ok()?;
+    Some(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes)))
+}
+
+/// The note's first heading, or its file name when it has none.
+pub fn note_title(path: &Path, content: &str) -> String {
+    health::headings(content)
+        .into_iter()
+        .next()
+        .map(|(text, _)| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().to_string())
+}
+
+/// New destination for a link, e.g. a note's page on a published site; `None` keeps the link.
+pub type LinkRewrite<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// How a note is turned into HTML.
+pub struct HtmlOptions<'a> {
+    pub dark_mode: bool,
+    /// Embed local images as data URIs instead of linking to the files.
+    pub inline_images: bool,
+    pub rewrite_link: Option<LinkRewrite<'a>>,
+    /// New source for images that are not inlined, e.g. their place inside a package.
+    pub rewrite_image: Option<LinkRewrite<'a>>,
+}
+
+/// The note as an HTML fragment: front matter dropped, headings with anchors and code blocks highlighted.
+pub fn render_html_body(path: &Path, content: &str, options: &HtmlOptions) -> String {
+    let body_start = tags::front_matter_range(content).map_or(0, |range| range.end);
+    let slugs: Vec<String> = health::headings(content).into_iter().map(|(_, slug)| slug).collect();
+    let mut slugs = slugs.iter();
+    let mut code: Option<(String, String)> = None;
+    let mut events = Vec::new();
+
+    for (event, range) in Parser::new_ext(content, markdown_options()).into_offset_iter() {
+        if range.start < body_start {
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Heading(level, None, classes)) => {
+                let id = slugs.next().map(String::as_str);
+                events.push(Event::Start(Tag::Heading(level, id, classes)));
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((language, String::new()));
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, buffer)) = &mut code {
+                    buffer.push_str(&text);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some((language, buffer)) = code.take() {
+                    events.push(Event::Html(highlight_code(&buffer, &language, options.dark_mode).into()));
+                }
+            }
+            Event::Start(Tag::Image(link_type, destination, title)) => {
+                let replaced = if options.inline_images {
+                    image_data_uri(path, &destination)
+                } else {
+                    options.rewrite_image.and_then(|rewrite| rewrite(&destination))
+                };
+                let destination = replaced.map_or(destination, Into::into);
+                events.push(Event::Start(Tag::Image(link_type, destination, title)));
+            }
+            Event::Start(Tag::Link(link_type, destination, title)) => {
+                let destination = options.rewrite_link
+                    .and_then(|rewrite| rewrite(&destination))
+                    .map_or(destination, Into::into);
+                events.push(Event::Start(Tag::Link(link_type, destination, title)));
+            }
+            event => events.push(event),
+        }
+    }
+
+    let mut body = String::new();
+    html::push_html(&mut body, events.into_iter());
+    body
+}
+
+pub fn stylesheet(palette: &Palette) -> String {
+    format!(
+        r#"body {{ background: {background}; color: {text}; font-family: -apple-system, "Segoe UI", Roboto, sans-serif; line-height: 1.6; max-width: 800px; margin: 2em auto; padding: 0 1em; }}
+a {{ color: {accent}; }}
+h1, h2, h3, h4, h5, h6 {{ line-height: 1.25; margin-top: 1.5em; }}
+code {{ font-family: "JetBrains Mono", Consolas, monospace; background: {surface}; padding: 0.1em 0.3em; border-radius: 4px; }}
+pre {{ padding: 1em; border-radius: 8px; overflow-x: auto; }}
+pre code {{ background: none; padding: 0; }}
+blockquote {{ border-left: 4px solid {accent}; margin-left: 0; padding-left: 1em; color: {muted}; }}
+img {{ max-width: 100%; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid {border}; padding: 0.3em 0.6em; }}
+hr {{ border: none; border-top: 1px solid {border}; }}
+"#,
+        background = css_color(palette.background),
+        surface = css_color(palette.surface),
+        text = css_color(palette.text),
+        muted = css_color(palette.muted),
+        accent = css_color(palette.accent),
+        border = css_color(palette.border),
+    )
+}
+
+/// A self-contained HTML page for the note, styled with the light or dark palette.
+pub fn note_to_html(path: &Path, content: &str, dark_mode: bool) -> String {
+    let mut title = String::new();
+    let _ = escape_html(&mut title, &note_title(path, content));
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        title,
+        stylesheet(&Palette::new(dark_mode)),
+        render_html_body(path, content, &HtmlOptions { dark_mode, inline_images: true, rewrite_link: None, rewrite_image: None }),
+    )
+}
+
+// Notes of a category in sidebar order, subcategories after the category's own notes
+fn book_notes(category: &Category, notes: &mut Vec<PathBuf>) {
+    notes.extend(category.files.iter().map(|file| file.path.clone()));
+    for subcategory in &category.subcategories {
+        book_notes(subcategory, notes);
+    }
+}
+
+/// Title and notes of a category exported as a book.
+pub fn book_contents(categories: &[Category], book: &Path) -> Result<(String, Vec<(PathBuf, String)>), String> {
+    let category = workspace::find_category(categories, book).ok_or_else(|| book.display().to_string())?;
+    let mut paths = Vec::new();
+    book_notes(category, &mut paths);
+    let notes = paths.into_iter()
+        .map(|path| fs::read_to_string(&path).map(|content| (path, content)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok((category.name.clone(), notes))
+}
+
+/// Language tag for word processors' spell checking.
+fn document_language(language: Language) -> &'static str {
+    match language {
+        Language::EN => "en-US",
+        Language::RU => "ru-RU",
+    }
+}
+
+/// The exported file. Single-note formats take the first note; `book` is the category the notes come from, if any.
+pub fn export_notes(root: &Path, book: Option<&Path>, title: &str, notes: &[(PathBuf, String)], options: &ExportOptions) -> Result<Vec<u8>, String> {
+    let Some((first, first_content)) = notes.first() else {
+        return Err(match options.language {
+            Language::EN => "There are no notes to export".to_string(),
+            Language::RU => "Нет заметок для экспорта".to_string(),
+        });
+    };
+    Ok(match options.format {
+        ExportFormat::Html => note_to_html(first, first_content, options.dark_mode).into_bytes(),
+        ExportFormat::Pdf => pdf::notes_to_pdf(notes, title, &options.pdf)?,
+        ExportFormat::Epub => {
+            let source = match book {
+                Some(book) => book.to_path_buf(),
+                None => first.parent().unwrap_or(root).to_path_buf(),
+            };
+            let (language, contents_title) = match options.language {
+                Language::EN => ("en", "Contents"),
+                Language::RU => ("ru", "Содержание"),
+            };
+            let metadata = BookMetadata::load(&source, notes, title, language);
+            epub::notes_to_epub(root, &source, notes, &metadata, contents_title)?
+        }
+        ExportFormat::Docx => office::note_to_docx(root, first, first_content, document_language(options.language))?,
+        ExportFormat::Odt => office::note_to_odt(root, first, first_content, document_language(options.language))?,
+    })
+}
+
+impl MdReader {
+    pub(crate) fn open_export_dialog(&mut self, book: Option<PathBuf>) {
+        let (format, path) = match (&book, &self.selected_file) {
+            (Some(category), _) => (ExportFormat::Pdf, category.with_extension(ExportFormat::Pdf.extension())),
+            (None, Some(note)) => (ExportFormat::Html, note.with_extension(ExportFormat::Html.extension())),
+            (None, None) => return,
+        };
+        self.export = Some(ExportDialog {
+            format,
+            book,
+            pdf: PdfOptions::default(),
+            path: path.to_string_lossy().to_string(),
+            error: None,
+            saved: None,
+        });
+    }
+
+    /// Title and notes to export: the open note, or a category's notes as a book.
+    fn export_contents(&self, book: Option<&Path>) -> Result<(String, Vec<(PathBuf, String)>), String> {
+        match (book, &self.selected_file) {
+            (Some(book), _) => book_contents(&self.categories, book),
+            (None, Some(note)) => Ok((note_title(note, &self.file_content), vec![(note.clone(), self.file_content.clone())])),
+            (None, None) => Ok((String::new(), Vec::new())),
+        }
+    }
+
+    fn export(&self, dialog: &ExportDialog, target: &Path) -> Result<(), String> {
+        let (title, notes) = self.export_contents(dialog.book.as_deref())?;
+        let options = ExportOptions {
+            format: dialog.format,
+            pdf: dialog.pdf,
+            dark_mode: self.dark_mode,
+            language: self.current_language,
+        };
+        let bytes = export_notes(&self.root_dir, dialog.book.as_deref(), &title, &notes, &options)?;
+        fs::write(target, bytes).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn render_export_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.export.take() else { return };
+        let (window_title, format_label, path_label, export_text) = match self.current_language {
+            Language::EN => ("Export note", "Format:", "Save to:", "Export"),
+            Language::RU => ("Экспорт заметки", "Формат:", "Сохранить в:", "Экспортировать"),
+        };
+        let (contents_label, this_note, page_label, margin_label) = match self.current_language {
+            Language::EN => ("Contents:", "This note", "Page size:", "Margins, mm:"),
+            Language::RU => ("Содержимое:", "Эта заметка", "Размер страницы:", "Поля, мм:"),
+        };
+        let mut categories = Vec::new();
+        collect_category_paths(&self.categories, &mut categories);
+        let category_name = |path: &Path| path.strip_prefix(&self.root_dir).unwrap_or(path).to_string_lossy().to_string();
+
+        let mut open = true;
+        let mut export = false;
+        egui::Window::new(window_title)
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format_label);
+                    let previous = dialog.format;
+                    egui::ComboBox::from_id_source("export_format")
+                        .selected_text(dialog.format.label())
+                        .show_ui(ui, |ui| {
+                            for format in ExportFormat::ALL {
+                                if dialog.book.is_some() && !format.supports_books() {
+                                    continue;
+                                }
+                                ui.selectable_value(&mut dialog.format, format, format.label());
+                            }
+                        });
+                    // Вместе с форматом меняем и расширение файла
+                    if dialog.format != previous {
+                        dialog.path = Path::new(&dialog.path).with_extension(dialog.format.extension()).to_string_lossy().to_string();
+                        dialog.saved = None;
+                    }
+                });
+                if dialog.format.supports_books() {
+                    ui.horizontal(|ui| {
+                        ui.label(contents_label);
+                        let previous = dialog.book.clone();
+                        let selected = dialog.book.as_deref().map_or(this_note.to_string(), category_name);
+                        egui::ComboBox::from_id_source("export_contents")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                if self.selected_file.is_some() {
+                                    ui.selectable_value(&mut dialog.book, None, this_note);
+                                }
+                                for category in &categories {
+                                    ui.selectable_value(&mut dialog.book, Some(category.clone()), format!("📁 {}", category_name(category)));
+                                }
+                            });
+                        // Путь по умолчанию следует за выбранным содержимым
+                        if dialog.book != previous {
+                            let source = dialog.book.as_ref().or(self.selected_file.as_ref());
+                            if let Some(source) = source {
+                                dialog.path = source.with_extension(dialog.format.extension()).to_string_lossy().to_string();
+                            }
+                            dialog.saved = None;
+                        }
+                    });
+                }
+                if dialog.format == ExportFormat::Pdf {
+                    ui.horizontal(|ui| {
+                        ui.label(page_label);
+                        egui::ComboBox::from_id_source("export_page_size")
+                            .selected_text(dialog.pdf.page_size.label())
+                            .show_ui(ui, |ui| {
+                                for size in PageSize::ALL {
+                                    ui.selectable_value(&mut dialog.pdf.page_size, size, size.label());
+                                }
+                            });
+                        ui.label(margin_label);
+                        ui.add(egui::DragValue::new(&mut dialog.pdf.margin).clamp_range(0.0..=60.0).speed(0.5));
+                    });
+                }
+                ui.label(path_label);
+                let response = ui.add(egui::TextEdit::singleline(&mut dialog.path).desired_width(f32::INFINITY));
+                if response.changed() {
+                    dialog.error = None;
+                    dialog.saved = None;
+                }
+                export = ui.button(export_text).clicked()
+                    || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)));
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(Color32::from_rgb(235, 87, 87), error);
+                }
+                if let Some(saved) = &dialog.saved {
+                    let text = match self.current_language {
+                        Language::EN => format!("Saved to {}", saved.display()),
+                        Language::RU => format!("Сохранено в {}", saved.display()),
+                    };
+                    ui.label(RichText::new(text).weak());
+                }
+            });
+
+        if export {
+            let target = PathBuf::from(dialog.path.trim());
+            match self.export(&dialog, &target) {
+                Ok(()) => dialog.saved = Some(target),
+                Err(e) => dialog.error = Some(e),
+            }
+        }
+        if open {
+            self.export = Some(dialog);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use eframe::egui::{self, Color32, RichText};
+use regex::{NoExpand, Regex};
+
+use crate::search::{self, Highlight, SearchOptions};
+use crate::{Language, MdReader};
+
+/// Ctrl+F bar over the open note. Its matches live in `MdReader::highlight`,
+/// so read mode and the editor paint them the same way as search results.
+#[derive(Default)]
+pub struct FindBar {
+    pub open: bool,
+    query: String,
+    replace_with: String,
+    options: SearchOptions,
+    regex: Option<Regex>,
+    error: Option<String>,
+    focus_query: bool,
+    // Режим, для которого найдены совпадения: в чтении считаются только видимые
+    matched_in_edit_mode: bool,
+}
+
+impl MdReader {
+    pub(crate) fn open_find_bar(&mut self) {
+        self.find_bar.open = true;
+        self.find_bar.focus_query = true;
+    }
+
+    fn close_find_bar(&mut self) {
+        self.find_bar.open = false;
+        self.highlight = None;
+    }
+
+    /// Finds every match in the open note, keeping the focus on the match at or after the previous one.
+    /// Read mode only keeps matches inside text it actually draws, so front matter, link targets and markup don't count.
+    fn refresh_find_matches(&mut self, scroll: bool) {
+        self.find_bar.error = None;
+        self.find_bar.regex = None;
+        if self.find_bar.query.is_empty() {
+            self.highlight = None;
+            return;
+        }
+        let regex = match search::build_regex(&self.find_bar.query, self.find_bar.options) {
+            Ok(regex) => regex,
+            Err(e) => {
+                self.find_bar.error = Some(e.to_string());
+                self.highlight = None;
+                return;
+            }
+        };
+
+        let mut ranges: Vec<_> = search::search_content(&self.file_content, &regex)
+            .into_iter()
+            .flat_map(|line| line.ranges)
+            .collect();
+        if !self.edit_mode {
+            let rendered = crate::rendered_source_ranges(&self.file_content);
+            ranges.retain(|range| rendered.iter().any(|source| source.start <= range.start && range.end <= source.end));
+        }
+        self.find_bar.matched_in_edit_mode = self.edit_mode;
+        let previous = self.highlight.as_ref().and_then(Highlight::focused_range).map_or(0, |range| range.start);
+        let focused = ranges.iter().position(|range| range.start >= previous).unwrap_or(0);
+        self.highlight = Some(Highlight { ranges, focused, scroll_pending: scroll });
+        self.find_bar.regex = Some(regex);
+    }
+
+    fn step_find_match(&mut self, forward: bool) {
+        let Some(highlight) = &mut self.highlight else { return };
+        let count = highlight.ranges.len();
+        if count == 0 {
+            return;
+        }
+        highlight.focused = if forward { (highlight.focused + 1) % count } else { (highlight.focused + count - 1) % count };
+        highlight.scroll_pending = true;
+    }
+
+    fn find_replacement(&self) -> String {
+        // Группы есть только у регулярных выражений; в обычном режиме «$» — просто символ
+        if self.find_bar.options.regex {
+            self.find_bar.replace_with.clone()
+        } else {
+            self.find_bar.replace_with.replace('$', "$$")
+        }
+    }
+
+    fn replace_focused_match(&mut self) {
+        let Some(range) = self.highlight.as_ref().and_then(Highlight::focused_range) else { return };
+        let Some(regex) = &self.find_bar.regex else { return };
+        let Some(captures) = regex.captures_at(&self.file_content, range.start) else { return };
+        if captures.get(0).map(|m| m.range()) != Some(range.clone()) {
+            return;
+        }
+        let mut replacement = String::new();
+        captures.expand(&self.find_replacement(), &mut replacement);
+
+        self.file_content.replace_range(range.clone(), &replacement);
+        if let Err(e) = self.save_file() {
+            eprintln!("Ошибка сохранения файла: {}", e);
+        }
+        self.refresh_find_matches(true);
+        // Фокус переходит на следующее совпадение после вставленного текста
+        let next_start = range.start + replacement.len();
+        if let Some(highlight) = &mut self.highlight {
+            highlight.focused = highlight.ranges.iter().position(|r| r.start >= next_start).unwrap_or(0);
+        }
+    }
+
+    fn replace_all_matches(&mut self) {
+        let Some(regex) = &self.find_bar.regex else { return };
+        let replaced = if self.find_bar.options.regex {
+            regex.replace_all(&self.file_content, self.find_bar.replace_with.as_str())
+        } else {
+            regex.replace_all(&self.file_content, NoExpand(&self.find_bar.replace_with))
+        };
+        let replaced = replaced.into_owned();
+        if replaced == self.file_content {
+            return;
+        }
+        self.file_content = replaced;
+        if let Err(e) = self.save_file() {
+            eprintln!("Ошибка сохранения файла: {}", e);
+        }
+        self.refresh_find_matches(false);
+    }
+
+    pub(crate) fn render_find_bar(&mut self, ui: &mut egui::Ui) {
+        if !self.find_bar.open || self.selected_file.is_none() {
+            return;
+        }
+        // Правка текста или другая заметка сбрасывают подсветку — ищем заново
+        let mode_changed = self.find_bar.matched_in_edit_mode != self.edit_mode;
+        if (self.highlight.is_none() || mode_changed) && !self.find_bar.query.is_empty() && self.find_bar.error.is_none() {
+            self.refresh_find_matches(false);
+        }
+
+        let (hint, replace_hint, case_tip, word_tip, regex_tip, previous_tip, next_tip, replace_text, replace_all_text) = match self.current_language {
+            Language::EN => ("Find in note…", "Replace with…", "Match case", "Whole words", "Regular expression", "Previous match (Shift+Enter)", "Next match (Enter)", "Replace", "Replace all"),
+            Language::RU => ("Найти в заметке…", "Заменить на…", "Учитывать регистр", "Слово целиком", "Регулярное выражение", "Предыдущее (Shift+Enter)", "Следующее (Enter)", "Заменить", "Заменить все"),
+        };
+
+        let mut search_changed = false;
+        let mut step = None;
+        let mut close = false;
+        let mut replace_one = false;
+        let mut replace_all = false;
+
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let response = ui.add(egui::TextEdit::singleline(&mut self.find_bar.query).hint_text(hint).desired_width(240.0));
+                if self.find_bar.focus_query {
+                    response.request_focus();
+                    self.find_bar.focus_query = false;
+                }
+                search_changed |= response.changed();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    step = Some(!ui.input(|i| i.modifiers.shift));
+                    // Enter снимает фокус с поля — возвращаем его, чтобы листать дальше
+                    self.find_bar.focus_query = true;
+                }
+                if (response.has_focus() || response.lost_focus()) && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    close = true;
+                }
+
+                let options = &mut self.find_bar.options;
+                search_changed |= ui.toggle_value(&mut options.case_sensitive, "Aa").on_hover_text(case_tip).changed();
+                search_changed |= ui.toggle_value(&mut options.whole_word, "W").on_hover_text(word_tip).changed();
+                search_changed |= ui.toggle_value(&mut options.regex, ".*").on_hover_text(regex_tip).changed();
+
+                let count = self.highlight.as_ref().map_or(0, |h| h.ranges.len());
+                if let Some(error) = &self.find_bar.error {
+                    ui.colored_label(Color32::from_rgb(235, 87, 87), error);
+                } else if !self.find_bar.query.is_empty() {
+                    let position = match (count, self.current_language) {
+                        (0, Language::EN) => "No results".to_string(),
+                        (0, Language::RU) => "Нет совпадений".to_string(),
+                        (_, Language::EN) => format!("{} of {}", self.highlight.as_ref().map_or(0, |h| h.focused) + 1, count),
+                        (_, Language::RU) => format!("{} из {}", self.highlight.as_ref().map_or(0, |h| h.focused) + 1, count),
+                    };
+                    ui.label(RichText::new(position).weak());
+                }
+
+                if ui.add_enabled(count > 0, egui::Button::new("⬆")).on_hover_text(previous_tip).clicked() {
+                    step = Some(false);
+                }
+                if ui.add_enabled(count > 0, egui::Button::new("⬇")).on_hover_text(next_tip).clicked() {
+                    step = Some(true);
+                }
+                if ui.button("✕").clicked() {
+                    close = true;
+                }
+            });
+
+            if self.edit_mode {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.find_bar.replace_with).hint_text(replace_hint).desired_width(240.0));
+                    let has_matches = self.highlight.as_ref().is_some_and(|h| !h.ranges.is_empty());
+                    if ui.add_enabled(has_matches, egui::Button::new(replace_text)).clicked() {
+                        replace_one = true;
+                    }
+                    if ui.add_enabled(has_matches, egui::Button::new(replace_all_text)).clicked() {
+                        replace_all = true;
+                    }
+                });
+            }
+        });
+
+        if close {
+            self.close_find_bar();
+            return;
+        }
+        if search_changed {
+            self.refresh_find_matches(true);
+        }
+        if let Some(forward) = step {
+            self.step_find_match(forward);
+        }
+        if replace_one {
+            self.replace_focused_match();
+        }
+        if replace_all {
+            self.replace_all_matches();
+        }
+    }
+}
@@ -0,0 +1,46 @@
+/// Fuzzy subsequence matching for the quick-open switcher and the command palette.
+///
+/// Every query character has to appear in `candidate` in order, ignoring case.
+/// Consecutive matches and matches at word starts score higher, gaps score lower.
+/// Returns the score and the char indices of matched characters in `candidate`.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i32, Vec<usize>)> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+
+    let mut score = 0;
+    let mut matched = Vec::with_capacity(query.len());
+    let mut query_index = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for (index, c) in chars.iter().enumerate() {
+        if query_index == query.len() {
+            break;
+        }
+        if !c.to_lowercase().eq(std::iter::once(query[query_index])) {
+            continue;
+        }
+
+        score += 1;
+        match previous_match {
+            Some(previous) if previous + 1 == index => score += 5,
+            Some(previous) => score -= ((index - previous - 1) as i32).min(5),
+            None => score -= (index as i32).min(5),
+        }
+        let word_start = index == 0 || {
+            let before = chars[index - 1];
+            !before.is_alphanumeric() || (before.is_lowercase() && c.is_uppercase())
+        };
+        if word_start {
+            score += 8;
+        }
+
+        matched.push(index);
+        previous_match = Some(index);
+        query_index += 1;
+    }
+
+    (query_index == query.len()).then_some((score, matched))
+}
@@ -0,0 +1,386 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, ecolor::Hsva, Align2, Color32, FontId, Pos2, Sense, Stroke, Vec2};
+
+use crate::{collect_category_paths, links, stable_hash, tags, Language, MdReader};
+
+// Параметры раскладки: желаемое расстояние между узлами и притяжение к центру
+const IDEAL_DISTANCE: f32 = 60.0;
+const GRAVITY: f32 = 0.02;
+const START_TEMPERATURE: f32 = 30.0;
+const COOLING: f32 = 0.97;
+// Ниже этой «температуры» раскладка считается устоявшейся и перестаёт перерисовываться
+const MIN_TEMPERATURE: f32 = 0.1;
+// Дальше этого расстояния узлы не отталкиваются: так отталкивание считается по соседним ячейкам сетки, а не по всем парам
+const REPULSION_RANGE: f32 = IDEAL_DISTANCE * 3.0;
+// Подписи всех узлов показываем только при таком приближении или на маленьких графах
+const LABEL_ZOOM: f32 = 1.3;
+const ALWAYS_LABEL_NODES: usize = 40;
+
+#[derive(Clone, PartialEq)]
+struct Filters {
+    local: bool,
+    depth: usize,
+    folder: Option<PathBuf>,
+    tag: String,
+}
+
+/// Visible nodes and edges; rebuilt when the link graph, the filters or the open note change.
+struct GraphCache {
+    generation: u64,
+    filters: Filters,
+    center: Option<PathBuf>,
+    nodes: Vec<PathBuf>,
+    edges: Vec<(usize, usize)>,
+    // Позиции узлов в порядке nodes; в общую карту попадают только при пересборке
+    positions: Vec<Vec2>,
+}
+
+/// Force-directed view of the notes and the links between them.
+pub struct GraphView {
+    pub open: bool,
+    filters: Filters,
+    positions: HashMap<PathBuf, Vec2>,
+    pan: Vec2,
+    zoom: f32,
+    temperature: f32,
+    dragged: Option<PathBuf>,
+    cache: Option<GraphCache>,
+}
+
+impl Default for GraphView {
+    fn default() -> Self {
+        Self {
+            open: false,
+            filters: Filters { local: false, depth: 1, folder: None, tag: String::new() },
+            positions: HashMap::new(),
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+            temperature: START_TEMPERATURE,
+            dragged: None,
+            cache: None,
+        }
+    }
+}
+
+/// A stable starting position, so the same vault always unfolds the same way.
+/// Bigger graphs start on a wider disk, about one ideal distance apart, so no grid cell starts crowded.
+fn initial_position(path: &Path, node_count: usize) -> Vec2 {
+    let hash = stable_hash(path.to_string_lossy().bytes());
+    let angle = (hash & 0xffff) as f32 / 65535.0 * std::f32::consts::TAU;
+    let spread = (IDEAL_DISTANCE * (node_count as f32 / std::f32::consts::PI).sqrt()).max(250.0);
+    let radius = 50.0 + ((hash >> 16) & 0xffff) as f32 / 65535.0 * spread;
+    Vec2::angled(angle) * radius
+}
+
+/// Notes within `depth` links of `center`, following links in both directions.
+fn neighborhood(center: &Path, edges: &[(PathBuf, PathBuf)], depth: usize) -> HashSet<PathBuf> {
+    let mut visited = HashSet::from([center.to_path_buf()]);
+    let mut queue = VecDeque::from([(center.to_path_buf(), 0)]);
+    while let Some((note, distance)) = queue.pop_front() {
+        if distance == depth {
+            continue;
+        }
+        for (source, target) in edges {
+            let next = if *source == note { target } else if *target == note { source } else { continue };
+            if visited.insert(next.clone()) {
+                queue.push_back((next.clone(), distance + 1));
+            }
+        }
+    }
+    visited
+}
+
+/// One step of a Fruchterman–Reingold layout; returns how far nodes moved.
+/// Repulsion uses the grid variant, so a step costs about as much as the number of nodes and edges.
+fn layout_step(positions: &mut [Vec2], edges: &[(usize, usize)], temperature: f32, pinned: Option<usize>) -> f32 {
+    let mut forces = vec![Vec2::ZERO; positions.len()];
+    let cell_of = |position: Vec2| ((position.x / REPULSION_RANGE).floor() as i32, (position.y / REPULSION_RANGE).floor() as i32);
+    let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (index, position) in positions.iter().enumerate() {
+        grid.entry(cell_of(*position)).or_default().push(index);
+    }
+    for (i, position) in positions.iter().enumerate() {
+        let (x, y) = cell_of(*position);
+        for cell in [(x - 1, y - 1), (x, y - 1), (x + 1, y - 1), (x - 1, y), (x, y), (x + 1, y), (x - 1, y + 1), (x, y + 1), (x + 1, y + 1)] {
+            for &j in grid.get(&cell).into_iter().flatten() {
+                if j == i {
+                    continue;
+                }
+                let delta = *position - positions[j];
+                let distance = delta.length().max(1.0);
+                if distance < REPULSION_RANGE {
+                    forces[i] += delta / distance * (IDEAL_DISTANCE * IDEAL_DISTANCE / distance);
+                }
+            }
+        }
+    }
+    for &(a, b) in edges {
+        let delta = positions[a] - positions[b];
+        let distance = delta.length().max(1.0);
+        let force = delta / distance * (distance * distance / IDEAL_DISTANCE);
+        forces[a] -= force;
+        forces[b] += force;
+    }
+
+    let mut moved = 0.0;
+    for (index, (position, force)) in positions.iter_mut().zip(forces).enumerate() {
+        if Some(index) == pinned {
+            continue;
+        }
+        let force = force - *position * GRAVITY;
+        let length = force.length();
+        if length > 0.0 {
+            let step = force / length * length.min(temperature);
+            *position += step;
+            moved += step.length();
+        }
+    }
+    moved
+}
+
+impl MdReader {
+    fn graph_filters_nodes(&self, filters: &Filters, all_edges: &[(PathBuf, PathBuf)]) -> Vec<PathBuf> {
+        let mut nodes: Vec<PathBuf> = self.link_graph.notes().cloned().collect();
+        if let Some(folder) = &filters.folder {
+            nodes.retain(|note| note.starts_with(folder));
+        }
+        let tag = filters.tag.trim().trim_start_matches('#');
+        if !tag.is_empty() {
+            let mut tagged = Vec::new();
+            tags::collect_tagged(&self.categories, tag, &mut tagged);
+            let tagged: HashSet<PathBuf> = tagged.into_iter().map(|(path, _)| path).collect();
+            nodes.retain(|note| tagged.contains(note));
+        }
+        if filters.local {
+            if let Some(center) = &self.selected_file {
+                let near = neighborhood(center, all_edges, filters.depth);
+                nodes.retain(|note| near.contains(note));
+            }
+        }
+        nodes.sort();
+        nodes
+    }
+
+    fn refresh_graph_cache(&mut self) {
+        let view = &self.graph_view;
+        let center = if view.filters.local { self.selected_file.clone() } else { None };
+        let fresh = view.cache.as_ref().is_some_and(|cache| {
+            cache.generation == self.link_graph.generation && cache.filters == view.filters && cache.center == center
+        });
+        if fresh {
+            return;
+        }
+
+        let all_edges = self.link_graph.edges(&self.root_dir);
+        let nodes = self.graph_filters_nodes(&view.filters, &all_edges);
+        let index: HashMap<&PathBuf, usize> = nodes.iter().enumerate().map(|(i, note)| (note, i)).collect();
+        let edges = all_edges.iter()
+            .filter_map(|(source, target)| Some((*index.get(source)?, *index.get(target)?)))
+            .collect();
+
+        let generation = self.link_graph.generation;
+        let view = &mut self.graph_view;
+        if let Some(old) = view.cache.take() {
+            view.positions.extend(old.nodes.into_iter().zip(old.positions));
+        }
+        let positions = nodes.iter()
+            .map(|note| *view.positions.entry(note.clone()).or_insert_with(|| initial_position(note, nodes.len())))
+            .collect();
+        view.cache = Some(GraphCache { generation, filters: view.filters.clone(), center, nodes, edges, positions });
+        view.temperature = START_TEMPERATURE;
+    }
+
+    /// Node color: one hue per top-level category, gray for notes in the workspace root.
+    fn category_colors(&self) -> HashMap<PathBuf, Color32> {
+        self.categories.iter().enumerate()
+            .map(|(index, category)| {
+                let hue = (index as f32 * 0.618_034) % 1.0;
+                (category.path.clone(), Color32::from(Hsva::new(hue, 0.55, 0.9, 1.0)))
+            })
+            .collect()
+    }
+
+    pub(crate) fn render_graph_view(&mut self, ctx: &egui::Context) {
+        if !self.graph_view.open {
+            return;
+        }
+        self.refresh_graph_cache();
+
+        let (title, local_text, depth_text, folder_text, all_folders, tag_hint, reset_text) = match self.current_language {
+            Language::EN => ("Note graph", "Local", "Depth", "Folder:", "All", "Tag…", "Reset view"),
+            Language::RU => ("Граф заметок", "Локальный", "Глубина", "Папка:", "Все", "Тег…", "Сбросить вид"),
+        };
+        let mut folders = Vec::new();
+        collect_category_paths(&self.categories, &mut folders);
+        let colors = self.category_colors();
+        let root = self.root_dir.clone();
+
+        let mut open = true;
+        let mut to_open = None;
+        egui::Window::new(title)
+            .open(&mut open)
+            .default_size([800.0, 600.0])
+            .resizable(true)
+            .show(ctx, |ui| {
+                let view = &mut self.graph_view;
+                let node_count = view.cache.as_ref().map_or(0, |cache| cache.nodes.len());
+                let edge_count = view.cache.as_ref().map_or(0, |cache| cache.edges.len());
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut view.filters.local, local_text);
+                    ui.add_enabled(view.filters.local, egui::DragValue::new(&mut view.filters.depth).clamp_range(1..=5).prefix(format!("{}: ", depth_text)));
+                    ui.label(folder_text);
+                    let folder_label = |folder: &Option<PathBuf>| match folder {
+                        Some(path) => links::relative_path(&root, path),
+                        None => all_folders.to_string(),
+                    };
+                    egui::ComboBox::from_id_source("graph_folder")
+                        .selected_text(folder_label(&view.filters.folder))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut view.filters.folder, None, all_folders);
+                            for folder in &folders {
+                                ui.selectable_value(&mut view.filters.folder, Some(folder.clone()), folder_label(&Some(folder.clone())));
+                            }
+                        });
+                    ui.add(egui::TextEdit::singleline(&mut view.filters.tag).hint_text(tag_hint).desired_width(100.0));
+                    if ui.button(reset_text).clicked() {
+                        view.pan = Vec2::ZERO;
+                        view.zoom = 1.0;
+                    }
+                    ui.label(egui::RichText::new(format!("{} / {}", node_count, edge_count)).weak());
+                });
+
+                let Some(cache) = &mut view.cache else { return };
+                let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+                let rect = response.rect;
+                painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+                let positions = &mut cache.positions;
+                let to_screen = |position: Vec2, pan: Vec2, zoom: f32| rect.center() + pan + position * zoom;
+
+                let mut degree = vec![0usize; cache.nodes.len()];
+                for &(a, b) in &cache.edges {
+                    degree[a] += 1;
+                    degree[b] += 1;
+                }
+                let radius = |index: usize, zoom: f32| (3.0 + (degree[index] as f32).sqrt() * 1.5) * zoom.clamp(0.5, 2.0);
+
+                // Узел под курсором
+                let pointer = response.hover_pos();
+                let hovered = pointer.and_then(|pointer| {
+                    positions.iter().enumerate()
+                        .map(|(index, position)| (index, to_screen(*position, view.pan, view.zoom).distance(pointer)))
+                        .filter(|(index, distance)| *distance <= radius(*index, view.zoom) + 3.0)
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .map(|(index, _)| index)
+                });
+
+                // Перетаскивание узла двигает его, перетаскивание фона — весь граф
+                if response.drag_started() {
+                    view.dragged = hovered.map(|index| cache.nodes[index].clone());
+                }
+                let dragged_index = view.dragged.as_ref().and_then(|dragged| cache.nodes.iter().position(|note| note == dragged));
+                if response.dragged() {
+                    match (dragged_index, pointer) {
+                        (Some(index), Some(pointer)) => {
+                            positions[index] = (pointer - rect.center() - view.pan) / view.zoom;
+                            view.temperature = view.temperature.max(START_TEMPERATURE / 3.0);
+                        }
+                        _ => view.pan += response.drag_delta(),
+                    }
+                }
+                if response.drag_released() {
+                    view.dragged = None;
+                }
+
+                if response.hovered() {
+                    let (scroll, pinch) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+                    let factor = (scroll / 200.0).exp() * pinch;
+                    if factor != 1.0 {
+                        let new_zoom = (view.zoom * factor).clamp(0.1, 8.0);
+                        // Точка под курсором остаётся на месте
+                        if let Some(pointer) = pointer {
+                            let anchor = pointer - rect.center() - view.pan;
+                            view.pan -= anchor * (new_zoom / view.zoom - 1.0);
+                        }
+                        view.zoom = new_zoom;
+                    }
+                }
+
+                if response.clicked() {
+                    if let Some(index) = hovered {
+                        to_open = Some(cache.nodes[index].clone());
+                    }
+                }
+
+                if view.temperature > MIN_TEMPERATURE {
+                    layout_step(positions, &cache.edges, view.temperature, dragged_index);
+                    view.temperature *= COOLING;
+                    ui.ctx().request_repaint();
+                }
+
+                let neighbors: HashSet<usize> = hovered.map_or_else(HashSet::new, |hovered| {
+                    cache.edges.iter()
+                        .filter_map(|&(a, b)| if a == hovered { Some(b) } else if b == hovered { Some(a) } else { None })
+                        .collect()
+                });
+                let edge_color = ui.visuals().weak_text_color().gamma_multiply(0.5);
+                let accent = ui.visuals().selection.bg_fill;
+                for &(a, b) in &cache.edges {
+                    let active = hovered.is_some_and(|hovered| hovered == a || hovered == b);
+                    let stroke = if active { Stroke::new(1.5, accent) } else { Stroke::new(1.0, edge_color) };
+                    painter.line_segment([to_screen(positions[a], view.pan, view.zoom), to_screen(positions[b], view.pan, view.zoom)], stroke);
+                }
+
+                let label_all = view.zoom >= LABEL_ZOOM || cache.nodes.len() <= ALWAYS_LABEL_NODES;
+                for (index, note) in cache.nodes.iter().enumerate() {
+                    let center: Pos2 = to_screen(positions[index], view.pan, view.zoom);
+                    if !rect.expand(50.0).contains(center) {
+                        continue;
+                    }
+                    let color = colors.iter()
+                        .find(|(category, _)| note.starts_with(category))
+                        .map_or(Color32::GRAY, |(_, color)| *color);
+                    let faded = hovered.is_some_and(|hovered| hovered != index && !neighbors.contains(&index));
+                    let color = if faded { color.gamma_multiply(0.3) } else { color };
+                    let r = radius(index, view.zoom);
+                    painter.circle_filled(center, r, color);
+
+                    let selected = self.selected_file.as_ref() == Some(note);
+                    if selected {
+                        painter.circle_stroke(center, r + 2.0, Stroke::new(2.0, accent));
+                    }
+                    if label_all || selected || Some(index) == hovered || neighbors.contains(&index) {
+                        let name = note.file_stem().unwrap_or_default().to_string_lossy();
+                        let text_color = if faded { ui.visuals().weak_text_color() } else { ui.visuals().text_color() };
+                        painter.text(center + Vec2::new(0.0, r + 2.0), Align2::CENTER_TOP, name, FontId::proportional(11.0), text_color);
+                    }
+                }
+
+                // Легенда: цвет каждой категории верхнего уровня
+                let mut legend_pos = rect.left_bottom() + Vec2::new(10.0, -16.0);
+                for category in self.categories.iter().rev() {
+                    let color = colors.get(&category.path).copied().unwrap_or(Color32::GRAY);
+                    painter.circle_filled(legend_pos + Vec2::new(5.0, 6.0), 5.0, color);
+                    painter.text(legend_pos + Vec2::new(16.0, 0.0), Align2::LEFT_TOP, &category.name, FontId::proportional(12.0), ui.visuals().text_color());
+                    legend_pos.y -= 16.0;
+                }
+
+                if let Some(index) = hovered {
+                    let relative = links::relative_path(&root, &cache.nodes[index]);
+                    response.on_hover_text(relative);
+                }
+            });
+
+        self.graph_view.open = open;
+        if let Some(path) = to_open {
+            self.load_file(&path);
+            if let Some(parent) = path.parent() {
+                self.current_dir = parent.to_path_buf();
+            }
+            self.expand_path_to(&path);
+        }
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, RichText};
+use pulldown_cmark::{Event, Parser, Tag};
+use walkdir::WalkDir;
+
+use crate::links::{self, Link, LinkKind, NoteIndex};
+use crate::search::Highlight;
+use crate::{tags, Category, Language, MdReader, SidebarTab};
+
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum IssueKind {
+    BrokenLink,
+    MissingImage,
+    MissingAnchor,
+    OrphanNote,
+    EmptyCategory,
+}
+
+impl IssueKind {
+    pub const ALL: [IssueKind; 5] = [
+        IssueKind::BrokenLink,
+        IssueKind::MissingImage,
+        IssueKind::MissingAnchor,
+        IssueKind::OrphanNote,
+        IssueKind::EmptyCategory,
+    ];
+
+    pub fn label(self, language: Language) -> &'static str {
+        match (self, language) {
+            (IssueKind::BrokenLink, Language::EN) => "Broken links",
+            (IssueKind::BrokenLink, Language::RU) => "Битые ссылки",
+            (IssueKind::MissingImage, Language::EN) => "Missing images",
+            (IssueKind::MissingImage, Language::RU) => "Отсутствующие изображения",
+            (IssueKind::MissingAnchor, Language::EN) => "Links to missing headings",
+            (IssueKind::MissingAnchor, Language::RU) => "Ссылки на несуществующие заголовки",
+            (IssueKind::OrphanNote, Language::EN) => "Notes nothing links to",
+            (IssueKind::OrphanNote, Language::RU) => "Заметки без входящих ссылок",
+            (IssueKind::EmptyCategory, Language::EN) => "Empty categories",
+            (IssueKind::EmptyCategory, Language::RU) => "Пустые категории",
+        }
+    }
+
+    /// Links that lead nowhere, as opposed to notes nobody links to and empty categories.
+    pub fn is_broken_link(self) -> bool {
+        matches!(self, IssueKind::BrokenLink | IssueKind::MissingImage | IssueKind::MissingAnchor)
+    }
+}
+
+/// How an issue can be fixed in one click.
+pub enum Fix {
+    /// Replace `old` at `range` of the note with `new`, e.g. point a link at a renamed file.
+    Replace { range: Range<usize>, old: String, new: String },
+    /// Move the empty folder to the trash.
+    DeleteFolder,
+}
+
+pub struct Issue {
+    pub kind: IssueKind,
+    pub path: PathBuf,
+    /// Where the problem is in the note, if it is about a link.
+    pub range: Option<Range<usize>>,
+    pub line: usize,
+    pub detail: String,
+    pub fix: Option<Fix>,
+}
+
+#[derive(Default)]
+pub struct HealthReport {
+    pub open: bool,
+    issues: Option<Vec<Issue>>,
+    status: Option<String>,
+}
+
+/// Every file under `root` outside of hidden folders, notes and attachments alike.
+pub(crate) fn workspace_files(root: &Path) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect()
+}
+
+fn is_image(path: &str) -> bool {
+    Path::new(path).extension()
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// GitHub-style anchor of a heading: lowercase, punctuation dropped, spaces turned into dashes.
+pub fn heading_slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            c if c.is_whitespace() => Some('-'),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Headings of a note as (text, anchor); repeated headings get `-1`, `-2`… like on GitHub.
+pub fn headings(content: &str) -> Vec<(String, String)> {
+    let mut headings = Vec::new();
+    let mut current: Option<String> = None;
+    for event in Parser::new(tags::body(content)) {
+        match event {
+            Event::Start(Tag::Heading(..)) => current = Some(String::new()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = &mut current {
+                    heading.push_str(&text);
+                }
+            }
+            Event::End(Tag::Heading(..)) => {
+                if let Some(text) = current.take() {
+                    headings.push(text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    headings.into_iter()
+        .map(|text| {
+            let slug = heading_slug(&text);
+            let count = seen.entry(slug.clone()).or_insert(0);
+            let slug = if *count == 0 { slug } else { format!("{}-{}", slug, count) };
+            *count += 1;
+            (text, slug)
+        })
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != *cb)).min(row[j] + 1).min(above + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// The candidate closest to `wanted`, if it is close enough to be the same name with a typo or a small edit.
+fn most_similar<'a, T>(wanted: &str, candidates: impl Iterator<Item = (&'a str, T)>) -> Option<T> {
+    let wanted = wanted.to_lowercase();
+    // Допускаем примерно одну правку на три символа
+    let max_distance = (wanted.chars().count() / 3).max(2);
+    candidates
+        .map(|(name, value)| (levenshtein(&wanted, &name.to_lowercase()), value))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, value)| value)
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+/// Where the anchor of `link` is written in the note.
+fn anchor_range(link: &Link, anchor: &str) -> Range<usize> {
+    match link.kind {
+        LinkKind::Markdown => link.range.end - anchor.len()..link.range.end,
+        LinkKind::Wiki => link.range.end + 1..link.range.end + 1 + anchor.len(),
+    }
+}
+
+fn anchor_exists(anchor: &str, headings: &[(String, String)]) -> bool {
+    let slug = heading_slug(&links::percent_decode(anchor));
+    headings.iter().any(|(_, heading)| *heading == slug || heading == anchor)
+}
+
+/// A link to a missing anchor, with a fix when a heading with a similar anchor exists.
+fn anchor_issue(path: &Path, content: &str, link: &Link, anchor: &str, headings: &[(String, String)]) -> Issue {
+    let range = anchor_range(link, anchor);
+    // Вики-ссылки указывают заголовок текстом, обычные — якорем
+    let wiki = link.kind == LinkKind::Wiki;
+    let fix = most_similar(anchor, headings.iter().map(|(text, slug)| if wiki { text.as_str() } else { slug.as_str() }).map(|name| (name, name)))
+        .map(|name| Fix::Replace { range: range.clone(), old: anchor.to_string(), new: name.to_string() });
+    Issue {
+        kind: IssueKind::MissingAnchor,
+        path: path.to_path_buf(),
+        line: line_of(content, range.start),
+        range: Some(range),
+        detail: format!("{}#{}", link.target, anchor),
+        fix,
+    }
+}
+
+/// Checks every note of the workspace for broken links, missing images and anchors, and notes nobody links to.
+pub fn check_notes(root: &Path, categories: &[Category]) -> Vec<Issue> {
+    let notes = links::markdown_files(root);
+    let index = NoteIndex::new(&notes);
+    let files = workspace_files(root);
+    let mut linked: HashSet<PathBuf> = HashSet::new();
+    let mut heading_cache: HashMap<PathBuf, Vec<(String, String)>> = HashMap::new();
+    let mut issues = Vec::new();
+
+    for note in &notes {
+        let Ok(content) = fs::read_to_string(note) else { continue };
+        for link in links::extract_links(&content) {
+            if link.kind == LinkKind::Markdown && links::is_external(&link.target) {
+                continue;
+            }
+            // Якорь на заголовок этой же заметки: [раздел](#раздел)
+            if link.kind == LinkKind::Markdown && link.target.is_empty() {
+                if let Some(anchor) = &link.anchor {
+                    let own = heading_cache.entry(note.clone()).or_insert_with(|| headings(&content));
+                    if !anchor.is_empty() && !anchor_exists(anchor, own) {
+                        issues.push(anchor_issue(note, &content, &link, anchor, own));
+                    }
+                }
+                continue;
+            }
+
+            // Вики-ссылки на вложения (![[схема.png]]) ищем по имени файла, а не по имени заметки
+            let wiki_attachment = link.kind == LinkKind::Wiki
+                && Path::new(&link.target).extension().is_some_and(|ext| ext != "md");
+            let target = if wiki_attachment {
+                let name = link.target.rsplit('/').next().unwrap_or(&link.target).to_lowercase();
+                files.iter()
+                    .find(|file| file.file_name().is_some_and(|file_name| file_name.to_string_lossy().to_lowercase() == name))
+                    .cloned()
+            } else {
+                links::resolve(root, note, &link, &index)
+            };
+
+            match target {
+                Some(target) if target.exists() => {
+                    if target != *note {
+                        linked.insert(target.clone());
+                    }
+                    let Some(anchor) = link.anchor.as_deref().filter(|anchor| !anchor.is_empty()) else { continue };
+                    // Ссылки на блоки (#^id) и якоря в не-markdown файлах не проверяем
+                    if anchor.starts_with('^') || target.extension().is_none_or(|ext| ext != "md") {
+                        continue;
+                    }
+                    let target_headings = heading_cache.entry(target.clone()).or_insert_with(|| {
+                        fs::read_to_string(&target).map(|content| headings(&content)).unwrap_or_default()
+                    });
+                    if !anchor_exists(anchor, target_headings) {
+                        issues.push(anchor_issue(note, &content, &link, anchor, target_headings));
+                    }
+                }
+                _ => issues.push(broken_link_issue(root, note, &content, &link, &notes, &files, &index, wiki_attachment)),
+            }
+        }
+    }
+
+    let mut orphans: Vec<&PathBuf> = notes.iter().filter(|note| !linked.contains(*note)).collect();
+    orphans.sort();
+    issues.extend(orphans.into_iter().map(|note| Issue {
+        kind: IssueKind::OrphanNote,
+        path: note.clone(),
+        range: None,
+        line: 1,
+        detail: String::new(),
+        fix: None,
+    }));
+
+    let mut empty = Vec::new();
+    collect_empty_categories(categories, &mut empty);
+    issues.extend(empty.into_iter().map(|path| Issue {
+        kind: IssueKind::EmptyCategory,
+        path,
+        range: None,
+        line: 0,
+        detail: String::new(),
+        fix: Some(Fix::DeleteFolder),
+    }));
+    issues
+}
+
+/// A link to a missing file, with a fix when a file with a similar name exists (e.g. after a rename outside the app).
+#[allow(clippy::too_many_arguments)]
+fn broken_link_issue(root: &Path, note: &Path, content: &str, link: &Link, notes: &[PathBuf], files: &[PathBuf], index: &NoteIndex, wiki_attachment: bool) -> Issue {
+    let image = is_image(&link.target) || (link.embed && link.kind == LinkKind::Markdown);
+    let wanted = Path::new(&links::percent_decode(&link.target)).to_path_buf();
+    let wanted_extension = wanted.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+    let wanted_stem = wanted.file_stem().unwrap_or_default().to_string_lossy().to_string();
+
+    // Заметки сравниваем с заметками, вложения — с файлами того же расширения
+    let candidates: Vec<&PathBuf> = match (&wanted_extension, link.kind) {
+        (None, LinkKind::Wiki) => notes.iter().collect(),
+        (Some(ext), _) if ext != "md" || wiki_attachment => files.iter()
+            .filter(|file| file.extension().is_some_and(|file_ext| file_ext.to_string_lossy().to_lowercase() == *ext))
+            .collect(),
+        _ => notes.iter().collect(),
+    };
+    let stems: Vec<(String, &PathBuf)> = candidates.into_iter()
+        .map(|file| (file.file_stem().unwrap_or_default().to_string_lossy().to_string(), file))
+        .collect();
+    let fix = most_similar(&wanted_stem, stems.iter().map(|(stem, file)| (stem.as_str(), *file)))
+        .map(|file| {
+            let new = if wiki_attachment {
+                file.file_name().unwrap_or_default().to_string_lossy().to_string()
+            } else {
+                links::rewritten_target(root, note, link, file, index)
+            };
+            Fix::Replace { range: link.range.clone(), old: content[link.range.clone()].to_string(), new }
+        })
+        .filter(|fix| !matches!(fix, Fix::Replace { old, new, .. } if old == new));
+
+    Issue {
+        kind: if image { IssueKind::MissingImage } else { IssueKind::BrokenLink },
+        path: note.to_path_buf(),
+        range: Some(link.range.clone()),
+        line: line_of(content, link.range.start),
+        detail: content[link.range.clone()].to_string(),
+        fix,
+    }
+}
+
+fn collect_empty_categories(categories: &[Category], empty: &mut Vec<PathBuf>) {
+    for category in categories {
+        // В категории без заметок могут лежать вложения — такую папку не удаляем
+        if category.files.is_empty() && category.subcategories.is_empty() && workspace_files(&category.path).is_empty() {
+            empty.push(category.path.clone());
+        }
+        collect_empty_categories(&category.subcategories, empty);
+    }
+}
+
+impl MdReader {
+    pub(crate) fn open_health_report(&mut self) {
+        self.health.open = true;
+        self.health.status = None;
+        self.health.issues = Some(check_notes(&self.root_dir, &self.categories));
+    }
+
+    fn apply_fix(&mut self, index: usize) {
+        let Some(issue) = self.health.issues.as_mut().map(|issues| issues.remove(index)) else { return };
+        match &issue.fix {
+            Some(Fix::Replace { range, old, new }) => {
+                let content = match fs::read_to_string(&issue.path) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        eprintln!("Ошибка чтения файла: {}", e);
+                        return;
+                    }
+                };
+                // Заметку могли изменить после проверки — тогда не трогаем её
+                if content.get(range.clone()) == Some(old.as_str()) {
+                    let mut fixed = content;
+                    fixed.replace_range(range.clone(), new);
+                    match fs::write(&issue.path, &fixed) {
+                        Ok(()) => self.reload_changed_files(&[(issue.path.clone(), fixed)]),
+                        Err(e) => eprintln!("Ошибка сохранения файла: {}", e),
+                    }
+                    self.health.status = None;
+                } else {
+                    self.health.status = Some(match self.current_language {
+                        Language::EN => "The note changed since the check, nothing was fixed".to_string(),
+                        Language::RU => "Заметка изменилась после проверки, ничего не исправлено".to_string(),
+                    });
+                }
+            }
+            Some(Fix::DeleteFolder) => self.delete_entry(&issue.path),
+            None => {}
+        }
+        self.health.issues = Some(check_notes(&self.root_dir, &self.categories));
+    }
+
+    fn open_issue(&mut self, issue: &Issue) {
+        if issue.kind == IssueKind::EmptyCategory {
+            self.sidebar_tab = SidebarTab::Files;
+            self.current_dir = issue.path.clone();
+            self.expand_path_to(&issue.path);
+            return;
+        }
+        self.load_file(&issue.path);
+        if let Some(range) = &issue.range {
+            self.highlight = Some(Highlight { ranges: vec![range.clone()], focused: 0, scroll_pending: true });
+        }
+    }
+
+    /// Window listing every problem found in the workspace, grouped by kind.
+    pub(crate) fn render_health_report(&mut self, ctx: &egui::Context) {
+        if !self.health.open {
+            return;
+        }
+        let Some(issues) = self.health.issues.take() else {
+            self.open_health_report();
+            return;
+        };
+        let (window_title, recheck_text, healthy_text, fix_tip, delete_text) = match self.current_language {
+            Language::EN => ("Workspace health", "🔄 Check again", "No problems found", "Replace with", "🗑 Delete"),
+            Language::RU => ("Проверка заметок", "🔄 Проверить снова", "Проблем не найдено", "Заменить на", "🗑 Удалить"),
+        };
+        let summary = match self.current_language {
+            Language::EN => format!("Problems found: {}", issues.len()),
+            Language::RU => format!("Найдено проблем: {}", issues.len()),
+        };
+
+        let mut open = self.health.open;
+        let mut to_open = None;
+        let mut to_fix = None;
+        let mut recheck = false;
+
+        egui::Window::new(window_title)
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(if issues.is_empty() { healthy_text.to_string() } else { summary });
+                    recheck = ui.button(recheck_text).clicked();
+                });
+                if let Some(status) = &self.health.status {
+                    ui.colored_label(ui.visuals().warn_fg_color, status);
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(450.0).show(ui, |ui| {
+                    for kind in IssueKind::ALL {
+                        let count = issues.iter().filter(|issue| issue.kind == kind).count();
+                        if count == 0 {
+                            continue;
+                        }
+                        egui::CollapsingHeader::new(RichText::new(format!("{} ({})", kind.label(self.current_language), count)).strong())
+                            .id_source(("health_group", kind as usize))
+                            // Заметок без входящих ссылок обычно много — по умолчанию свёрнуты
+                            .default_open(kind != IssueKind::OrphanNote)
+                            .show(ui, |ui| {
+                                for (i, issue) in issues.iter().enumerate().filter(|(_, issue)| issue.kind == kind) {
+                                    ui.horizontal(|ui| {
+                                        let mut location = links::relative_path(&self.root_dir, &issue.path);
+                                        if issue.range.is_some() {
+                                            location = format!("{}:{}", location, issue.line);
+                                        }
+                                        let icon = if kind == IssueKind::EmptyCategory { "📁" } else { "📄" };
+                                        if ui.link(format!("{} {}", icon, location)).clicked() {
+                                            to_open = Some(i);
+                                        }
+                                        if !issue.detail.is_empty() {
+                                            ui.label(RichText::new(&issue.detail).monospace().weak());
+                                        }
+                                        let fix_button = match &issue.fix {
+                                            Some(Fix::Replace { new, .. }) => Some((format!("🔧 {}", new), format!("{} {}", fix_tip, new))),
+                                            Some(Fix::DeleteFolder) => Some((delete_text.to_string(), String::new())),
+                                            None => None,
+                                        };
+                                        if let Some((text, tip)) = fix_button {
+                                            let response = ui.small_button(text);
+                                            let response = if tip.is_empty() { response } else { response.on_hover_text(tip) };
+                                            if response.clicked() {
+                                                to_fix = Some(i);
+                                            }
+                                        }
+                                    });
+                                }
+                            });
+                    }
+                });
+            });
+        self.health.open = open;
+
+        if let Some(i) = to_open {
+            self.open_issue(&issues[i]);
+        }
+        self.health.issues = Some(issues);
+        if let Some(i) = to_fix {
+            self.apply_fix(i);
+        }
+        if recheck {
+            self.open_health_report();
+        }
+    }
+}
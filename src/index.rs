@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use pulldown_cmark::{Event, Parser, Tag};
+
+use crate::{links, tags};
+
+/// Workspace folder for app data. Hidden, so it never shows up as a category.
+pub const DATA_DIR_NAME: &str = ".mdreader";
+const INDEX_FILE_NAME: &str = "index";
+const INDEX_HEADER: &str = "mdreader-index\t1";
+
+// Вес термов: заголовки и название заметки важнее основного текста
+const BODY_WEIGHT: f32 = 1.0;
+const HEADING_WEIGHT: f32 = 3.0;
+const TITLE_BONUS: f32 = 2.0;
+
+// Стандартные параметры BM25
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+// Индекс пишется на диск не чаще, чем раз в этот интервал
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+struct Document {
+    /// Relative to the workspace root.
+    path: PathBuf,
+    modified: u128,
+    size: u64,
+    /// Number of tokens, for BM25 length normalization.
+    length: f32,
+    terms: Vec<(String, f32)>,
+}
+
+/// Notes read from disk by `scan`, for `SearchIndex::apply`. Reading is the slow part of a sync,
+/// so it can run away from the UI thread.
+pub struct Scan {
+    /// The stamps `scan` compared against.
+    stamps: HashMap<PathBuf, (u128, u64)>,
+    /// Relative paths of every note found.
+    seen: HashSet<PathBuf>,
+    changed: Vec<Document>,
+}
+
+/// Full-text index of every note in the workspace, ranked with BM25.
+pub struct SearchIndex {
+    documents: Vec<Option<Document>>,
+    by_path: HashMap<PathBuf, usize>,
+    postings: BTreeMap<String, Vec<(usize, f32)>>,
+    total_length: f64,
+    dirty: bool,
+    last_save: Instant,
+}
+
+/// Lowercased words; anything that isn't a letter or digit separates them, in any script.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        // «ё» и «е» в русских текстах взаимозаменяемы
+        .map(|word| word.to_lowercase().replace('ё', "е"))
+}
+
+fn index_path(root: &Path) -> PathBuf {
+    root.join(DATA_DIR_NAME).join(INDEX_FILE_NAME)
+}
+
+fn file_stamp(path: &Path) -> Option<(u128, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_millis();
+    Some((modified, metadata.len()))
+}
+
+/// Weighted term frequencies and token count of a note.
+fn analyze(path: &Path, content: &str) -> (Vec<(String, f32)>, f32) {
+    let mut weights: HashMap<String, f32> = HashMap::new();
+    let mut length = 0.0;
+    let mut in_heading = false;
+
+    for event in Parser::new(content) {
+        match event {
+            Event::Start(Tag::Heading(..)) => in_heading = true,
+            Event::End(Tag::Heading(..)) => in_heading = false,
+            Event::Text(text) | Event::Code(text) => {
+                let weight = if in_heading { HEADING_WEIGHT } else { BODY_WEIGHT };
+                for token in tokenize(&text) {
+                    *weights.entry(token).or_default() += weight;
+                    length += 1.0;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Название — первая строка (как в сайдбаре) и имя файла
+    let title = tags::body(content).lines().next().unwrap_or("");
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    for token in tokenize(title).chain(tokenize(&stem)) {
+        *weights.entry(token).or_default() += TITLE_BONUS;
+    }
+
+    (weights.into_iter().collect(), length)
+}
+
+/// Reads the `files` that are new or changed since `stamps` (taken with `SearchIndex::stamps`).
+pub fn scan(root: &Path, files: &[PathBuf], stamps: HashMap<PathBuf, (u128, u64)>) -> Scan {
+    let mut seen = HashSet::with_capacity(files.len());
+    let mut changed = Vec::new();
+    for path in files {
+        let Ok(relative) = path.strip_prefix(root).map(Path::to_path_buf) else { continue };
+        let Some((modified, size)) = file_stamp(path) else { continue };
+        if stamps.get(&relative) != Some(&(modified, size)) {
+            if let Ok(content) = fs::read_to_string(path) {
+                let (terms, length) = analyze(path, &content);
+                changed.push(Document { path: relative.clone(), modified, size, length, terms });
+            }
+        }
+        seen.insert(relative);
+    }
+    Scan { stamps, seen, changed }
+}
+
+impl SearchIndex {
+    fn empty() -> Self {
+        Self {
+            documents: Vec::new(),
+            by_path: HashMap::new(),
+            postings: BTreeMap::new(),
+            total_length: 0.0,
+            dirty: false,
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Loads the stored index. A missing or unreadable file gives an empty index that `sync` fills.
+    pub fn load(root: &Path) -> Self {
+        let mut index = Self::empty();
+        let Ok(content) = fs::read_to_string(index_path(root)) else { return index };
+        let mut lines = content.lines();
+        if lines.next() != Some(INDEX_HEADER) {
+            return index;
+        }
+
+        let mut current: Option<Document> = None;
+        for line in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields.as_slice() {
+                ["D", path, modified, size, length] => {
+                    if let Some(document) = current.take() {
+                        index.insert(document);
+                    }
+                    current = Some(Document {
+                        path: PathBuf::from(path),
+                        modified: modified.parse().unwrap_or(0),
+                        size: size.parse().unwrap_or(0),
+                        length: length.parse().unwrap_or(0.0),
+                        terms: Vec::new(),
+                    });
+                }
+                [term, weight] => {
+                    if let (Some(document), Ok(weight)) = (current.as_mut(), weight.parse()) {
+                        document.terms.push((term.to_string(), weight));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(document) = current {
+            index.insert(document);
+        }
+        index
+    }
+
+    pub fn save(&mut self, root: &Path) -> Result<(), std::io::Error> {
+        let mut content = String::from(INDEX_HEADER);
+        content.push('\n');
+        for document in self.documents.iter().flatten() {
+            content.push_str(&format!(
+                "D\t{}\t{}\t{}\t{}\n",
+                document.path.to_string_lossy(), document.modified, document.size, document.length
+            ));
+            for (term, weight) in &document.terms {
+                content.push_str(&format!("{}\t{}\n", term, weight));
+            }
+        }
+
+        let path = index_path(root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Пишем во временный файл, чтобы не оставить обрезанный индекс
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &path)?;
+
+        self.dirty = false;
+        self.last_save = Instant::now();
+        Ok(())
+    }
+
+    /// Whether the debounced save may run now, so notes edited since can be reindexed first.
+    pub fn save_due(&self) -> bool {
+        self.last_save.elapsed() >= SAVE_INTERVAL
+    }
+
+    /// Saves pending changes, but not more often than every few seconds.
+    pub fn save_if_due(&mut self, root: &Path) {
+        if self.dirty && self.save_due() {
+            if let Err(e) = self.save(root) {
+                eprintln!("Ошибка сохранения индекса: {}", e);
+            }
+        }
+    }
+
+    pub fn save_if_dirty(&mut self, root: &Path) {
+        if self.dirty {
+            if let Err(e) = self.save(root) {
+                eprintln!("Ошибка сохранения индекса: {}", e);
+            }
+        }
+    }
+
+    fn insert(&mut self, document: Document) {
+        if document.path.to_string_lossy().contains(['\t', '\n']) {
+            return;
+        }
+        // Переиндексированная заметка занимает свой прежний слот
+        let reused = self.by_path.get(&document.path).copied();
+        self.remove_relative(&document.path);
+        let id = reused.unwrap_or_else(|| {
+            self.documents.push(None);
+            self.documents.len() - 1
+        });
+
+        for (term, weight) in &document.terms {
+            self.postings.entry(term.clone()).or_default().push((id, *weight));
+        }
+        self.total_length += document.length as f64;
+        self.by_path.insert(document.path.clone(), id);
+        self.documents[id] = Some(document);
+    }
+
+    fn remove_relative(&mut self, relative: &Path) {
+        let Some(id) = self.by_path.remove(relative) else { return };
+        let Some(document) = self.documents[id].take() else { return };
+        for (term, _) in &document.terms {
+            if let Some(postings) = self.postings.get_mut(term) {
+                postings.retain(|(doc, _)| *doc != id);
+                if postings.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_length -= document.length as f64;
+        self.dirty = true;
+    }
+
+    /// Reindexes a note from its current content, e.g. right after saving it.
+    pub fn update(&mut self, root: &Path, path: &Path, content: &str) {
+        let Ok(relative) = path.strip_prefix(root) else { return };
+        let (modified, size) = file_stamp(path).unwrap_or((0, content.len() as u64));
+        let (terms, length) = analyze(path, content);
+        self.insert(Document { path: relative.to_path_buf(), modified, size, length, terms });
+        self.dirty = true;
+    }
+
+    /// Modification stamps of the indexed notes, by relative path.
+    pub fn stamps(&self) -> HashMap<PathBuf, (u128, u64)> {
+        self.documents.iter().flatten()
+            .map(|document| (document.path.clone(), (document.modified, document.size)))
+            .collect()
+    }
+
+    /// Takes in a `scan`: new and changed notes are (re)indexed, removed ones are dropped.
+    /// Notes reindexed with `update` after the scan's stamps were taken are left as they are.
+    pub fn apply(&mut self, scan: Scan) {
+        let untouched = |index: &Self, path: &Path| {
+            let current = index.by_path.get(path).and_then(|id| index.documents[*id].as_ref());
+            current.map(|document| (document.modified, document.size)) == scan.stamps.get(path).copied()
+        };
+        for document in scan.changed {
+            if untouched(self, &document.path) {
+                self.insert(document);
+                self.dirty = true;
+            }
+        }
+        let removed: Vec<PathBuf> = self.by_path.keys()
+            .filter(|path| !scan.seen.contains(*path) && untouched(self, path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.remove_relative(&path);
+        }
+    }
+
+    /// Brings the index in line with the notes on disk: new and changed notes are (re)indexed,
+    /// removed ones are dropped. Unchanged notes are only `stat`ed.
+    pub fn sync(&mut self, root: &Path) {
+        let files = links::markdown_files(root);
+        self.apply(scan(root, &files, self.stamps()));
+    }
+
+    pub fn note_count(&self) -> usize {
+        self.by_path.len()
+    }
+
+    /// Notes containing every query term (the last one also as a prefix), best BM25 score first.
+    pub fn search(&self, root: &Path, query: &str, limit: usize) -> Vec<(PathBuf, f32)> {
+        let terms: Vec<String> = tokenize(query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let document_count = self.by_path.len() as f32;
+        let average_length = (self.total_length / document_count.max(1.0) as f64) as f32;
+        let mut scores: HashMap<usize, (f32, usize)> = HashMap::new();
+
+        for (position, term) in terms.iter().enumerate() {
+            let is_last = position + 1 == terms.len();
+            // Для последнего слова учитываем и продолжения: «докум» найдёт «документация»
+            let matching: Vec<&Vec<(usize, f32)>> = if is_last {
+                self.postings.range(term.clone()..)
+                    .take_while(|(key, _)| key.starts_with(term.as_str()))
+                    .map(|(_, postings)| postings)
+                    .collect()
+            } else {
+                self.postings.get(term).into_iter().collect()
+            };
+
+            let mut term_scores: HashMap<usize, f32> = HashMap::new();
+            for postings in matching {
+                let frequency = postings.len() as f32;
+                let idf = (1.0 + (document_count - frequency + 0.5) / (frequency + 0.5)).ln();
+                for (id, weight) in postings {
+                    let Some(document) = &self.documents[*id] else { continue };
+                    let norm = 1.0 - BM25_B + BM25_B * document.length / average_length.max(1.0);
+                    let score = idf * weight * (BM25_K1 + 1.0) / (weight + BM25_K1 * norm);
+                    *term_scores.entry(*id).or_default() += score;
+                }
+            }
+            for (id, score) in term_scores {
+                let entry = scores.entry(id).or_default();
+                entry.0 += score;
+                entry.1 += 1;
+            }
+        }
+
+        let mut ranked: Vec<(PathBuf, f32)> = scores.into_iter()
+            .filter(|(_, (_, matched_terms))| *matched_terms == terms.len())
+            .filter_map(|(id, (score, _))| {
+                let document = self.documents[id].as_ref()?;
+                Some((root.join(&document.path), score))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_keeps_notes_reindexed_while_it_ran() {
+        let root = std::env::temp_dir().join(format!("mdreader-index-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let (note, gone) = (root.join("note.md"), root.join("gone.md"));
+        fs::write(&note, "apple").unwrap();
+        fs::write(&gone, "cherry").unwrap();
+        let mut index = SearchIndex::empty();
+        index.sync(&root);
+        fs::remove_file(&gone).unwrap();
+
+        fs::write(&note, "banana").unwrap();
+        let scan = scan(&root, &links::markdown_files(&root), index.stamps());
+        // Пока шло чтение, заметку сохранили ещё раз — её свежий текст важнее прочитанного
+        index.update(&root, &note, "cherry pie");
+        index.apply(scan);
+
+        let found = |query: &str| index.search(&root, query, 10).into_iter().map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(found("pie"), vec![note.clone()]);
+        assert!(found("banana").is_empty());
+        assert_eq!(found("cherry"), vec![note]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::links::{self, Link, NoteIndex};
+
+// Контекст ссылки — предложение вокруг неё, но не длиннее этого
+const CONTEXT_MAX_LEN: usize = 240;
+
+struct NoteLinks {
+    modified: u128,
+    size: u64,
+    links: Vec<Link>,
+}
+
+/// Notes read from disk by `scan`, for `LinkGraph::apply`, like `index::Scan`.
+pub struct Scan {
+    stamps: HashMap<PathBuf, (u128, u64)>,
+    files: Vec<PathBuf>,
+    changed: Vec<(PathBuf, NoteLinks)>,
+}
+
+/// Parses the links of the `files` that are new or changed since `stamps` (taken with `LinkGraph::stamps`).
+pub fn scan(files: Vec<PathBuf>, stamps: HashMap<PathBuf, (u128, u64)>) -> Scan {
+    let changed = files.iter()
+        .filter_map(|path| {
+            let (modified, size) = file_stamp(path)?;
+            if stamps.get(path) == Some(&(modified, size)) {
+                return None;
+            }
+            let content = fs::read_to_string(path).ok()?;
+            Some((path.clone(), NoteLinks { modified, size, links: links::extract_links(&content) }))
+        })
+        .collect();
+    Scan { stamps, files, changed }
+}
+
+/// Links between all notes of the workspace. Notes are parsed once and re-parsed only
+/// when they change; links are resolved on demand, so wiki-links follow renames.
+pub struct LinkGraph {
+    notes: HashMap<PathBuf, NoteLinks>,
+    index: NoteIndex,
+    /// Bumped on every change, so views can tell when their cached results are stale.
+    pub generation: u64,
+}
+
+fn file_stamp(path: &Path) -> Option<(u128, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_millis();
+    Some((modified, metadata.len()))
+}
+
+/// The sentence around `range` in `content`, and where `range` ended up inside it.
+pub fn context_around(content: &str, range: Range<usize>) -> (String, Range<usize>) {
+    let is_boundary = |c: char| matches!(c, '.' | '!' | '?' | '\n');
+    let mut start = content[..range.start]
+        .rfind(is_boundary)
+        .map_or(0, |i| i + 1);
+    let mut end = content[range.end..]
+        .find(is_boundary)
+        .map_or(content.len(), |i| range.end + i + 1);
+
+    // Очень длинные предложения обрезаем вокруг ссылки
+    if end - start > CONTEXT_MAX_LEN {
+        let half = CONTEXT_MAX_LEN.saturating_sub(range.len()) / 2;
+        start = start.max(range.start.saturating_sub(half));
+        end = end.min(range.end + half);
+        while !content.is_char_boundary(start) {
+            start += 1;
+        }
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+    }
+
+    let raw = &content[start..end];
+    let trimmed_start = raw.len() - raw.trim_start().len();
+    let text = raw.trim().to_string();
+    let offset = start + trimmed_start;
+    let highlight = range.start.saturating_sub(offset)..(range.end - offset).min(text.len());
+    (text, highlight)
+}
+
+impl Default for LinkGraph {
+    fn default() -> Self {
+        Self {
+            notes: HashMap::new(),
+            index: NoteIndex::new(&[]),
+            generation: 0,
+        }
+    }
+}
+
+impl LinkGraph {
+    fn rebuild_index(&mut self) {
+        let files: Vec<PathBuf> = self.notes.keys().cloned().collect();
+        self.index = NoteIndex::new(&files);
+    }
+
+    /// Re-parses a note from its current content, e.g. right after saving it.
+    pub fn update(&mut self, path: &Path, content: &str) {
+        let (modified, size) = file_stamp(path).unwrap_or((0, content.len() as u64));
+        let is_new = self.notes
+            .insert(path.to_path_buf(), NoteLinks { modified, size, links: links::extract_links(content) })
+            .is_none();
+        if is_new {
+            self.rebuild_index();
+        }
+        self.generation += 1;
+    }
+
+    /// Modification stamps of the parsed notes.
+    pub fn stamps(&self) -> HashMap<PathBuf, (u128, u64)> {
+        self.notes.iter().map(|(path, note)| (path.clone(), (note.modified, note.size))).collect()
+    }
+
+    /// Takes in a `scan`, like `SearchIndex::apply`: notes updated after its stamps were taken are left as they are.
+    pub fn apply(&mut self, scan: Scan) {
+        let untouched = |notes: &HashMap<PathBuf, NoteLinks>, path: &Path| {
+            notes.get(path).map(|note| (note.modified, note.size)) == scan.stamps.get(path).copied()
+        };
+        let mut changed = false;
+        for (path, note) in scan.changed {
+            if untouched(&self.notes, &path) {
+                self.notes.insert(path, note);
+                changed = true;
+            }
+        }
+
+        let existing: HashSet<&PathBuf> = scan.files.iter().collect();
+        let before = self.notes.len();
+        let removed: Vec<PathBuf> = self.notes.keys()
+            .filter(|path| !existing.contains(path) && untouched(&self.notes, path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.notes.remove(&path);
+        }
+        changed |= self.notes.len() != before;
+
+        if changed {
+            self.rebuild_index();
+            self.generation += 1;
+        }
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = &PathBuf> {
+        self.notes.keys()
+    }
+
+    /// Links of `note` that point at another note, with the note they resolve to.
+    pub fn outgoing<'a>(&'a self, root: &'a Path, note: &'a Path) -> impl Iterator<Item = (&'a Link, PathBuf)> + 'a {
+        self.notes.get(note)
+            .into_iter()
+            .flat_map(|entry| &entry.links)
+            .filter_map(move |link| {
+                let target = links::resolve(root, note, link, &self.index)?;
+                self.notes.contains_key(&target).then_some((link, target))
+            })
+    }
+
+    /// Every note linking to `target`, with the links that do, sorted by path.
+    pub fn backlinks(&self, root: &Path, target: &Path) -> Vec<(PathBuf, Vec<Link>)> {
+        let mut result: Vec<(PathBuf, Vec<Link>)> = self.notes.keys()
+            .filter(|source| source.as_path() != target)
+            .filter_map(|source| {
+                let links: Vec<Link> = self.outgoing(root, source)
+                    .filter(|(_, resolved)| resolved == target)
+                    .map(|(link, _)| link.clone())
+                    .collect();
+                (!links.is_empty()).then(|| (source.clone(), links))
+            })
+            .collect();
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+
+    /// All links between notes as (source, target) pairs, without duplicates and self-links.
+    pub fn edges(&self, root: &Path) -> Vec<(PathBuf, PathBuf)> {
+        let mut edges: Vec<(PathBuf, PathBuf)> = self.notes.keys()
+            .flat_map(|source| {
+                self.outgoing(root, source)
+                    .filter(move |(_, target)| target != source)
+                    .map(move |(_, target)| (source.clone(), target))
+            })
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use walkdir::WalkDir;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum LinkKind {
+    Markdown,
+    Wiki,
+}
+
+/// A link found in a note. `range` covers only the target as written, so it can be replaced in place.
+#[derive(Clone, Debug)]
+pub struct Link {
+    pub kind: LinkKind,
+    pub target: String,
+    pub anchor: Option<String>,
+    pub range: Range<usize>,
+    /// Written with a leading `!`, i.e. an image or an embedded note.
+    pub embed: bool,
+    angle_brackets: bool,
+}
+
+pub struct LineChange {
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// New content for a note whose links have to be rewritten.
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub new_content: String,
+    pub changes: Vec<LineChange>,
+}
+
+fn markdown_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"!?\[(?:[^\[\]]|\[[^\]]*\])*\]\(\s*(<[^>\n]*>|[^)\s]+)(?:\s+(?:"[^"\n]*"|'[^'\n]*'))?\s*\)"#).unwrap()
+    })
+}
+
+fn wiki_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"!?\[\[([^\]\|#\n]+)(#[^\]\|\n]*)?(\|[^\]\n]*)?\]\]").unwrap())
+}
+
+/// Every markdown file under `root`, skipping hidden folders such as `.git` or `.trash`.
+pub fn markdown_files(root: &Path) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "md"))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Byte ranges of code blocks and inline code, where link-like text is not a link.
+pub fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    Parser::new(content)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(_)) | Event::Code(_) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn extract_links(content: &str) -> Vec<Link> {
+    let code = code_ranges(content);
+    let in_code = |offset: usize| code.iter().any(|range| range.contains(&offset));
+    let mut links = Vec::new();
+
+    for caps in markdown_link_regex().captures_iter(content) {
+        let whole = caps.get(0).unwrap();
+        if in_code(whole.start()) {
+            continue;
+        }
+        let raw = caps.get(1).unwrap();
+        let angle_brackets = raw.as_str().starts_with('<');
+        let range = if angle_brackets { raw.start() + 1..raw.end() - 1 } else { raw.range() };
+        let (target, anchor) = split_anchor(&content[range.clone()]);
+        links.push(Link {
+            kind: LinkKind::Markdown,
+            target,
+            anchor,
+            range,
+            embed: whole.as_str().starts_with('!'),
+            angle_brackets,
+        });
+    }
+
+    for caps in wiki_link_regex().captures_iter(content) {
+        let whole = caps.get(0).unwrap();
+        if in_code(whole.start()) {
+            continue;
+        }
+        let target = caps.get(1).unwrap();
+        links.push(Link {
+            kind: LinkKind::Wiki,
+            target: target.as_str().trim().to_string(),
+            anchor: caps.get(2).map(|a| a.as_str()[1..].to_string()),
+            range: target.range(),
+            embed: whole.as_str().starts_with('!'),
+            angle_brackets: false,
+        });
+    }
+
+    links.sort_by_key(|link| link.range.start);
+    links
+}
+
+fn split_anchor(raw: &str) -> (String, Option<String>) {
+    match raw.split_once('#') {
+        Some((target, anchor)) => (target.to_string(), Some(anchor.to_string())),
+        None => (raw.to_string(), None),
+    }
+}
+
+pub fn is_external(target: &str) -> bool {
+    target.contains("://") || target.starts_with("mailto:")
+}
+
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+            decoded.push(u8::from_str_radix(hex, 16).unwrap());
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Lexically removes `.` and `..` components, without touching the filesystem.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Relative link from a note in `from_dir` to `to`, always with `/` separators.
+pub fn relative_path(from_dir: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to_components: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to_components).take_while(|(a, b)| a == b).count();
+
+    let mut parts: Vec<String> = Vec::new();
+    for _ in common..from.len() {
+        parts.push("..".to_string());
+    }
+    for component in &to_components[common..] {
+        parts.push(component.as_os_str().to_string_lossy().to_string());
+    }
+    parts.join("/")
+}
+
+/// Where `path` ends up after the given moves (a moved folder takes its contents along).
+pub fn map_path(path: &Path, moves: &[(PathBuf, PathBuf)]) -> PathBuf {
+    for (old, new) in moves {
+        if let Ok(rest) = path.strip_prefix(old) {
+            return if rest.as_os_str().is_empty() { new.clone() } else { new.join(rest) };
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Lookup of notes by file stem, which is how wiki-links address them.
+pub struct NoteIndex {
+    by_stem: HashMap<String, Vec<PathBuf>>,
+}
+
+impl NoteIndex {
+    pub fn new(files: &[PathBuf]) -> Self {
+        let mut by_stem: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for file in files {
+            if let Some(stem) = file.file_stem() {
+                by_stem.entry(stem.to_string_lossy().to_lowercase()).or_default().push(file.clone());
+            }
+        }
+        Self { by_stem }
+    }
+
+    fn is_unique(&self, stem: &str) -> bool {
+        self.by_stem.get(&stem.to_lowercase()).is_none_or(|paths| paths.len() <= 1)
+    }
+}
+
+/// Resolves a link found in `note` to an absolute path. External links resolve to `None`.
+pub fn resolve(root: &Path, note: &Path, link: &Link, index: &NoteIndex) -> Option<PathBuf> {
+    match link.kind {
+        LinkKind::Markdown => {
+            if link.target.is_empty() || is_external(&link.target) {
+                return None;
+            }
+            let target = percent_decode(&link.target);
+            let base = if target.starts_with('/') { root.to_path_buf() } else { note.parent()?.to_path_buf() };
+            Some(normalize(&base.join(target.trim_start_matches('/'))))
+        }
+        LinkKind::Wiki => {
+            let target = link.target.trim_end_matches(".md");
+            if target.contains('/') {
+                return Some(normalize(&root.join(format!("{}.md", target))));
+            }
+            let candidates = index.by_stem.get(&target.to_lowercase())?;
+            // При совпадении имён предпочитаем заметку из той же папки
+            candidates.iter()
+                .find(|candidate| candidate.parent() == note.parent())
+                .or_else(|| candidates.first())
+                .cloned()
+        }
+    }
+}
+
+/// The text that should replace `link`'s target once it points at `new_target` from `new_note`.
+pub fn rewritten_target(root: &Path, new_note: &Path, link: &Link, new_target: &Path, index: &NoteIndex) -> String {
+    let mut text = match link.kind {
+        LinkKind::Markdown => {
+            let path = if link.target.starts_with('/') {
+                format!("/{}", relative_path(root, new_target))
+            } else {
+                relative_path(new_note.parent().unwrap_or(root), new_target)
+            };
+            if link.angle_brackets { path } else { path.replace(' ', "%20") }
+        }
+        LinkKind::Wiki => {
+            let stem = new_target.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let text = if !link.target.contains('/') && index.is_unique(&stem) {
+                stem
+            } else {
+                relative_path(root, &new_target.with_extension(""))
+            };
+            if link.target.ends_with(".md") { format!("{}.md", text) } else { text }
+        }
+    };
+    if link.kind == LinkKind::Markdown {
+        if let Some(anchor) = &link.anchor {
+            text.push('#');
+            text.push_str(anchor);
+        }
+    }
+    text
+}
+
+/// Computes how every note under `root` has to change so that its links survive the given moves.
+/// Must be called before the moves happen; the returned paths are the notes' new locations.
+pub fn plan_moves(root: &Path, moves: &[(PathBuf, PathBuf)]) -> Vec<FileEdit> {
+    let files = markdown_files(root);
+    let index = NoteIndex::new(&files);
+    let new_files: Vec<PathBuf> = files.iter().map(|file| map_path(file, moves)).collect();
+    let new_index = NoteIndex::new(&new_files);
+    let mut edits = Vec::new();
+
+    for (file, new_file) in files.iter().zip(&new_files) {
+        let Ok(content) = fs::read_to_string(file) else { continue };
+        let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+
+        for link in extract_links(&content) {
+            let Some(target) = resolve(root, file, &link, &index) else { continue };
+            if !target.exists() {
+                continue;
+            }
+            let new_target = map_path(&target, moves);
+            // Ссылка, которая и после переноса ведёт туда же (например, внутри перенесённой папки), остаётся как есть
+            if resolve(root, new_file, &link, &new_index).as_ref() == Some(&new_target) {
+                continue;
+            }
+            let text = rewritten_target(root, new_file, &link, &new_target, &new_index);
+            if text != content[link.range.clone()] {
+                replacements.push((link.range.clone(), text));
+            }
+        }
+
+        if let Some(edit) = apply_replacements(new_file, &content, replacements) {
+            edits.push(edit);
+        }
+    }
+    edits
+}
+
+/// Builds a [`FileEdit`] out of non-overlapping replacements, with a per-line preview.
+pub fn apply_replacements(path: &Path, content: &str, mut replacements: Vec<(Range<usize>, String)>) -> Option<FileEdit> {
+    if replacements.is_empty() {
+        return None;
+    }
+    replacements.sort_by_key(|(range, _)| range.start);
+
+    let mut new_content = String::with_capacity(content.len());
+    let mut last = 0;
+    for (range, text) in &replacements {
+        new_content.push_str(&content[last..range.start]);
+        new_content.push_str(text);
+        last = range.end;
+    }
+    new_content.push_str(&content[last..]);
+
+    let old_lines: Vec<&str> = content.lines().collect();
+    let new_lines: Vec<&str> = new_content.lines().collect();
+    let changes = old_lines.iter().zip(&new_lines)
+        .enumerate()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(i, (before, after))| LineChange {
+            line: i + 1,
+            before: before.to_string(),
+            after: after.to_string(),
+        })
+        .collect();
+
+    Some(FileEdit { path: path.to_path_buf(), new_content, changes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A fresh workspace in the temp dir with the given notes.
+    fn workspace(notes: &[(&str, &str)]) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!("mdreader-links-{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+        for (path, content) in notes {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    /// New content of `note` after moving `from` to `to`, or `None` when it doesn't change.
+    fn rewritten(notes: &[(&str, &str)], from: &str, to: &str, note: &str) -> Option<String> {
+        let root = workspace(notes);
+        let edits = plan_moves(&root, &[(root.join(from), root.join(to))]);
+        let note = map_path(&root.join(note), &[(root.join(from), root.join(to))]);
+        let content = edits.into_iter().find(|edit| edit.path == note).map(|edit| edit.new_content);
+        fs::remove_dir_all(&root).unwrap();
+        content
+    }
+
+    #[test]
+    fn rewrites_relative_links_and_keeps_anchors() {
+        let notes = [("a/note.md", "See [target](../b/target.md#setup)."), ("b/target.md", "# Target")];
+        assert_eq!(rewritten(&notes, "b/target.md", "c/target.md", "a/note.md").as_deref(), Some("See [target](../c/target.md#setup)."));
+    }
+
+    #[test]
+    fn keeps_root_absolute_links_absolute() {
+        let notes = [("a/note.md", "[t](/b/target.md)"), ("b/target.md", "")];
+        assert_eq!(rewritten(&notes, "b/target.md", "c/d/target.md", "a/note.md").as_deref(), Some("[t](/c/d/target.md)"));
+    }
+
+    #[test]
+    fn rewrites_percent_encoded_and_angle_bracket_links() {
+        let notes = [("a/note.md", "[one](../b/my%20target.md) [two](<../b/my target.md>)"), ("b/my target.md", "")];
+        assert_eq!(
+            rewritten(&notes, "b/my target.md", "c/my target.md", "a/note.md").as_deref(),
+            Some("[one](../c/my%20target.md) [two](<../c/my target.md>)"),
+        );
+    }
+
+    #[test]
+    fn rewrites_wiki_links_keeping_anchor_and_alias() {
+        let notes = [("note.md", "[[target#Intro|the target]] and ![[target]]"), ("b/target.md", "")];
+        assert_eq!(
+            rewritten(&notes, "b/target.md", "b/renamed.md", "note.md").as_deref(),
+            Some("[[renamed#Intro|the target]] and ![[renamed]]"),
+        );
+    }
+
+    #[test]
+    fn leaves_code_untouched() {
+        let content = "`[t](b/target.md)`\n\n```\n[t](b/target.md) [[target]]\n```\n";
+        let notes = [("note.md", content), ("b/target.md", "")];
+        assert_eq!(rewritten(&notes, "b/target.md", "c/target.md", "note.md"), None);
+    }
+
+    #[test]
+    fn moved_note_rewrites_its_own_links() {
+        let notes = [("a/note.md", "[t](../b/target.md)"), ("b/target.md", "")];
+        assert_eq!(rewritten(&notes, "a/note.md", "a/deeper/note.md", "a/note.md").as_deref(), Some("[t](../../b/target.md)"));
+    }
+
+    #[test]
+    fn replacements_preview_changed_lines() {
+        let content = "first\nsecond [x](old.md)\nthird";
+        let start = content.find("old.md").unwrap();
+        let edit = apply_replacements(Path::new("note.md"), content, vec![(start..start + 6, "new.md".to_string())]).unwrap();
+        assert_eq!(edit.new_content, "first\nsecond [x](new.md)\nthird");
+        assert_eq!(edit.changes.len(), 1);
+        assert_eq!((edit.changes[0].line, edit.changes[0].after.as_str()), (2, "second [x](new.md)"));
+        assert!(apply_replacements(Path::new("note.md"), content, Vec::new()).is_none());
+    }
+}
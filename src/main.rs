@@ -1,9 +1,66 @@
 use eframe::egui::{self, Color32, FontFamily, FontId, TextStyle, Visuals, RichText, FontData, FontDefinitions};
+use eframe::egui::text::{CCursor, CCursorRange, LayoutJob, TextFormat};
+use std::collections::HashSet;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, SystemTime};
 use std::fs;
-use pulldown_cmark::{Parser, Event, Tag, HeadingLevel};
-use sys_locale; // Import for language detection
+use pulldown_cmark::{Parser, Event, Tag, HeadingLevel, Options};
+
+mod backlinks;
+mod cli;
+mod clipboard;
+mod commands;
+mod context_menu;
+mod epub;
+mod export;
+mod find_bar;
+mod index;
+mod link_graph;
+mod links;
+mod fuzzy;
+mod graph_view;
+mod health;
+mod names;
+mod office;
+mod pdf;
+mod publish;
+mod query;
+mod quick_open;
+mod rename;
+mod replace;
+mod search;
+mod smart_folders;
+mod sort;
+mod tabs;
+mod tags;
+mod trash;
+mod tui;
+mod workspace;
+
+use backlinks::Backlinks;
+use clipboard::SourceMap;
+use commands::{Command, CommandPalette};
+use export::ExportDialog;
+use find_bar::FindBar;
+use graph_view::GraphView;
+use health::HealthReport;
+use index::SearchIndex;
+use link_graph::LinkGraph;
+use names::NameError;
+use publish::PublishDialog;
+use quick_open::QuickOpen;
+use rename::RenameDialog;
+use replace::ReplaceState;
+use search::{Highlight, SearchState};
+use smart_folders::SmartFolders;
+use sort::SortMode;
+use trash::UndoToast;
+
+// Как часто проверяем, дочитал ли фоновый поток заметки для индекса
+const SCAN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 // Enum to represent supported languages
 #[derive(PartialEq, Clone, Copy)]
@@ -12,11 +69,23 @@ enum Language {
     RU,
 }
 
+impl Language {
+    // Detect system language
+    fn from_system() -> Self {
+        match sys_locale::get_locale() {
+            Some(locale) if locale.starts_with("ru") => Language::RU,
+            _ => Language::EN, // Default to English
+        }
+    }
+}
+
 struct MdReader {
     current_dir: PathBuf,
     root_dir: PathBuf,
     categories: Vec<Category>,
     selected_file: Option<PathBuf>,
+    // Открытые вкладки; активная — selected_file
+    tabs: Vec<PathBuf>,
     edit_mode: bool,
     file_content: String,
     sidebar_width: f32,
@@ -24,38 +93,271 @@ struct MdReader {
     new_file_name: String,
     show_new_category_dialog: bool,
     show_new_file_dialog: bool,
+    // Ошибки, показываемые прямо в диалогах создания
+    new_category_error: Option<String>,
+    new_file_error: Option<String>,
     dark_mode: bool,
     current_language: Language, // Add state for current language
+    sort_mode: SortMode,
+    // Действие из сайдбара, которое применяем после отрисовки дерева
+    pending_action: Option<SidebarAction>,
+    // Выделенные в сайдбаре заметки (Ctrl/Shift+клик) для группового перемещения
+    selected_entries: Vec<PathBuf>,
+    selection_anchor: Option<PathBuf>,
+    rename_dialog: Option<RenameDialog>,
+    show_trash: bool,
+    // Содержимое корзины, пока её окно открыто; сбрасывается при удалении, восстановлении и очистке
+    trash_items: Option<Vec<trash::TrashItem>>,
+    undo_toast: Option<UndoToast>,
+    sidebar_tab: SidebarTab,
+    search: SearchState,
+    index: SearchIndex,
+    // Чтение новых и изменённых заметок для индекса и графа ссылок идёт в фоновом потоке
+    scan_worker: Option<Receiver<(index::Scan, link_graph::Scan)>>,
+    // Сохранённая заметка, которую ещё не переиндексировали
+    unindexed_note: Option<PathBuf>,
+    // Совпадения, подсвеченные в открытой заметке
+    highlight: Option<Highlight>,
+    // Недавно открытые заметки, самые свежие первыми
+    recent_files: Vec<PathBuf>,
+    quick_open: Option<QuickOpen>,
+    command_palette: Option<CommandPalette>,
+    replace: ReplaceState,
+    find_bar: FindBar,
+    // Тег, заметки с которым показаны в браузере тегов
+    tag_filter: Option<String>,
+    link_graph: LinkGraph,
+    show_backlinks: bool,
+    backlinks: Backlinks,
+    graph_view: GraphView,
+    smart_folders: SmartFolders,
+    health: HealthReport,
+    export: Option<ExportDialog>,
+    publish: Option<PublishDialog>,
+    // Создаётся при первом копировании форматированного текста
+    clipboard: Option<arboard::Clipboard>,
 }
 
+#[derive(PartialEq, Clone, Copy)]
+enum SidebarTab {
+    Files,
+    Search,
+    Tags,
+}
+
+// Sidebar actions that change the tree can't run while the tree is being rendered
+enum SidebarAction {
+    Reorder { dragged: PathBuf, target: PathBuf, after: bool },
+    Move { paths: Vec<PathBuf>, target_dir: PathBuf },
+    Delete(PathBuf),
+    Duplicate(PathBuf),
+    SelectRange(PathBuf),
+}
+
+// What happens when sidebar entries are dropped onto another entry
+enum DropKind {
+    // Вставка перед целью или, если after, после неё
+    Reorder { after: bool },
+    MoveInto(PathBuf),
+}
+
+#[derive(Clone)]
 struct Category {
     name: String,
     path: PathBuf,
     files: Vec<FileEntry>,
     subcategories: Vec<Category>,
     is_expanded: bool,
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
 }
 
+#[derive(Clone)]
 struct FileEntry {
     name: String,
     path: PathBuf,
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+    tags: Vec<String>,
+}
+
+impl Category {
+    fn new(path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+        Self {
+            name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            path: path.to_path_buf(),
+            files: Vec::new(),
+            subcategories: Vec::new(),
+            is_expanded: false,
+            modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+            created: metadata.as_ref().and_then(|m| m.created().ok()),
+        }
+    }
+}
+
+// Text of one rendered markdown block together with the search matches that fall into it
+#[derive(Default)]
+struct MarkdownText {
+    text: String,
+    // Диапазоны внутри text; true — совпадение, к которому нужно прокрутить
+    highlights: Vec<(Range<usize>, bool)>,
+    // Какие байты заметки показывает каждый кусок text — для копирования выделения как markdown
+    chunks: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl MarkdownText {
+    /// Appends text that came from `source` bytes of the note, carrying over overlapping highlights.
+    fn push(&mut self, text: &str, source: Range<usize>, highlight: Option<&Highlight>) {
+        let offset = self.text.len();
+        self.text.push_str(text);
+        self.chunks.push((offset..self.text.len(), source.clone()));
+
+        // Экранированные символы и сущности меняют длину — такие куски не подсвечиваем
+        let Some(highlight) = highlight else { return };
+        if text.len() != source.len() {
+            return;
+        }
+        for (index, range) in highlight.ranges.iter().enumerate() {
+            let start = range.start.max(source.start);
+            let end = range.end.min(source.end);
+            if start < end {
+                let local = offset + start - source.start..offset + end - source.start;
+                self.highlights.push((local, index == highlight.focused));
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn source_map(&self) -> SourceMap {
+        SourceMap { text: self.text.clone(), chunks: self.chunks.clone() }
+    }
+}
+
+// What a rendered block is, which decides how it is styled
+#[derive(Clone, Copy, PartialEq)]
+enum BlockKind {
+    Body,
+    Heading(HeadingLevel),
+    Code,
+    ListItem,
+}
+
+/// The note as reading mode shows it: one block per label, in order. The window and the terminal reader both draw these.
+fn markdown_blocks(content: &str, highlight: Option<&Highlight>) -> Vec<(BlockKind, MarkdownText)> {
+    // Front matter — это метаданные (теги показаны чипами), а не текст заметки
+    let body_start = tags::front_matter_range(content).map_or(0, |range| range.end);
+    let parser = Parser::new_ext(content, markdown_options()).into_offset_iter().filter(|(_, range)| range.start >= body_start);
+    let mut blocks = Vec::new();
+    let mut current_text = MarkdownText::default();
+    let mut in_code_block = false;
+    let mut in_list = false;
+    let mut current_heading_level = HeadingLevel::H1;
+
+    for (event, range) in parser {
+        match event {
+            Event::Start(Tag::Heading(level, _, _)) => {
+                if !current_text.is_empty() {
+                    blocks.push((BlockKind::Body, std::mem::take(&mut current_text)));
+                }
+                current_heading_level = level;
+            }
+            Event::End(Tag::Heading(..)) => {
+                blocks.push((BlockKind::Heading(current_heading_level), std::mem::take(&mut current_text)));
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code_block = false;
+                blocks.push((BlockKind::Code, std::mem::take(&mut current_text)));
+            }
+            Event::Start(Tag::List(_)) => {
+                in_list = true;
+            }
+            Event::End(Tag::List(_)) => {
+                in_list = false;
+            }
+            Event::Text(text) => {
+                if in_code_block {
+                    current_text.push(&text, range, highlight);
+                } else if in_list {
+                    let mut item = MarkdownText::default();
+                    item.text.push_str("• ");
+                    item.push(&text, range, highlight);
+                    blocks.push((BlockKind::ListItem, item));
+                } else {
+                    current_text.push(&text, range, highlight);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak if !in_code_block => {
+                current_text.text.push('\n');
+            }
+            _ => {}
+        }
+    }
+
+    if !current_text.is_empty() {
+        blocks.push((BlockKind::Body, current_text));
+    }
+    blocks
+}
+
+/// Byte ranges of the note that reading mode draws verbatim, i.e. where a match can be highlighted.
+fn rendered_source_ranges(content: &str) -> Vec<Range<usize>> {
+    markdown_blocks(content, None)
+        .into_iter()
+        .flat_map(|(_, block)| block.chunks)
+        .filter(|(text, source)| text.len() == source.len())
+        .map(|(_, source)| source)
+        .collect()
+}
+
+/// FNV-1a with fixed constants: unlike the std hasher it gives the same value on every Rust version.
+fn stable_hash(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+// Hidden folders (.git, .trash, ...) are not part of the notes tree
+fn is_hidden(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+// Every category of the tree, depth first, e.g. for folder pickers
+fn collect_category_paths(categories: &[Category], paths: &mut Vec<PathBuf>) {
+    for category in categories {
+        paths.push(category.path.clone());
+        collect_category_paths(&category.subcategories, paths);
+    }
+}
+
+// Markdown extensions for reading mode; exports parse with the same ones so they match the screen
+fn markdown_options() -> Options {
+    Options::empty()
+}
+
+// Payload for dragging sidebar entries around
+struct SidebarDrag {
+    paths: Vec<PathBuf>,
 }
 
 impl MdReader {
     fn new() -> Self {
         let root_dir = std::env::current_dir().unwrap();
 
-        // Detect system language
-        let default_language = match sys_locale::get_locale() {
-            Some(locale) if locale.starts_with("ru") => Language::RU,
-            _ => Language::EN, // Default to English
-        };
+        let index = SearchIndex::load(&root_dir);
+        let recent_files = quick_open::load_recent(&root_dir);
+        let smart_folders = smart_folders::load(&root_dir);
 
         let mut app = Self {
             current_dir: root_dir.clone(),
             root_dir,
             categories: Vec::new(),
             selected_file: None,
+            tabs: Vec::new(),
             edit_mode: false,
             file_content: String::new(),
             sidebar_width: 300.0,
@@ -63,41 +365,123 @@ impl MdReader {
             new_file_name: String::new(),
             show_new_category_dialog: false,
             show_new_file_dialog: false,
+            new_category_error: None,
+            new_file_error: None,
             dark_mode: true,
-            current_language: default_language, // Initialize with detected language
+            current_language: Language::from_system(), // Initialize with detected language
+            // Without order files manual mode falls back to natural name order
+            sort_mode: SortMode::Manual,
+            pending_action: None,
+            selected_entries: Vec::new(),
+            selection_anchor: None,
+            rename_dialog: None,
+            show_trash: false,
+            trash_items: None,
+            undo_toast: None,
+            sidebar_tab: SidebarTab::Files,
+            search: SearchState::default(),
+            index,
+            scan_worker: None,
+            unindexed_note: None,
+            highlight: None,
+            recent_files,
+            quick_open: None,
+            command_palette: None,
+            replace: ReplaceState::default(),
+            find_bar: FindBar::default(),
+            tag_filter: None,
+            link_graph: LinkGraph::default(),
+            show_backlinks: false,
+            backlinks: Backlinks::default(),
+            graph_view: GraphView::default(),
+            smart_folders,
+            health: HealthReport::default(),
+            export: None,
+            publish: None,
+            clipboard: None,
         };
         app.scan_directory();
         app
     }
 
-    fn scan_directory(&mut self) {
-        self.categories.clear();
-        
-        for entry in WalkDir::new(&self.root_dir)
-            .min_depth(1)
-            .max_depth(1)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_dir() {
-                let mut category = Category {
-                    name: entry.file_name().to_string_lossy().to_string(),
-                    path: entry.path().to_path_buf(),
-                    files: Vec::new(),
-                    subcategories: Vec::new(),
-                    is_expanded: false,
-                };
-                
-                self.scan_category_recursively(&mut category);
-                self.categories.push(category);
-            }
+    /// Rescans the tree while keeping every expanded category expanded.
+    fn refresh_tree(&mut self) {
+        let expanded = workspace::expanded_paths(&self.categories);
+        self.scan_directory();
+        workspace::restore_expanded(&mut self.categories, &expanded);
+    }
+
+    fn sort_tree(&mut self) {
+        sort::sort_categories(&mut self.categories, &self.root_dir, self.sort_mode);
+    }
+
+    fn find_category(&self, path: &Path) -> Option<&Category> {
+        workspace::find_category(&self.categories, path)
+    }
+
+    /// Applies a drag-and-drop reorder made in manual sort mode: `dragged` goes right before or `after` `target`.
+    fn apply_reorder(&mut self, dragged: &Path, target: &Path, after: bool) {
+        let Some(dir) = dragged.parent() else { return };
+        if target.parent() != Some(dir) || dragged == target {
+            return;
         }
 
+        // Текущий порядок в папке: сначала заметки, затем подкатегории, как в сайдбаре
+        let names: Vec<String> = if dir == self.root_dir {
+            self.categories.iter().map(|c| sort::entry_file_name(&c.path)).collect()
+        } else if let Some(category) = self.find_category(dir) {
+            category.files.iter().map(|f| sort::entry_file_name(&f.path))
+                .chain(category.subcategories.iter().map(|c| sort::entry_file_name(&c.path)))
+                .collect()
+        } else {
+            return;
+        };
+
+        let dragged_name = sort::entry_file_name(dragged);
+        let target_name = sort::entry_file_name(target);
+        if let Err(e) = sort::move_in_order(dir, names, &dragged_name, &target_name, after) {
+            eprintln!("Ошибка сохранения порядка: {}", e);
+            return;
+        }
+        self.refresh_tree();
+    }
+
+    fn scan_directory(&mut self) {
+        self.categories = workspace::scan_categories(&self.root_dir, self.sort_mode);
+        self.start_scan();
+
         // Восстанавливаем состояние развернутости для текущей директории
         let current_path = self.current_dir.clone();
         self.expand_path_to(&current_path);
     }
 
+    /// Reads new and changed notes for the index and the link graph on a background thread;
+    /// `finish_scan` takes the result in.
+    fn start_scan(&mut self) {
+        let (root, index_stamps, graph_stamps) = (self.root_dir.clone(), self.index.stamps(), self.link_graph.stamps());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // Один обход папок на индекс и граф
+            let files = links::markdown_files(&root);
+            let index_scan = index::scan(&root, &files, index_stamps);
+            let _ = sender.send((index_scan, link_graph::scan(files, graph_stamps)));
+        });
+        self.scan_worker = Some(receiver);
+    }
+
+    fn finish_scan(&mut self, ctx: &egui::Context) {
+        let Some(worker) = &self.scan_worker else { return };
+        match worker.try_recv() {
+            Ok((index_scan, graph_scan)) => {
+                self.index.apply(index_scan);
+                self.link_graph.apply(graph_scan);
+                self.scan_worker = None;
+            }
+            Err(TryRecvError::Empty) => ctx.request_repaint_after(SCAN_POLL_INTERVAL),
+            Err(TryRecvError::Disconnected) => self.scan_worker = None,
+        }
+    }
+
     fn expand_path_to(&mut self, target_path: &Path) {
         fn expand_in_category(category: &mut Category, target_path: &Path) -> bool {
             if target_path.starts_with(&category.path) {
@@ -117,129 +501,93 @@ impl MdReader {
         }
     }
 
-    fn scan_category_recursively(&self, category: &mut Category) {
-        self.scan_files_in_category(category);
-        
-        for entry in WalkDir::new(&category.path)
-            .min_depth(1)
-            .max_depth(1)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_dir() {
-                let mut subcategory = Category {
-                    name: entry.file_name().to_string_lossy().to_string(),
-                    path: entry.path().to_path_buf(),
-                    files: Vec::new(),
-                    subcategories: Vec::new(),
-                    is_expanded: false,
-                };
-                
-                self.scan_category_recursively(&mut subcategory);
-                category.subcategories.push(subcategory);
-            }
-        }
-    }
-
-    fn scan_files_in_category(&self, category: &mut Category) {
-        for entry in WalkDir::new(&category.path)
-            .min_depth(1)
-            .max_depth(1)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_file() && entry.path().extension().map_or(false, |ext| ext == "md") {
-                if let Ok(content) = fs::read_to_string(entry.path()) {
-                    let title = content.lines()
-                        .next()
-                        .unwrap_or("")
-                        .trim_start_matches(|c| c == '#' || c == ' ')
-                        .chars()
-                        .take(35)
-                        .collect::<String>();
-
-                    category.files.push(FileEntry {
-                        name: title,
-                        path: entry.path().to_path_buf(),
-                    });
-                }
+    fn save_file(&mut self) -> Result<(), std::io::Error> {
+        if let Some(path) = self.selected_file.clone() {
+            fs::write(&path, &self.file_content)?;
+            if self.unindexed_note.as_ref() != Some(&path) {
+                self.index_saved_note();
             }
+            // Сохраняем на каждое нажатие клавиши, поэтому индекс, ссылки и теги обновляем позже
+            self.unindexed_note = Some(path);
         }
+        Ok(())
     }
 
-    fn save_file(&self) -> Result<(), std::io::Error> {
-        if let Some(path) = &self.selected_file {
-            fs::write(path, &self.file_content)
+    /// Reindexes the note saved since the last call: its search terms, links and tags.
+    /// Runs when another note opens, before the debounced index save and before searching.
+    fn index_saved_note(&mut self) {
+        let Some(path) = self.unindexed_note.take() else { return };
+        let content = if self.selected_file.as_ref() == Some(&path) {
+            self.file_content.clone()
         } else {
-            Ok(())
-        }
+            match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(_) => return,
+            }
+        };
+        self.index.update(&self.root_dir, &path, &content);
+        self.link_graph.update(&path, &content);
+        self.update_file_tags(&path, &content);
     }
 
     fn load_file(&mut self, path: &Path) {
+        self.index_saved_note();
         if let Ok(content) = fs::read_to_string(path) {
+            self.open_in_active_tab(path);
+            self.highlight = None;
             self.file_content = content;
             self.selected_file = Some(path.to_path_buf());
+            self.remember_recent(path);
         }
     }
 
-    fn create_category(&mut self) {
-        if !self.new_category_name.is_empty() {
-            let new_path = self.current_dir.join(&self.new_category_name);
-            if !new_path.exists() {
-                if let Ok(_) = fs::create_dir(&new_path) {
-                    // Сохраняем состояние развернутости категорий
-                    let expanded_states: Vec<(PathBuf, bool)> = self.categories
-                        .iter()
-                        .map(|c| (c.path.clone(), c.is_expanded))
-                        .collect();
-                    
-                    self.new_category_name.clear();
-                    self.show_new_category_dialog = false;
-                    self.scan_directory();
-                    
-                    // Восстанавливаем состояние развернутости
-                    for category in &mut self.categories {
-                        if let Some(state) = expanded_states.iter().find(|(path, _)| path == &category.path) {
-                            category.is_expanded = state.1;
-                        }
-                    }
-                }
-            }
+    /// Where the create dialogs will put the new entry, e.g. "In: guides/setup".
+    fn target_folder_text(&self) -> String {
+        let relative = links::relative_path(&self.root_dir, &self.current_dir);
+        let folder = if relative.is_empty() { "/".to_string() } else { relative };
+        match self.current_language {
+            Language::EN => format!("In: {}", folder),
+            Language::RU => format!("В папке: {}", folder),
         }
     }
 
-    fn create_file(&mut self) {
-        if !self.new_file_name.is_empty() {
-            let file_name = if self.new_file_name.ends_with(".md") {
-                self.new_file_name.clone()
-            } else {
-                format!("{}.md", self.new_file_name)
-            };
-            
-            let file_path = self.current_dir.join(&file_name);
-            if !file_path.exists() {
-                if let Ok(_) = fs::write(&file_path, format!("# {}\n", self.new_file_name)) {
-                    // Сохраняем состояние развернутости категорий
-                    let expanded_states: Vec<(PathBuf, bool)> = self.categories
-                        .iter()
-                        .map(|c| (c.path.clone(), c.is_expanded))
-                        .collect();
-                    
-                    self.new_file_name.clear();
-                    self.show_new_file_dialog = false;
-                    self.scan_directory();
-                    
-                    // Восстанавливаем состояние развернутости
-                    for category in &mut self.categories {
-                        if let Some(state) = expanded_states.iter().find(|(path, _)| path == &category.path) {
-                            category.is_expanded = state.1;
-                        }
-                    }
-                    
-                    self.load_file(&file_path);
-                }
-            }
+    /// Creates a category below `current_dir`. `a/b` creates the intermediate categories too.
+    fn create_category(&mut self) -> Result<(), NameError> {
+        let new_path = names::resolve_new_path(&self.root_dir, &self.current_dir, &self.new_category_name)?;
+        if new_path.exists() {
+            return Err(NameError::AlreadyExists(new_path));
+        }
+        fs::create_dir_all(&new_path)?;
+
+        self.new_category_name.clear();
+        self.show_new_category_dialog = false;
+        self.refresh_tree();
+        self.expand_path_to(&new_path);
+        Ok(())
+    }
+
+    /// Creates a note below `current_dir` and opens it. `a/b/note` creates the categories on the way.
+    fn create_file(&mut self) -> Result<(), NameError> {
+        let mut file_path = names::resolve_new_path(&self.root_dir, &self.current_dir, &self.new_file_name)?;
+        if file_path.extension().is_none_or(|ext| ext != "md") {
+            let file_name = format!("{}.md", sort::entry_file_name(&file_path));
+            file_path.set_file_name(file_name);
+        }
+        if file_path.exists() {
+            return Err(NameError::AlreadyExists(file_path));
         }
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let title = file_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        fs::write(&file_path, format!("# {}\n", title))?;
+
+        self.new_file_name.clear();
+        self.show_new_file_dialog = false;
+        self.refresh_tree();
+        self.expand_path_to(&file_path);
+        self.load_file(&file_path);
+        Ok(())
     }
 
     fn render_category(&mut self, ui: &mut egui::Ui, category: &mut Category) {
@@ -273,11 +621,13 @@ impl MdReader {
             .rounding(10.0)     // Было 8.0
             .min_size(egui::vec2(ui.available_width(), 16.0))  // Минимальная высота кнопки
         );
+        let response = self.sidebar_drag_target(ui, response, &category.path, true);
         
         if response.clicked() {
             category.is_expanded = !category.is_expanded;
             self.current_dir = category.path.clone();
         }
+        response.context_menu(|ui| self.category_context_menu(ui, category));
         
         if category.is_expanded {
             ui.indent("category_indent", |ui| {
@@ -285,6 +635,12 @@ impl MdReader {
                 ui.add_space(5.0);
                 
                 for file in &category.files {
+                    let is_selected = self.selected_entries.contains(&file.path);
+                    let stroke = if is_selected {
+                        egui::Stroke::new(2.0, ui.visuals().selection.bg_fill)
+                    } else {
+                        egui::Stroke::NONE
+                    };
                     let file_response = ui.add(
                         egui::Button::new(
                             RichText::new(format!("📄 {}", file.name))
@@ -292,13 +648,30 @@ impl MdReader {
                                 .size(14.0)  // Оставляем файлы немного меньше категорий
                         )
                         .fill(button_color)
+                        .stroke(stroke)
                         .rounding(8.0)
                         .min_size(egui::vec2(ui.available_width(), 28.0))  // Чуть меньше высота для файлов
                     );
+                    let file_response = self.sidebar_drag_target(ui, file_response, &file.path, false);
                     
                     if file_response.clicked() {
-                        self.load_file(&file.path);
+                        let modifiers = ui.input(|i| i.modifiers);
+                        if modifiers.command {
+                            if is_selected {
+                                self.selected_entries.retain(|path| path != &file.path);
+                            } else {
+                                self.selected_entries.push(file.path.clone());
+                            }
+                            self.selection_anchor = Some(file.path.clone());
+                        } else if modifiers.shift {
+                            self.pending_action = Some(SidebarAction::SelectRange(file.path.clone()));
+                        } else {
+                            self.load_file(&file.path);
+                            self.selected_entries = vec![file.path.clone()];
+                            self.selection_anchor = Some(file.path.clone());
+                        }
                     }
+                    file_response.context_menu(|ui| self.file_context_menu(ui, file));
                 }
                 
                 ui.add_space(5.0);
@@ -310,24 +683,144 @@ impl MdReader {
         }
     }
 
-    fn render_markdown(&self, ui: &mut egui::Ui, content: &str) {
-        let parser = Parser::new(content);
-        let mut current_text = String::new();
-        let mut in_code_block = false;
-        let mut in_list = false;
-        let mut current_heading_level = HeadingLevel::H1;
-        
-        for event in parser {
-            match event {
-                Event::Start(Tag::Heading(level, _, _)) => {
-                    if !current_text.is_empty() {
-                        ui.label(&current_text);
-                        current_text.clear();
-                    }
-                    current_heading_level = level;
+    /// Makes a sidebar entry draggable and accepts entries dropped onto it:
+    /// siblings are reordered in manual mode, anything else is moved into the entry's folder.
+    fn sidebar_drag_target(&mut self, ui: &egui::Ui, response: egui::Response, path: &Path, is_category: bool) -> egui::Response {
+        let response = response.interact(egui::Sense::click_and_drag());
+        // Выделенные заметки перетаскиваются вместе
+        let paths = if !is_category && self.selected_entries.iter().any(|selected| selected == path) {
+            self.selected_entries.clone()
+        } else {
+            vec![path.to_path_buf()]
+        };
+        response.dnd_set_drag_payload(SidebarDrag { paths });
+
+        if let Some(payload) = response.dnd_hover_payload::<SidebarDrag>() {
+            let stroke = egui::Stroke::new(2.0, ui.visuals().selection.bg_fill);
+            match self.drop_kind(&response, &payload.paths, path, is_category) {
+                Some(DropKind::Reorder { after }) => {
+                    // Линия показывает, куда встанет перетаскиваемый элемент
+                    let y = if after { response.rect.bottom() + 3.0 } else { response.rect.top() - 3.0 };
+                    ui.painter().hline(response.rect.x_range(), y, stroke);
                 }
-                Event::End(Tag::Heading(..)) => {
-                    let font_size = match current_heading_level {
+                Some(DropKind::MoveInto(_)) => {
+                    ui.painter().rect_stroke(response.rect.expand(2.0), 8.0, stroke);
+                }
+                None => {}
+            }
+        }
+        if let Some(payload) = response.dnd_release_payload::<SidebarDrag>() {
+            match self.drop_kind(&response, &payload.paths, path, is_category) {
+                Some(DropKind::Reorder { after }) => {
+                    self.pending_action = Some(SidebarAction::Reorder {
+                        dragged: payload.paths[0].clone(),
+                        target: path.to_path_buf(),
+                        after,
+                    });
+                }
+                Some(DropKind::MoveInto(target_dir)) => {
+                    self.pending_action = Some(SidebarAction::Move {
+                        paths: payload.paths.clone(),
+                        target_dir,
+                    });
+                }
+                None => {}
+            }
+        }
+        response
+    }
+
+    fn drop_kind(&self, response: &egui::Response, dragged: &[PathBuf], target: &Path, target_is_category: bool) -> Option<DropKind> {
+        if dragged.iter().any(|path| path == target) {
+            return None;
+        }
+
+        let is_sibling = dragged.len() == 1 && dragged[0].parent() == target.parent();
+        if is_sibling && self.sort_mode == SortMode::Manual {
+            // Заметка: верхняя половина — вставка перед ней, нижняя — после.
+            // Категория: верхняя и нижняя четверти — перед и после, середина — перемещение внутрь
+            let edge = if target_is_category { 0.25 } else { 0.5 };
+            let fraction = response.hover_pos()
+                .map(|pos| (pos.y - response.rect.top()) / response.rect.height());
+            match fraction {
+                Some(fraction) if fraction < edge => return Some(DropKind::Reorder { after: false }),
+                Some(fraction) if fraction >= 1.0 - edge => return Some(DropKind::Reorder { after: true }),
+                _ if !target_is_category => return Some(DropKind::Reorder { after: false }),
+                _ => {}
+            }
+        }
+
+        let target_dir = if target_is_category { target } else { target.parent()? };
+        let already_there = dragged.iter().all(|path| path.parent() == Some(target_dir));
+        let into_itself = dragged.iter().any(|path| target_dir.starts_with(path));
+        if already_there || into_itself {
+            return None;
+        }
+        Some(DropKind::MoveInto(target_dir.to_path_buf()))
+    }
+
+    /// Moves entries into `target_dir` on disk, rewriting links that point at them.
+    fn move_into(&mut self, paths: &[PathBuf], target_dir: &Path) {
+        // Заметка внутри выделенной папки переедет вместе с папкой
+        let moves: Vec<(PathBuf, PathBuf)> = paths.iter()
+            .filter(|path| path.parent() != Some(target_dir))
+            .filter(|path| !path.ancestors().skip(1).any(|ancestor| paths.iter().any(|other| other == ancestor)))
+            .map(|path| (path.clone(), target_dir.join(path.file_name().unwrap_or_default())))
+            .collect();
+        // Одноимённые записи из разных папок затёрли бы друг друга
+        let mut names = HashSet::new();
+        if let Some((_, taken)) = moves.iter().find(|(_, new)| !names.insert(new.clone())) {
+            eprintln!("Ошибка перемещения: {} выбрано больше одного раза", taken.display());
+            return;
+        }
+
+        match self.move_entries(&moves) {
+            Ok(()) => {
+                self.selected_entries = moves.into_iter().map(|(_, new)| new).collect();
+                self.expand_path_to(target_dir);
+            }
+            Err(e) => eprintln!("Ошибка перемещения: {}", e),
+        }
+    }
+
+    /// Notes in the order they are currently shown in the sidebar.
+    fn visible_files(&self) -> Vec<PathBuf> {
+        fn collect(categories: &[Category], files: &mut Vec<PathBuf>) {
+            for category in categories.iter().filter(|c| c.is_expanded) {
+                files.extend(category.files.iter().map(|f| f.path.clone()));
+                collect(&category.subcategories, files);
+            }
+        }
+
+        let mut files = Vec::new();
+        collect(&self.categories, &mut files);
+        files
+    }
+
+    fn select_range(&mut self, to: &Path) {
+        let files = self.visible_files();
+        let end = files.iter().position(|path| path == to);
+        let start = self.selection_anchor.as_ref()
+            .and_then(|anchor| files.iter().position(|path| path == anchor))
+            .or(end);
+        if let (Some(start), Some(end)) = (start, end) {
+            self.selected_entries = files[start.min(end)..=start.max(end)].to_vec();
+        }
+    }
+
+    fn render_markdown(&self, ui: &mut egui::Ui, content: &str) {
+        let mut labels = Vec::new();
+        let body_format = TextFormat {
+            font_id: TextStyle::Body.resolve(ui.style()),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+
+        for (kind, block) in markdown_blocks(content, self.highlight.as_ref()) {
+            let format = match kind {
+                BlockKind::Body | BlockKind::ListItem => body_format.clone(),
+                BlockKind::Heading(level) => {
+                    let font_size = match level {
                         HeadingLevel::H1 => 24.0,
                         HeadingLevel::H2 => 20.0,
                         HeadingLevel::H3 => 18.0,
@@ -335,52 +828,42 @@ impl MdReader {
                         HeadingLevel::H5 => 14.0,
                         HeadingLevel::H6 => 12.0,
                     };
-                    ui.heading(RichText::new(&current_text)
-                        .size(font_size)
-                        .color(Color32::from_rgb(200, 200, 200)));
-                    current_text.clear();
-                }
-                Event::Start(Tag::CodeBlock(_)) => {
-                    in_code_block = true;
-                }
-                Event::End(Tag::CodeBlock(_)) => {
-                    in_code_block = false;
-                    ui.add(egui::Label::new(RichText::new(&current_text)
-                        .monospace()
-                        .color(Color32::from_rgb(150, 150, 150))));
-                    current_text.clear();
-                }
-                Event::Start(Tag::List(_)) => {
-                    in_list = true;
-                }
-                Event::End(Tag::List(_)) => {
-                    in_list = false;
-                }
-                Event::Text(text) => {
-                    if in_code_block {
-                        current_text.push_str(&text);
-                    } else if in_list {
-                        ui.label(format!("• {}", text));
-                    } else {
-                        current_text.push_str(&text);
+                    TextFormat {
+                        font_id: FontId::proportional(font_size),
+                        color: Color32::from_rgb(200, 200, 200),
+                        ..Default::default()
                     }
                 }
-                Event::SoftBreak => {
-                    if !in_code_block {
-                        current_text.push('\n');
-                    }
-                }
-                Event::HardBreak => {
-                    if !in_code_block {
-                        current_text.push('\n');
-                    }
-                }
-                _ => {}
-            }
+                BlockKind::Code => TextFormat {
+                    font_id: TextStyle::Monospace.resolve(ui.style()),
+                    color: Color32::from_rgb(150, 150, 150),
+                    ..Default::default()
+                },
+            };
+            self.markdown_label(ui, &block, format, &mut labels);
         }
-        
-        if !current_text.is_empty() {
-            ui.label(&current_text);
+
+        if ui.input(|i| i.events.contains(&egui::Event::Copy)) {
+            clipboard::remember_rendered(ui.ctx(), content, labels);
+        }
+    }
+
+    /// Adds a block of rendered markdown, painting highlighted matches and scrolling to the focused one.
+    fn markdown_label(&self, ui: &mut egui::Ui, block: &MarkdownText, format: TextFormat, labels: &mut Vec<SourceMap>) {
+        let match_color = search::match_color();
+        let focused_color = ui.visuals().selection.bg_fill;
+        let ranges: Vec<(Range<usize>, Color32)> = block.highlights.iter()
+            .map(|(range, focused)| (range.clone(), if *focused { focused_color } else { match_color }))
+            .collect();
+
+        let mut job = LayoutJob::default();
+        search::append_highlighted(&mut job, &block.text, &ranges, format);
+        let response = ui.label(job);
+        labels.push(block.source_map());
+
+        let focused = block.highlights.iter().any(|(_, focused)| *focused);
+        if focused && self.highlight.as_ref().is_some_and(|h| h.scroll_pending) {
+            response.scroll_to_me(Some(egui::Align::Center));
         }
     }
 
@@ -413,7 +896,18 @@ impl MdReader {
 }
 
 impl eframe::App for MdReader {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.index_saved_note();
+        self.index.save_if_dirty(&self.root_dir);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.finish_scan(ctx);
+        if self.index.save_due() {
+            self.index_saved_note();
+        }
+        self.index.save_if_due(&self.root_dir);
+
         let mut style = (*ctx.style()).clone();
         
         // Корректируем размеры шрифтов
@@ -508,7 +1002,7 @@ impl eframe::App for MdReader {
                         .rounding(10.0)
                         .min_size(egui::vec2(140.0, 35.0))
                 ).clicked() {
-                    self.show_new_category_dialog = true;
+                    self.run_command(Command::NewCategory);
                 }
 
                 // --- Create Note Button ---
@@ -521,7 +1015,7 @@ impl eframe::App for MdReader {
                         .rounding(10.0)
                         .min_size(egui::vec2(120.0, 35.0))
                 ).clicked() {
-                    self.show_new_file_dialog = true;
+                    self.run_command(Command::NewNote);
                 }
 
                 // --- Edit/Read Mode Button ---
@@ -536,7 +1030,7 @@ impl eframe::App for MdReader {
                         .rounding(10.0)
                         .min_size(egui::vec2(160.0, 35.0))
                 ).clicked() {
-                    self.edit_mode = !self.edit_mode;
+                    self.run_command(Command::ToggleEditMode);
                 }
 
                 // --- Theme Toggle Button ---
@@ -551,7 +1045,7 @@ impl eframe::App for MdReader {
                         .rounding(10.0)
                         .min_size(egui::vec2(140.0, 35.0))
                 ).clicked() {
-                    self.toggle_theme();
+                    self.run_command(Command::ToggleTheme);
                 }
 
                 // --- Language Toggle Button ---
@@ -564,7 +1058,7 @@ impl eframe::App for MdReader {
                         .rounding(10.0)
                         .min_size(egui::vec2(50.0, 35.0)) // Smaller button
                 ).clicked() {
-                    self.toggle_language();
+                    self.run_command(Command::ToggleLanguage);
                 }
 
             });
@@ -591,8 +1085,15 @@ impl eframe::App for MdReader {
             egui::Window::new(window_title)
                 .open(&mut dialog_open)
                 .show(ctx, |ui| {
+                    ui.label(RichText::new(self.target_folder_text()).weak());
                     ui.label(label_text);
                     let text_edit_response = ui.text_edit_singleline(&mut self.new_category_name);
+                    if text_edit_response.changed() {
+                        self.new_category_error = None;
+                    }
+                    if let Some(error) = &self.new_category_error {
+                        ui.colored_label(Color32::from_rgb(235, 87, 87), error);
+                    }
                     let button_response = ui.button(button_text);
 
                     if button_response.clicked() || (text_edit_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))) {
@@ -601,8 +1102,13 @@ impl eframe::App for MdReader {
                 });
 
             if should_create {
-                self.create_category();
-                dialog_open = false;
+                match self.create_category() {
+                    Ok(()) => dialog_open = false,
+                    Err(e) => self.new_category_error = Some(e.message(self.current_language, &self.root_dir)),
+                }
+            }
+            if !dialog_open {
+                self.new_category_error = None;
             }
             self.show_new_category_dialog = dialog_open;
         }
@@ -627,8 +1133,15 @@ impl eframe::App for MdReader {
             egui::Window::new(window_title)
                 .open(&mut dialog_open)
                 .show(ctx, |ui| {
+                    ui.label(RichText::new(self.target_folder_text()).weak());
                     ui.label(label_text);
                     let text_edit_response = ui.text_edit_singleline(&mut self.new_file_name);
+                    if text_edit_response.changed() {
+                        self.new_file_error = None;
+                    }
+                    if let Some(error) = &self.new_file_error {
+                        ui.colored_label(Color32::from_rgb(235, 87, 87), error);
+                    }
                     let button_response = ui.button(button_text);
 
                     if button_response.clicked() || (text_edit_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))) {
@@ -637,57 +1150,104 @@ impl eframe::App for MdReader {
                 });
 
             if should_create {
-                self.create_file();
-                dialog_open = false;
+                match self.create_file() {
+                    Ok(()) => dialog_open = false,
+                    Err(e) => self.new_file_error = Some(e.message(self.current_language, &self.root_dir)),
+                }
+            }
+            if !dialog_open {
+                self.new_file_error = None;
             }
             self.show_new_file_dialog = dialog_open;
         }
 
+        self.handle_shortcuts(ctx);
+        self.render_command_palette(ctx);
+        self.render_quick_open(ctx);
+        self.render_rename_dialog(ctx);
+        self.render_replace_window(ctx);
+        self.render_graph_view(ctx);
+        self.render_health_report(ctx);
+        self.render_export_dialog(ctx);
+        self.render_publish_dialog(ctx);
+        self.render_trash_window(ctx);
+        self.render_undo_toast(ctx);
+
         egui::SidePanel::left("sidebar")
             .resizable(true)
             .min_width(200.0)
             .max_width(600.0)
             .default_width(self.sidebar_width)
             .show(ctx, |ui| {
-                // Add a vertical ScrollArea
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    let categories = std::mem::take(&mut self.categories);
-                    for mut category in categories {
-                        self.render_category(ui, &mut category);
-                        self.categories.push(category);
-                    }
+                let (files_tab_text, search_tab_text, tags_tab_text) = match self.current_language {
+                    Language::EN => ("📁 Files", "🔍 Search", "🏷 Tags"),
+                    Language::RU => ("📁 Файлы", "🔍 Поиск", "🏷 Теги"),
+                };
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.sidebar_tab, SidebarTab::Files, files_tab_text);
+                    ui.selectable_value(&mut self.sidebar_tab, SidebarTab::Search, search_tab_text);
+                    ui.selectable_value(&mut self.sidebar_tab, SidebarTab::Tags, tags_tab_text);
                 });
+                ui.separator();
+
+                let trash_text = match self.current_language {
+                    Language::EN => "🗑 Trash",
+                    Language::RU => "🗑 Корзина",
+                };
+                egui::TopBottomPanel::bottom("sidebar_bottom")
+                    .show_separator_line(false)
+                    .show_inside(ui, |ui| {
+                        if ui.button(trash_text).clicked() {
+                            self.show_trash = !self.show_trash;
+                        }
+                    });
+
+                match self.sidebar_tab {
+                    SidebarTab::Files => self.render_files_tab(ui),
+                    SidebarTab::Search => self.render_search_panel(ui),
+                    SidebarTab::Tags => self.render_tags_panel(ui),
+                }
             });
 
+        match self.pending_action.take() {
+            Some(SidebarAction::Reorder { dragged, target, after }) => self.apply_reorder(&dragged, &target, after),
+            Some(SidebarAction::Move { paths, target_dir }) => self.move_into(&paths, &target_dir),
+            Some(SidebarAction::Delete(path)) => self.delete_entry(&path),
+            Some(SidebarAction::Duplicate(path)) => self.duplicate_file(&path),
+            Some(SidebarAction::SelectRange(path)) => self.select_range(&path),
+            None => {}
+        }
+
+        self.render_backlinks_panel(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.add_space(10.0); // Уменьшаем отступ сверху
+            self.render_tab_bar(ui);
+            self.render_find_bar(ui);
             if let Some(_path) = &self.selected_file {
                 let content_width = ui.available_width() - 20.0; // Уменьшаем боковые отступы
                 
-                if self.edit_mode {
-                    let response = ui.add(
-                        egui::TextEdit::multiline(&mut self.file_content)
-                            .desired_width(content_width)
-                            .desired_rows(30)
-                            .margin(egui::vec2(10.0, 10.0)) // Уменьшаем внутренние отступы
-                    );
-                    
-                    if response.changed() {
-                        if let Err(e) = self.save_file() {
-                            eprintln!("Ошибка сохранения файла: {}", e);
+                egui::ScrollArea::vertical()
+                    .id_source("note_scroll")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        if self.edit_mode {
+                            self.render_editor(ui, content_width);
+                        } else {
+                            ui.add_space(5.0);
+                            egui::Frame::none()
+                                .inner_margin(egui::Margin { left: 10.0, right: 10.0, ..Default::default() }) // Уменьшаем отступ слева
+                                .show(ui, |ui| {
+                                    ui.set_max_width(content_width);
+                                    self.render_tag_chips(ui);
+                                    self.render_markdown(ui, &self.file_content);
+                                });
+                            ui.add_space(5.0);
                         }
-                    }
-                } else {
-                    ui.add_space(5.0);
-                    let mut text_ui = ui.child_ui(
-                        egui::Rect::from_min_size(
-                            ui.min_rect().min + egui::vec2(10.0, 0.0), // Уменьшаем отступ слева
-                            egui::vec2(content_width, ui.available_height())
-                        ),
-                        *ui.layout()
-                    );
-                    self.render_markdown(&mut text_ui, &self.file_content);
-                    ui.add_space(5.0);
+                    });
+
+                if let Some(highlight) = &mut self.highlight {
+                    highlight.scroll_pending = false;
                 }
             }
             ui.add_space(10.0); // Уменьшаем отступ снизу
@@ -695,7 +1255,138 @@ impl eframe::App for MdReader {
     }
 }
 
+impl MdReader {
+    fn render_files_tab(&mut self, ui: &mut egui::Ui) {
+        let sort_label = match self.current_language {
+            Language::EN => "Sort:",
+            Language::RU => "Сортировка:",
+        };
+        let mut sort_changed = false;
+        ui.horizontal(|ui| {
+            ui.label(sort_label);
+            egui::ComboBox::from_id_source("sort_mode")
+                .selected_text(self.sort_mode.label(self.current_language))
+                .show_ui(ui, |ui| {
+                    for mode in SortMode::ALL {
+                        sort_changed |= ui.selectable_value(&mut self.sort_mode, mode, mode.label(self.current_language)).changed();
+                    }
+                });
+        });
+        if sort_changed {
+            self.sort_tree();
+        }
+
+        // Add a vertical ScrollArea
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            self.render_smart_folders(ui);
+            let categories = std::mem::take(&mut self.categories);
+            for mut category in categories {
+                self.render_category(ui, &mut category);
+                self.categories.push(category);
+            }
+
+            // Свободное место под деревом принимает категории в корень
+            let (rect, response) = ui.allocate_exact_size(
+                egui::vec2(ui.available_width(), ui.available_height().max(40.0)),
+                egui::Sense::hover(),
+            );
+            let root_drop = |paths: &[PathBuf]| {
+                paths.iter().all(|path| path.is_dir() && path.parent() != Some(self.root_dir.as_path()))
+            };
+            if response.dnd_hover_payload::<SidebarDrag>().is_some_and(|payload| root_drop(&payload.paths)) {
+                let stroke = egui::Stroke::new(2.0, ui.visuals().selection.bg_fill);
+                ui.painter().rect_stroke(rect.shrink(2.0), 8.0, stroke);
+            }
+            if let Some(payload) = response.dnd_release_payload::<SidebarDrag>() {
+                if root_drop(&payload.paths) {
+                    self.pending_action = Some(SidebarAction::Move {
+                        paths: payload.paths.clone(),
+                        target_dir: self.root_dir.clone(),
+                    });
+                }
+            }
+        });
+    }
+
+    fn render_editor(&mut self, ui: &mut egui::Ui, content_width: f32) {
+        let editor_id = egui::Id::new("note_editor");
+
+        // Выделяем совпадение, к которому перешли из поиска
+        let pending = self.highlight.as_ref()
+            .filter(|h| h.scroll_pending)
+            .and_then(Highlight::focused_range)
+            .map(|range| {
+                let start = self.file_content[..range.start].chars().count();
+                let end = start + self.file_content[range].chars().count();
+                CCursorRange::two(CCursor::new(start), CCursor::new(end))
+            });
+        if let Some(selection) = pending {
+            let mut state = egui::TextEdit::load_state(ui.ctx(), editor_id).unwrap_or_default();
+            state.cursor.set_char_range(Some(selection));
+            egui::TextEdit::store_state(ui.ctx(), editor_id, state);
+            // Пока открыта панель поиска, фокус остаётся в ней
+            if !self.find_bar.open {
+                ui.memory_mut(|m| m.request_focus(editor_id));
+            }
+        }
+
+        // Совпадения подсвечиваем и в редакторе: выделение видно только у поля в фокусе
+        let match_color = search::match_color();
+        let focused_color = ui.visuals().selection.bg_fill;
+        let highlights: Vec<(Range<usize>, Color32)> = self.highlight.iter()
+            .flat_map(|h| h.ranges.iter().enumerate().map(move |(i, range)| (range.clone(), i == h.focused)))
+            .map(|(range, focused)| (range, if focused { focused_color } else { match_color }))
+            .collect();
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let format = TextFormat {
+                font_id: TextStyle::Body.resolve(ui.style()),
+                color: ui.visuals().widgets.inactive.text_color(),
+                ..Default::default()
+            };
+            // После правки диапазоны могут устареть — пропускаем те, что режут символ
+            let ranges: Vec<(Range<usize>, Color32)> = highlights.iter()
+                .filter(|(range, _)| range.end <= text.len() && text.is_char_boundary(range.start) && text.is_char_boundary(range.end))
+                .cloned()
+                .collect();
+            let mut job = LayoutJob::default();
+            search::append_highlighted(&mut job, text, &ranges, format);
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|f| f.layout_job(job))
+        };
+
+        let mut editor = egui::TextEdit::multiline(&mut self.file_content)
+            .id(editor_id)
+            .desired_width(content_width)
+            .desired_rows(30)
+            .margin(egui::vec2(10.0, 10.0)); // Уменьшаем внутренние отступы
+        if !highlights.is_empty() {
+            editor = editor.layouter(&mut layouter);
+        }
+        let output = editor.show(ui);
+
+        if let Some(selection) = pending {
+            let rect = output.galley.pos_from_ccursor(selection.primary)
+                .translate(output.galley_pos.to_vec2());
+            ui.scroll_to_rect(rect, Some(egui::Align::Center));
+        }
+        
+        if output.response.changed() {
+            // Позиции совпадений после правки уже неверны
+            self.highlight = None;
+            if let Err(e) = self.save_file() {
+                eprintln!("Ошибка сохранения файла: {}", e);
+            }
+        }
+    }
+}
+
 fn main() -> Result<(), eframe::Error> {
+    // Подкоманды (export, search, check, index) и --tui работают без окна: в CI и по SSH
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1200.0, 800.0])
@@ -709,6 +1400,7 @@ fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(|cc| {
             MdReader::setup_fonts(&cc.egui_ctx);
+            clipboard::install_source_copy(&cc.egui_ctx);
             Box::new(MdReader::new())
         }),
     )
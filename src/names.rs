@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+
+use crate::Language;
+
+/// Characters that are not allowed in file names on at least one supported OS.
+const FORBIDDEN_CHARS: [char; 8] = ['<', '>', ':', '"', '\\', '|', '?', '*'];
+
+/// Device names Windows refuses to create, with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+pub enum NameError {
+    Empty,
+    ForbiddenChar(char),
+    OutsideWorkspace,
+    Reserved(String),
+    Hidden(String),
+    AlreadyExists(PathBuf),
+    Io(std::io::Error),
+}
+
+impl NameError {
+    pub fn message(&self, language: Language, root: &Path) -> String {
+        let display = |path: &PathBuf| path.strip_prefix(root).unwrap_or(path).display().to_string();
+        match (self, language) {
+            (NameError::Empty, Language::EN) => "Name can't be empty".to_string(),
+            (NameError::Empty, Language::RU) => "Имя не может быть пустым".to_string(),
+            (NameError::ForbiddenChar(c), Language::EN) => format!("Character '{}' is not allowed", c.escape_default()),
+            (NameError::ForbiddenChar(c), Language::RU) => format!("Символ '{}' недопустим", c.escape_default()),
+            (NameError::OutsideWorkspace, Language::EN) => "Path must stay inside the workspace".to_string(),
+            (NameError::OutsideWorkspace, Language::RU) => "Путь должен оставаться внутри рабочей папки".to_string(),
+            (NameError::Reserved(name), Language::EN) => format!("\"{}\" is a reserved name", name),
+            (NameError::Reserved(name), Language::RU) => format!("\"{}\" — зарезервированное имя", name),
+            (NameError::Hidden(name), Language::EN) => format!("\"{}\" would be hidden: names can't start with a dot", name),
+            (NameError::Hidden(name), Language::RU) => format!("\"{}\" будет скрыто: имя не может начинаться с точки", name),
+            (NameError::AlreadyExists(path), Language::EN) => format!("\"{}\" already exists", display(path)),
+            (NameError::AlreadyExists(path), Language::RU) => format!("\"{}\" уже существует", display(path)),
+            (NameError::Io(e), Language::EN) => format!("Failed to create: {}", e),
+            (NameError::Io(e), Language::RU) => format!("Не удалось создать: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for NameError {
+    fn from(e: std::io::Error) -> Self {
+        NameError::Io(e)
+    }
+}
+
+/// Checks a single path component and returns it trimmed.
+pub fn validate_component(name: &str) -> Result<String, NameError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(NameError::Empty);
+    }
+    if name == "." || name == ".." {
+        return Err(NameError::OutsideWorkspace);
+    }
+    if let Some(c) = name.chars().find(|c| FORBIDDEN_CHARS.contains(c) || c.is_control()) {
+        return Err(NameError::ForbiddenChar(c));
+    }
+    if name.starts_with('.') {
+        return Err(NameError::Hidden(name.to_string()));
+    }
+    // Windows молча отрезает точки в конце имени
+    if name.ends_with('.') {
+        return Err(NameError::ForbiddenChar('.'));
+    }
+    let base = name.split('.').next().unwrap_or(name).trim();
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(base)) {
+        return Err(NameError::Reserved(name.to_string()));
+    }
+    Ok(name.to_string())
+}
+
+/// Turns user input like `guides/setup/intro` into a path below `base_dir`.
+/// Absolute paths and `..` are rejected so nothing can be created outside `root`.
+pub fn resolve_new_path(root: &Path, base_dir: &Path, input: &str) -> Result<PathBuf, NameError> {
+    let input = input.trim();
+    if input.starts_with('/') || Path::new(input).is_absolute() {
+        return Err(NameError::OutsideWorkspace);
+    }
+
+    let mut path = base_dir.to_path_buf();
+    let mut has_components = false;
+    for component in input.split('/').filter(|component| !component.trim().is_empty()) {
+        path.push(validate_component(component)?);
+        has_components = true;
+    }
+    if !has_components {
+        return Err(NameError::Empty);
+    }
+    if !path.starts_with(root) {
+        return Err(NameError::OutsideWorkspace);
+    }
+    Ok(path)
+}
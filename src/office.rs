@@ -0,0 +1,781 @@
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use printpdf::image_crate;
+use pulldown_cmark::{Event, Parser, Tag};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::health::workspace_files;
+use crate::links::{self, NoteIndex};
+use crate::publish::{escaped, wiki_links_to_markdown};
+use crate::{markdown_options, tags};
+
+// Картинки без явного размера считаем по обычной экранной плотности
+const IMAGE_DPI: f32 = 96.0;
+// Ширина текста на странице A4 с полями по 2,5 см
+const MAX_IMAGE_WIDTH_INCHES: f32 = 6.2;
+const EMU_PER_INCH: f32 = 914_400.0;
+const HEADING_SIZES: [u32; 6] = [36, 32, 28, 26, 24, 22];
+
+#[derive(Clone, Default)]
+struct Span {
+    text: String,
+    bold: bool,
+    italic: bool,
+    code: bool,
+    link: Option<String>,
+}
+
+enum Inline {
+    Text(Span),
+    Break,
+    Image { path: PathBuf, alt: String },
+}
+
+enum Block {
+    Heading { level: usize, inlines: Vec<Inline> },
+    Paragraph(Vec<Inline>),
+    Code(String),
+    Quote(Vec<Block>),
+    List { start: Option<u64>, items: Vec<Vec<Block>> },
+    Rule,
+}
+
+enum Container {
+    Blocks(Vec<Block>),
+    List { start: Option<u64>, items: Vec<Vec<Block>> },
+}
+
+/// Collects the event stream into a block tree both document writers walk.
+struct Builder {
+    stack: Vec<Container>,
+    inlines: Vec<Inline>,
+    bold: usize,
+    italic: usize,
+    link: Option<String>,
+    image: Option<(PathBuf, String)>,
+    code: Option<String>,
+    heading: Option<usize>,
+}
+
+impl Builder {
+    fn push_block(&mut self, block: Block) {
+        match self.stack.last_mut() {
+            Some(Container::Blocks(blocks)) => blocks.push(block),
+            // Текст прямо в списке (без пункта) в CommonMark не встречается, но на всякий случай заводим пункт
+            Some(Container::List { items, .. }) => items.push(vec![block]),
+            None => {}
+        }
+    }
+
+    fn flush(&mut self) {
+        let inlines = std::mem::take(&mut self.inlines);
+        let blank = inlines.iter().all(|inline| matches!(inline, Inline::Text(span) if span.text.trim().is_empty()));
+        if !blank {
+            self.push_block(Block::Paragraph(inlines));
+        }
+    }
+
+    fn pop_blocks(&mut self) -> Vec<Block> {
+        match self.stack.pop() {
+            Some(Container::Blocks(blocks)) => blocks,
+            _ => Vec::new(),
+        }
+    }
+
+    fn text(&mut self, text: &str, code: bool) {
+        self.inlines.push(Inline::Text(Span {
+            text: text.to_string(),
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            code,
+            link: self.link.clone(),
+        }));
+    }
+
+    fn event(&mut self, note: &Path, event: Event) {
+        match event {
+            Event::Start(Tag::Paragraph) | Event::End(Tag::Paragraph) => self.flush(),
+            Event::Start(Tag::Heading(level, ..)) => {
+                self.flush();
+                self.heading = Some(level as usize);
+            }
+            Event::End(Tag::Heading(..)) => {
+                let level = self.heading.take().unwrap_or(1);
+                let inlines = std::mem::take(&mut self.inlines);
+                self.push_block(Block::Heading { level, inlines });
+            }
+            Event::Start(Tag::BlockQuote) | Event::Start(Tag::Item) => {
+                self.flush();
+                self.stack.push(Container::Blocks(Vec::new()));
+            }
+            Event::End(Tag::BlockQuote) => {
+                self.flush();
+                let blocks = self.pop_blocks();
+                self.push_block(Block::Quote(blocks));
+            }
+            Event::End(Tag::Item) => {
+                self.flush();
+                let blocks = self.pop_blocks();
+                if let Some(Container::List { items, .. }) = self.stack.last_mut() {
+                    items.push(blocks);
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                self.flush();
+                self.stack.push(Container::List { start, items: Vec::new() });
+            }
+            Event::End(Tag::List(_)) => {
+                self.flush();
+                if let Some(Container::List { start, items }) = self.stack.pop() {
+                    self.push_block(Block::List { start, items });
+                }
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.flush();
+                self.code = Some(String::new());
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some(code) = self.code.take() {
+                    self.push_block(Block::Code(code.trim_end_matches('\n').to_string()));
+                }
+            }
+            Event::Start(Tag::Emphasis) => self.italic += 1,
+            Event::End(Tag::Emphasis) => self.italic -= 1,
+            Event::Start(Tag::Strong) => self.bold += 1,
+            Event::End(Tag::Strong) => self.bold -= 1,
+            Event::Start(Tag::Link(_, destination, _)) => self.link = Some(destination.to_string()),
+            Event::End(Tag::Link(..)) => self.link = None,
+            Event::Start(Tag::Image(_, destination, _)) => {
+                let path = links::normalize(&note.parent().unwrap_or(Path::new("")).join(links::percent_decode(&destination)));
+                self.image = Some((path, String::new()));
+            }
+            Event::End(Tag::Image(..)) => {
+                if let Some((path, alt)) = self.image.take() {
+                    self.inlines.push(Inline::Image { path, alt });
+                }
+            }
+            Event::Text(text) => {
+                if let Some(code) = &mut self.code {
+                    code.push_str(&text);
+                } else if let Some((_, alt)) = &mut self.image {
+                    alt.push_str(&text);
+                } else {
+                    self.text(&text, false);
+                }
+            }
+            Event::Code(text) => self.text(&text, true),
+            Event::SoftBreak => self.text(" ", false),
+            Event::HardBreak => self.inlines.push(Inline::Break),
+            Event::Rule => {
+                self.flush();
+                self.push_block(Block::Rule);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The note as blocks, wiki links turned into markdown links first.
+fn document_blocks(root: &Path, note: &Path, content: &str) -> Vec<Block> {
+    let folder = note.parent().unwrap_or(root);
+    let index = NoteIndex::new(&links::markdown_files(root));
+    let attachments: Vec<PathBuf> = workspace_files(folder).into_iter()
+        .filter(|file| file.extension().is_none_or(|ext| ext != "md"))
+        .collect();
+    let markdown = wiki_links_to_markdown(root, folder, note, content, &index, &attachments);
+
+    let mut builder = Builder {
+        stack: vec![Container::Blocks(Vec::new())],
+        inlines: Vec::new(),
+        bold: 0,
+        italic: 0,
+        link: None,
+        image: None,
+        code: None,
+        heading: None,
+    };
+    let body_start = tags::front_matter_range(&markdown).map_or(0, |range| range.end);
+    for (event, range) in Parser::new_ext(&markdown, markdown_options()).into_offset_iter() {
+        if range.start >= body_start {
+            builder.event(note, event);
+        }
+    }
+    builder.flush();
+    builder.pop_blocks()
+}
+
+/// An embedded picture: where it comes from, its name inside the package and its size in inches.
+struct Media {
+    path: PathBuf,
+    name: String,
+    width: f32,
+    height: f32,
+}
+
+/// Registers an image for embedding; formats word processors can't show and unreadable files give `None`.
+fn add_media(media: &mut Vec<Media>, folder: &str, path: &Path) -> Option<usize> {
+    if let Some(index) = media.iter().position(|known| known.path == path) {
+        return Some(index);
+    }
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    if !matches!(extension.as_str(), "png" | "jpg" | "jpeg" | "gif" | "bmp") {
+        return None;
+    }
+    let (width, height) = image_crate::image_dimensions(path).ok()?;
+    let scale = (MAX_IMAGE_WIDTH_INCHES / (width as f32 / IMAGE_DPI)).min(1.0);
+    media.push(Media {
+        path: path.to_path_buf(),
+        name: format!("{}/image{}.{}", folder, media.len() + 1, extension),
+        width: width as f32 / IMAGE_DPI * scale,
+        height: height as f32 / IMAGE_DPI * scale,
+    });
+    Some(media.len() - 1)
+}
+
+fn image_mime(name: &str) -> &'static str {
+    match name.rsplit('.').next().unwrap_or("") {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        _ => "image/jpeg",
+    }
+}
+
+fn zip_package(files: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, bytes) in files {
+        // ODF требует, чтобы mimetype лежал первым и без сжатия
+        let method = if name == "mimetype" { CompressionMethod::Stored } else { CompressionMethod::Deflated };
+        zip.start_file(name, SimpleFileOptions::default().compression_method(method)).map_err(|e| e.to_string())?;
+        zip.write_all(&bytes).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map(Cursor::into_inner).map_err(|e| e.to_string())
+}
+
+fn media_files(media: &[Media], prefix: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+    media.iter()
+        .map(|item| {
+            fs::read(&item.path)
+                .map(|bytes| (format!("{}{}", prefix, item.name), bytes))
+                .map_err(|e| format!("{}: {}", item.path.display(), e))
+        })
+        .collect()
+}
+
+const DOCX_CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Default Extension="png" ContentType="image/png"/>
+<Default Extension="jpg" ContentType="image/jpeg"/>
+<Default Extension="jpeg" ContentType="image/jpeg"/>
+<Default Extension="gif" ContentType="image/gif"/>
+<Default Extension="bmp" ContentType="image/bmp"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/>
+</Types>
+"#;
+
+const DOCX_PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+</Relationships>
+"#;
+
+const RELATIONSHIP_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/";
+
+fn docx_styles(language: &str) -> String {
+    let mut headings = String::new();
+    for (i, size) in HEADING_SIZES.iter().enumerate() {
+        headings.push_str(&format!(
+            "<w:style w:type=\"paragraph\" w:styleId=\"Heading{level}\"><w:name w:val=\"heading {level}\"/><w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/><w:uiPriority w:val=\"9\"/><w:qFormat/><w:pPr><w:keepNext/><w:keepLines/><w:spacing w:before=\"240\" w:after=\"120\"/><w:outlineLvl w:val=\"{outline}\"/></w:pPr><w:rPr><w:b/><w:sz w:val=\"{size}\"/></w:rPr></w:style>\n",
+            level = i + 1,
+            outline = i,
+            size = size,
+        ));
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Calibri" w:cs="Calibri"/><w:sz w:val="22"/><w:lang w:val="{language}"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="120" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style>
+{headings}<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="720"/></w:pPr><w:rPr><w:i/><w:color w:val="595959"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="SourceCode"><w:name w:val="Source Code"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/><w:spacing w:after="120" w:line="240" w:lineRule="auto"/></w:pPr><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas" w:cs="Courier New"/><w:sz w:val="20"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:ind w:left="720"/><w:contextualSpacing/></w:pPr></w:style>
+<w:style w:type="character" w:styleId="VerbatimChar"><w:name w:val="Verbatim Char"/><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas" w:cs="Courier New"/><w:sz w:val="20"/><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style>
+</w:styles>
+"#,
+        language = language,
+        headings = headings,
+    )
+}
+
+fn docx_numbering(nums: &[(bool, u64)]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:numbering xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\n");
+    for (id, ordered) in [(0, false), (1, true)] {
+        xml.push_str(&format!("<w:abstractNum w:abstractNumId=\"{}\"><w:multiLevelType w:val=\"hybridMultilevel\"/>", id));
+        for level in 0..9 {
+            let (format, text) = if ordered { ("decimal", format!("%{}.", level + 1)) } else { ("bullet", "•".to_string()) };
+            xml.push_str(&format!(
+                "<w:lvl w:ilvl=\"{level}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"{format}\"/><w:lvlText w:val=\"{text}\"/><w:lvlJc w:val=\"left\"/><w:pPr><w:ind w:left=\"{indent}\" w:hanging=\"360\"/></w:pPr></w:lvl>",
+                level = level,
+                format = format,
+                text = text,
+                indent = 720 * (level + 1),
+            ));
+        }
+        xml.push_str("</w:abstractNum>\n");
+    }
+    // Каждый список — свой экземпляр нумерации, иначе номера продолжались бы из предыдущего списка
+    for (i, (ordered, start)) in nums.iter().enumerate() {
+        xml.push_str(&format!("<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"{}\"/>", i + 1, u8::from(*ordered)));
+        if *ordered {
+            for level in 0..9 {
+                xml.push_str(&format!("<w:lvlOverride w:ilvl=\"{}\"><w:startOverride w:val=\"{}\"/></w:lvlOverride>", level, start));
+            }
+        }
+        xml.push_str("</w:num>\n");
+    }
+    xml.push_str("</w:numbering>\n");
+    xml
+}
+
+/// Text for a `<w:t>` run; tabs and line breaks become their own elements.
+fn docx_text(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            line.split('\t')
+                .map(|part| format!("<w:t xml:space=\"preserve\">{}</w:t>", escaped(part)))
+                .collect::<Vec<_>>()
+                .join("<w:tab/>")
+        })
+        .collect::<Vec<_>>()
+        .join("<w:br/>")
+}
+
+/// A Word document being written: the body plus the parts it refers to.
+struct Docx {
+    body: String,
+    /// Relationship id, type and target of images and links.
+    relationships: Vec<(String, &'static str, String)>,
+    media: Vec<Media>,
+    /// Ordered flag and start number of every list.
+    nums: Vec<(bool, u64)>,
+    /// Drawings written so far; each one needs its own id, even when it shows an image already embedded.
+    drawings: usize,
+}
+
+impl Docx {
+    fn relationship(&mut self, kind: &'static str, target: String) -> String {
+        if let Some((id, ..)) = self.relationships.iter().find(|(_, known_kind, known)| *known_kind == kind && *known == target) {
+            return id.clone();
+        }
+        // rId1 и rId2 заняты стилями и нумерацией
+        let id = format!("rId{}", self.relationships.len() + 3);
+        self.relationships.push((id.clone(), kind, target));
+        id
+    }
+
+    fn runs(&mut self, inlines: &[Inline], bold: bool) -> String {
+        let mut xml = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(span) => {
+                    let link = span.link.as_ref().filter(|link| links::is_external(link));
+                    let mut properties = String::new();
+                    if link.is_some() {
+                        properties.push_str("<w:rStyle w:val=\"Hyperlink\"/>");
+                    } else if span.code {
+                        properties.push_str("<w:rStyle w:val=\"VerbatimChar\"/>");
+                    }
+                    if span.bold || bold {
+                        properties.push_str("<w:b/>");
+                    }
+                    if span.italic {
+                        properties.push_str("<w:i/>");
+                    }
+                    if !properties.is_empty() {
+                        properties = format!("<w:rPr>{}</w:rPr>", properties);
+                    }
+                    let run = format!("<w:r>{}{}</w:r>", properties, docx_text(&span.text));
+                    match link {
+                        Some(link) => {
+                            let id = self.relationship("hyperlink", link.clone());
+                            xml.push_str(&format!("<w:hyperlink r:id=\"{}\">{}</w:hyperlink>", id, run));
+                        }
+                        None => xml.push_str(&run),
+                    }
+                }
+                Inline::Break => xml.push_str("<w:r><w:br/></w:r>"),
+                Inline::Image { path, alt } => {
+                    let Some(index) = add_media(&mut self.media, "media", path) else {
+                        xml.push_str(&format!("<w:r><w:t xml:space=\"preserve\">[{}]</w:t></w:r>", escaped(alt)));
+                        continue;
+                    };
+                    let (name, width, height) = {
+                        let media = &self.media[index];
+                        (media.name.clone(), (media.width * EMU_PER_INCH) as u64, (media.height * EMU_PER_INCH) as u64)
+                    };
+                    let id = self.relationship("image", name.clone());
+                    self.drawings += 1;
+                    let picture = self.drawings;
+                    xml.push_str(&format!(
+                        "<w:r><w:drawing><wp:inline distT=\"0\" distB=\"0\" distL=\"0\" distR=\"0\"><wp:extent cx=\"{width}\" cy=\"{height}\"/><wp:docPr id=\"{picture}\" name=\"Picture {picture}\" descr=\"{alt}\"/><a:graphic xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\"><a:graphicData uri=\"http://schemas.openxmlformats.org/drawingml/2006/picture\"><pic:pic xmlns:pic=\"http://schemas.openxmlformats.org/drawingml/2006/picture\"><pic:nvPicPr><pic:cNvPr id=\"{picture}\" name=\"{name}\"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed=\"{id}\"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"{width}\" cy=\"{height}\"/></a:xfrm><a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>",
+                        width = width,
+                        height = height,
+                        picture = picture,
+                        alt = escaped(alt),
+                        name = escaped(&name),
+                        id = id,
+                    ));
+                }
+            }
+        }
+        xml
+    }
+
+    /// Writes blocks; `list` is the depth inside lists and `number` the numbering for the first paragraph of a list item.
+    fn blocks(&mut self, blocks: &[Block], quote: bool, list: Option<usize>, mut number: Option<(usize, usize)>) {
+        for block in blocks {
+            let number = number.take();
+            let paragraph_properties = |style: Option<&str>| {
+                let style = match (number, list, style) {
+                    (Some(_), _, _) | (None, Some(_), None) => Some("ListParagraph"),
+                    (None, _, Some(style)) => Some(style),
+                    (None, None, None) => quote.then_some("Quote"),
+                };
+                let mut properties = style.map(|style| format!("<w:pStyle w:val=\"{}\"/>", style)).unwrap_or_default();
+                if let Some((num, level)) = number {
+                    properties.push_str(&format!("<w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{}\"/></w:numPr>", level, num));
+                } else if let Some(level) = list {
+                    properties.push_str(&format!("<w:ind w:left=\"{}\"/>", 720 * (level + 1)));
+                }
+                if properties.is_empty() { properties } else { format!("<w:pPr>{}</w:pPr>", properties) }
+            };
+            match block {
+                Block::Heading { level, inlines } => {
+                    let properties = paragraph_properties(Some(&format!("Heading{}", level)));
+                    let runs = self.runs(inlines, false);
+                    self.body.push_str(&format!("<w:p>{}{}</w:p>\n", properties, runs));
+                }
+                Block::Paragraph(inlines) => {
+                    let properties = paragraph_properties(None);
+                    let runs = self.runs(inlines, false);
+                    self.body.push_str(&format!("<w:p>{}{}</w:p>\n", properties, runs));
+                }
+                Block::Code(code) => {
+                    let properties = paragraph_properties(Some("SourceCode"));
+                    self.body.push_str(&format!("<w:p>{}<w:r>{}</w:r></w:p>\n", properties, docx_text(code)));
+                }
+                Block::Quote(blocks) => self.blocks(blocks, true, list, number),
+                Block::List { start, items } => {
+                    self.nums.push((start.is_some(), start.unwrap_or(1)));
+                    let num = self.nums.len();
+                    let level = list.map_or(0, |level| level + 1);
+                    for item in items {
+                        self.blocks(item, quote, Some(level), Some((num, level)));
+                    }
+                }
+                Block::Rule => {
+                    self.body.push_str("<w:p><w:pPr><w:pBdr><w:bottom w:val=\"single\" w:sz=\"6\" w:space=\"1\" w:color=\"auto\"/></w:pBdr></w:pPr></w:p>\n");
+                }
+            }
+        }
+    }
+}
+
+/// The note as a Word document. `language` is a tag like `en-US` used for spell checking.
+pub fn note_to_docx(root: &Path, note: &Path, content: &str, language: &str) -> Result<Vec<u8>, String> {
+    let mut docx = Docx { body: String::new(), relationships: Vec::new(), media: Vec::new(), nums: Vec::new(), drawings: 0 };
+    docx.blocks(&document_blocks(root, note, content), false, None, None);
+
+    let document = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\" xmlns:wp=\"http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing\">\n<w:body>\n{}<w:sectPr><w:pgSz w:w=\"11906\" w:h=\"16838\"/><w:pgMar w:top=\"1440\" w:right=\"1440\" w:bottom=\"1440\" w:left=\"1440\" w:header=\"708\" w:footer=\"708\" w:gutter=\"0\"/></w:sectPr>\n</w:body>\n</w:document>\n",
+        docx.body,
+    );
+    let mut relationships = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n<Relationship Id=\"rId1\" Type=\"{0}styles\" Target=\"styles.xml\"/>\n<Relationship Id=\"rId2\" Type=\"{0}numbering\" Target=\"numbering.xml\"/>\n",
+        RELATIONSHIP_TYPE,
+    );
+    for (id, kind, target) in &docx.relationships {
+        let mode = if *kind == "hyperlink" { " TargetMode=\"External\"" } else { "" };
+        relationships.push_str(&format!("<Relationship Id=\"{}\" Type=\"{}{}\" Target=\"{}\"{}/>\n", id, RELATIONSHIP_TYPE, kind, escaped(target), mode));
+    }
+    relationships.push_str("</Relationships>\n");
+
+    let mut files = vec![
+        ("[Content_Types].xml".to_string(), DOCX_CONTENT_TYPES.as_bytes().to_vec()),
+        ("_rels/.rels".to_string(), DOCX_PACKAGE_RELS.as_bytes().to_vec()),
+        ("word/document.xml".to_string(), document.into_bytes()),
+        ("word/styles.xml".to_string(), docx_styles(language).into_bytes()),
+        ("word/numbering.xml".to_string(), docx_numbering(&docx.nums).into_bytes()),
+        ("word/_rels/document.xml.rels".to_string(), relationships.into_bytes()),
+    ];
+    files.extend(media_files(&docx.media, "word/")?);
+    zip_package(files)
+}
+
+const ODT_MIME: &str = "application/vnd.oasis.opendocument.text";
+
+const ODT_NAMESPACES: &str = r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:draw="urn:oasis:names:tc:opendocument:xmlns:drawing:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:svg="urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0" office:version="1.3""#;
+
+fn odt_styles(language: &str) -> String {
+    let (lang, country) = language.split_once('-').unwrap_or((language, ""));
+    let mut headings = String::new();
+    for (i, size) in HEADING_SIZES.iter().enumerate() {
+        headings.push_str(&format!(
+            "<style:style style:name=\"Heading_20_{level}\" style:display-name=\"Heading {level}\" style:family=\"paragraph\" style:parent-style-name=\"Heading\" style:next-style-name=\"Text_20_body\" style:default-outline-level=\"{level}\" style:class=\"text\"><style:text-properties fo:font-size=\"{size}pt\" fo:font-weight=\"bold\"/></style:style>\n",
+            level = i + 1,
+            size = size / 2,
+        ));
+    }
+    let list_levels = |ordered: bool| {
+        (1..=10)
+            .map(|level| {
+                let properties = format!(
+                    "<style:list-level-properties text:list-level-position-and-space-mode=\"label-alignment\"><style:list-level-label-alignment text:label-followed-by=\"listtab\" text:list-tab-stop-position=\"{indent}in\" fo:text-indent=\"-0.25in\" fo:margin-left=\"{indent}in\"/></style:list-level-properties>",
+                    indent = 0.5 * level as f32,
+                );
+                if ordered {
+                    format!("<text:list-level-style-number text:level=\"{}\" style:num-suffix=\".\" style:num-format=\"1\">{}</text:list-level-style-number>", level, properties)
+                } else {
+                    format!("<text:list-level-style-bullet text:level=\"{}\" text:bullet-char=\"•\">{}</text:list-level-style-bullet>", level, properties)
+                }
+            })
+            .collect::<String>()
+    };
+    format!(
+        r##"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-styles {namespaces}>
+<office:styles>
+<style:default-style style:family="paragraph"><style:paragraph-properties fo:margin-bottom="0.08in"/><style:text-properties style:font-name="Liberation Sans" fo:font-family="'Liberation Sans', Arial" fo:font-size="11pt" fo:language="{lang}" fo:country="{country}"/></style:default-style>
+<style:style style:name="Standard" style:family="paragraph" style:class="text"/>
+<style:style style:name="Text_20_body" style:display-name="Text body" style:family="paragraph" style:parent-style-name="Standard" style:class="text"><style:paragraph-properties fo:margin-top="0in" fo:margin-bottom="0.08in" fo:line-height="115%"/></style:style>
+<style:style style:name="Heading" style:family="paragraph" style:parent-style-name="Standard" style:next-style-name="Text_20_body" style:class="text"><style:paragraph-properties fo:margin-top="0.17in" fo:margin-bottom="0.08in" fo:keep-with-next="always"/></style:style>
+{headings}<style:style style:name="Quotations" style:family="paragraph" style:parent-style-name="Standard" style:class="html"><style:paragraph-properties fo:margin-left="0.4in" fo:margin-bottom="0.08in"/><style:text-properties fo:font-style="italic" fo:color="#595959"/></style:style>
+<style:style style:name="Preformatted_20_Text" style:display-name="Preformatted Text" style:family="paragraph" style:parent-style-name="Standard" style:class="html"><style:paragraph-properties fo:margin-bottom="0.08in" fo:background-color="#f2f2f2" fo:padding="0.04in"/><style:text-properties fo:font-family="'Liberation Mono', 'Courier New'" style:font-family-generic="modern" style:font-pitch="fixed" fo:font-size="10pt"/></style:style>
+<style:style style:name="Horizontal_20_Line" style:display-name="Horizontal Line" style:family="paragraph" style:parent-style-name="Standard" style:class="html"><style:paragraph-properties fo:margin-bottom="0.1in" fo:border-bottom="0.5pt solid #808080" fo:padding="0in"/><style:text-properties fo:font-size="6pt"/></style:style>
+<style:style style:name="Emphasis" style:family="text"><style:text-properties fo:font-style="italic"/></style:style>
+<style:style style:name="Strong_20_Emphasis" style:display-name="Strong Emphasis" style:family="text"><style:text-properties fo:font-weight="bold"/></style:style>
+<style:style style:name="Source_20_Text" style:display-name="Source Text" style:family="text"><style:text-properties fo:font-family="'Liberation Mono', 'Courier New'" style:font-family-generic="modern" style:font-pitch="fixed" fo:background-color="#f2f2f2"/></style:style>
+<style:style style:name="Internet_20_link" style:display-name="Internet link" style:family="text"><style:text-properties fo:color="#0563c1" style:text-underline-style="solid" style:text-underline-width="auto" style:text-underline-color="font-color"/></style:style>
+<text:list-style style:name="Bullet">{bullets}</text:list-style>
+<text:list-style style:name="Numbering">{numbers}</text:list-style>
+</office:styles>
+<office:automatic-styles>
+<style:page-layout style:name="Page"><style:page-layout-properties fo:page-width="21cm" fo:page-height="29.7cm" fo:margin-top="2.5cm" fo:margin-bottom="2.5cm" fo:margin-left="2.5cm" fo:margin-right="2.5cm"/></style:page-layout>
+</office:automatic-styles>
+<office:master-styles>
+<style:master-page style:name="Standard" style:page-layout-name="Page"/>
+</office:master-styles>
+</office:document-styles>
+"##,
+        namespaces = ODT_NAMESPACES,
+        lang = lang,
+        country = if country.is_empty() { "none" } else { country },
+        headings = headings,
+        bullets = list_levels(false),
+        numbers = list_levels(true),
+    )
+}
+
+fn push_spaces(xml: &mut String, spaces: &mut usize, at_start: bool) {
+    if *spaces == 0 {
+        return;
+    }
+    let collapsed = if at_start { *spaces } else { *spaces - 1 };
+    if !at_start {
+        xml.push(' ');
+    }
+    match collapsed {
+        0 => {}
+        1 => xml.push_str("<text:s/>"),
+        count => xml.push_str(&format!("<text:s text:c=\"{}\"/>", count)),
+    }
+    *spaces = 0;
+}
+
+/// ODF collapses runs of spaces, so all but the first are written as `<text:s/>`; a leading space is kept too.
+fn odt_text(text: &str) -> String {
+    let mut xml = String::new();
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            xml.push_str("<text:line-break/>");
+        }
+        let mut spaces = 0;
+        let mut at_start = true;
+        for c in line.chars() {
+            match c {
+                ' ' => spaces += 1,
+                '\t' => {
+                    push_spaces(&mut xml, &mut spaces, at_start);
+                    xml.push_str("<text:tab/>");
+                    at_start = false;
+                }
+                c => {
+                    push_spaces(&mut xml, &mut spaces, at_start);
+                    at_start = false;
+                    xml.push_str(&escaped(&c.to_string()));
+                }
+            }
+        }
+        push_spaces(&mut xml, &mut spaces, at_start);
+    }
+    xml
+}
+
+/// An OpenDocument text being written.
+struct Odt {
+    body: String,
+    media: Vec<Media>,
+    /// Frames written so far, for unique frame names.
+    frames: usize,
+}
+
+impl Odt {
+    fn runs(&mut self, inlines: &[Inline]) -> String {
+        let mut xml = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(span) => {
+                    let mut text = odt_text(&span.text);
+                    for (applies, style) in [(span.code, "Source_20_Text"), (span.italic, "Emphasis"), (span.bold, "Strong_20_Emphasis")] {
+                        if applies {
+                            text = format!("<text:span text:style-name=\"{}\">{}</text:span>", style, text);
+                        }
+                    }
+                    match span.link.as_ref().filter(|link| links::is_external(link)) {
+                        Some(link) => xml.push_str(&format!(
+                            "<text:a xlink:type=\"simple\" xlink:href=\"{}\" text:style-name=\"Internet_20_link\">{}</text:a>",
+                            escaped(link),
+                            text,
+                        )),
+                        None => xml.push_str(&text),
+                    }
+                }
+                Inline::Break => xml.push_str("<text:line-break/>"),
+                Inline::Image { path, alt } => {
+                    let Some(index) = add_media(&mut self.media, "Pictures", path) else {
+                        xml.push_str(&format!("[{}]", escaped(alt)));
+                        continue;
+                    };
+                    let media = &self.media[index];
+                    self.frames += 1;
+                    xml.push_str(&format!(
+                        "<draw:frame draw:name=\"Image{}\" text:anchor-type=\"as-char\" svg:width=\"{:.3}in\" svg:height=\"{:.3}in\"><draw:image xlink:href=\"{}\" xlink:type=\"simple\" xlink:show=\"embed\" xlink:actuate=\"onLoad\"/><svg:desc>{}</svg:desc></draw:frame>",
+                        self.frames,
+                        media.width,
+                        media.height,
+                        escaped(&media.name),
+                        escaped(alt),
+                    ));
+                }
+            }
+        }
+        xml
+    }
+
+    /// Writes blocks; `in_list` is set inside list items, whose nested lists inherit the outer list style.
+    fn blocks(&mut self, blocks: &[Block], quote: bool, in_list: bool) {
+        let paragraph_style = if quote { "Quotations" } else { "Text_20_body" };
+        for block in blocks {
+            match block {
+                Block::Heading { level, inlines } => {
+                    let runs = self.runs(inlines);
+                    self.body.push_str(&format!("<text:h text:style-name=\"Heading_20_{0}\" text:outline-level=\"{0}\">{1}</text:h>\n", level, runs));
+                }
+                Block::Paragraph(inlines) => {
+                    let runs = self.runs(inlines);
+                    self.body.push_str(&format!("<text:p text:style-name=\"{}\">{}</text:p>\n", paragraph_style, runs));
+                }
+                Block::Code(code) => {
+                    self.body.push_str(&format!("<text:p text:style-name=\"Preformatted_20_Text\">{}</text:p>\n", odt_text(code)));
+                }
+                Block::Quote(blocks) => self.blocks(blocks, true, in_list),
+                Block::List { start, items } => {
+                    let style = if start.is_some() { "Numbering" } else { "Bullet" };
+                    // Стиль задаём только внешнему списку, вложенные его наследуют
+                    if in_list {
+                        self.body.push_str("<text:list>\n");
+                    } else {
+                        self.body.push_str(&format!("<text:list text:style-name=\"{}\">\n", style));
+                    }
+                    for (i, item) in items.iter().enumerate() {
+                        match start {
+                            Some(start) if i == 0 && *start != 1 => self.body.push_str(&format!("<text:list-item text:start-value=\"{}\">", start)),
+                            _ => self.body.push_str("<text:list-item>"),
+                        }
+                        if item.is_empty() {
+                            self.body.push_str("<text:p/>");
+                        }
+                        self.blocks(item, quote, true);
+                        self.body.push_str("</text:list-item>\n");
+                    }
+                    self.body.push_str("</text:list>\n");
+                }
+                Block::Rule => self.body.push_str("<text:p text:style-name=\"Horizontal_20_Line\"/>\n"),
+            }
+        }
+    }
+}
+
+/// The note as an OpenDocument text. `language` is a tag like `en-US`.
+pub fn note_to_odt(root: &Path, note: &Path, content: &str, language: &str) -> Result<Vec<u8>, String> {
+    let mut odt = Odt { body: String::new(), media: Vec::new(), frames: 0 };
+    odt.blocks(&document_blocks(root, note, content), false, false);
+
+    let content_xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<office:document-content {}>\n<office:automatic-styles/>\n<office:body>\n<office:text>\n{}</office:text>\n</office:body>\n</office:document-content>\n",
+        ODT_NAMESPACES,
+        odt.body,
+    );
+
+    let mut manifest = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<manifest:manifest xmlns:manifest=\"urn:oasis:names:tc:opendocument:xmlns:manifest:1.0\" manifest:version=\"1.3\">\n<manifest:file-entry manifest:full-path=\"/\" manifest:version=\"1.3\" manifest:media-type=\"{}\"/>\n<manifest:file-entry manifest:full-path=\"content.xml\" manifest:media-type=\"text/xml\"/>\n<manifest:file-entry manifest:full-path=\"styles.xml\" manifest:media-type=\"text/xml\"/>\n",
+        ODT_MIME,
+    );
+    for media in &odt.media {
+        manifest.push_str(&format!("<manifest:file-entry manifest:full-path=\"{}\" manifest:media-type=\"{}\"/>\n", escaped(&media.name), image_mime(&media.name)));
+    }
+    manifest.push_str("</manifest:manifest>\n");
+
+    let mut files = vec![
+        ("mimetype".to_string(), ODT_MIME.as_bytes().to_vec()),
+        ("META-INF/manifest.xml".to_string(), manifest.into_bytes()),
+        ("content.xml".to_string(), content_xml.into_bytes()),
+        ("styles.xml".to_string(), odt_styles(language).into_bytes()),
+    ];
+    files.extend(media_files(&odt.media, "")?);
+    zip_package(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn repeated_image_gets_a_drawing_id_per_use() {
+        let root = std::env::temp_dir().join(format!("mdreader-office-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        image_crate::RgbImage::new(2, 2).save(root.join("dot.png")).unwrap();
+        let note = root.join("note.md");
+        let content = "![one](dot.png)\n\n![two](dot.png)\n";
+        fs::write(&note, content).unwrap();
+
+        let docx = note_to_docx(&root, &note, content, "en-US").unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(docx)).unwrap();
+        let mut document = String::new();
+        archive.by_name("word/document.xml").unwrap().read_to_string(&mut document).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(document.contains("<wp:docPr id=\"1\"") && document.contains("<wp:docPr id=\"2\""));
+        assert_eq!(document.matches("<pic:cNvPr id=\"2\"").count(), 1);
+        assert_eq!(archive.len(), 7, "the image is embedded once");
+    }
+}
@@ -0,0 +1,645 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use eframe::egui::{self, Color32};
+use printpdf::image_crate::{self, DynamicImage, RgbImage};
+use printpdf::lopdf::{self, Object, ObjectId, StringFormat};
+use printpdf::{Color, Image, ImageTransform, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Rect, Rgb};
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use syntect::easy::HighlightLines;
+use syntect::util::LinesWithEndings;
+use ttf_parser::Face;
+
+use crate::export::{self, Palette};
+use crate::{links, markdown_options, tags};
+
+const ROBOTO: &[u8] = include_bytes!("../assets/Roboto-Regular.ttf");
+const PT_TO_MM: f32 = 25.4 / 72.0;
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.0;
+const HEADING_SIZES: [f32; 6] = [20.0, 16.0, 14.0, 12.0, 11.0, 10.0];
+const LINE_SPACING: f32 = 1.45;
+// Отступ на каждый уровень списка и цитаты
+const INDENT: f32 = 6.0;
+// Картинки без явного размера печатаем как на экране с обычной плотностью
+const IMAGE_DPI: f32 = 96.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PageSize {
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    pub const ALL: [PageSize; 4] = [PageSize::A4, PageSize::A5, PageSize::Letter, PageSize::Legal];
+
+    /// Width and height in millimeters.
+    fn size(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::A5 => (148.0, 210.0),
+            PageSize::Letter => (215.9, 279.4),
+            PageSize::Legal => (215.9, 355.6),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PageSize::A4 => "A4",
+            PageSize::A5 => "A5",
+            PageSize::Letter => "Letter",
+            PageSize::Legal => "Legal",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct PdfOptions {
+    pub page_size: PageSize,
+    /// Margin on every side, in millimeters.
+    pub margin: f32,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self { page_size: PageSize::A4, margin: 20.0 }
+    }
+}
+
+/// The monospace font egui ships with, used for code.
+fn hack_font() -> &'static [u8] {
+    static FONT: OnceLock<Vec<u8>> = OnceLock::new();
+    FONT.get_or_init(|| {
+        egui::FontDefinitions::default().font_data
+            .get("Hack")
+            .map(|data| data.font.to_vec())
+            .unwrap_or_default()
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Text,
+    Code,
+    Link,
+}
+
+#[derive(Clone)]
+struct Run {
+    text: String,
+    style: Style,
+    /// Syntax highlighting color inside code blocks.
+    color: Option<Color32>,
+}
+
+enum Block {
+    Heading { level: usize, text: String },
+    Paragraph { runs: Vec<Run>, indent: f32, marker: Option<String>, quote: bool },
+    Code(Vec<Vec<Run>>),
+    Image { path: PathBuf, alt: String },
+    Rule,
+    PageBreak,
+}
+
+/// Code lines as colored runs, highlighted like in the HTML export.
+fn highlighted_lines(code: &str, language: &str) -> Vec<Vec<Run>> {
+    let syntaxes = export::syntax_set();
+    let syntax = syntaxes.find_syntax_by_token(language)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, &export::theme_set().themes["InspiredGitHub"]);
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let Ok(ranges) = highlighter.highlight_line(line, syntaxes) else {
+                return vec![Run { text: line.trim_end().replace('\t', "    "), style: Style::Code, color: None }];
+            };
+            ranges.into_iter()
+                .map(|(style, text)| Run {
+                    text: text.trim_end_matches(['\n', '\r']).replace('\t', "    "),
+                    style: Style::Code,
+                    color: Some(Color32::from_rgb(style.foreground.r, style.foreground.g, style.foreground.b)),
+                })
+                .filter(|run| !run.text.is_empty())
+                .collect()
+        })
+        .collect()
+}
+
+/// Splits a note into the blocks the PDF is laid out from.
+fn note_blocks(note: &Path, content: &str, blocks: &mut Vec<Block>) {
+    let body_start = tags::front_matter_range(content).map_or(0, |range| range.end);
+    let mut runs: Vec<Run> = Vec::new();
+    let mut heading: Option<(usize, String)> = None;
+    let mut code: Option<(String, String)> = None;
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut marker: Option<String> = None;
+    let mut quote = 0;
+    let mut link = 0;
+    let mut image = 0;
+
+    let flush = |runs: &mut Vec<Run>, marker: &mut Option<String>, blocks: &mut Vec<Block>, lists: usize, quote: usize| {
+        if runs.iter().all(|run| run.text.trim().is_empty()) {
+            runs.clear();
+            return;
+        }
+        blocks.push(Block::Paragraph {
+            runs: std::mem::take(runs),
+            indent: (lists + quote) as f32 * INDENT,
+            marker: marker.take(),
+            quote: quote > 0,
+        });
+    };
+
+    for (event, range) in Parser::new_ext(content, markdown_options()).into_offset_iter() {
+        if range.start < body_start {
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Heading(level, ..)) => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                heading = Some((level as usize, String::new()));
+            }
+            Event::End(Tag::Heading(..)) => {
+                if let Some((level, text)) = heading.take() {
+                    blocks.push(Block::Heading { level, text: text.trim().to_string() });
+                }
+            }
+            Event::End(Tag::Paragraph) | Event::End(Tag::Item) | Event::HardBreak => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+            }
+            Event::Start(Tag::BlockQuote) => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                quote += 1;
+            }
+            Event::End(Tag::BlockQuote) => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                quote -= 1;
+            }
+            Event::Start(Tag::List(start)) => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                lists.push(start);
+            }
+            Event::End(Tag::List(_)) => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                marker = Some(match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                });
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((language, String::new()));
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some((language, buffer)) = code.take() {
+                    blocks.push(Block::Code(highlighted_lines(&buffer, &language)));
+                }
+            }
+            Event::Start(Tag::Link(..)) => link += 1,
+            Event::End(Tag::Link(..)) => link -= 1,
+            Event::Start(Tag::Image(_, destination, _)) => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                image += 1;
+                let path = links::normalize(&note.parent().unwrap_or(Path::new("")).join(links::percent_decode(&destination)));
+                blocks.push(Block::Image { path, alt: String::new() });
+            }
+            Event::End(Tag::Image(..)) => image -= 1,
+            Event::Rule => {
+                flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+                blocks.push(Block::Rule);
+            }
+            Event::Text(text) => {
+                if let Some((_, buffer)) = &mut code {
+                    buffer.push_str(&text);
+                } else if let Some((_, heading)) = &mut heading {
+                    heading.push_str(&text);
+                } else if image > 0 {
+                    if let Some(Block::Image { alt, .. }) = blocks.last_mut() {
+                        alt.push_str(&text);
+                    }
+                } else {
+                    let style = if link > 0 { Style::Link } else { Style::Text };
+                    runs.push(Run { text: text.to_string(), style, color: None });
+                }
+            }
+            Event::Code(text) => {
+                if let Some((_, heading)) = &mut heading {
+                    heading.push_str(&text);
+                } else {
+                    runs.push(Run { text: text.to_string(), style: Style::Code, color: None });
+                }
+            }
+            Event::SoftBreak => runs.push(Run { text: " ".to_string(), style: Style::Text, color: None }),
+            _ => {}
+        }
+    }
+    flush(&mut runs, &mut marker, blocks, lists.len(), quote);
+}
+
+struct PdfFont {
+    face: Face<'static>,
+    font: IndirectFontRef,
+}
+
+impl PdfFont {
+    /// Width of `text` in millimeters; characters missing from the font are skipped, as printpdf does.
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let units: u32 = text.chars()
+            .filter_map(|c| self.face.glyph_index(c))
+            .map(|glyph| u32::from(self.face.glyph_hor_advance(glyph).unwrap_or(0)))
+            .sum();
+        units as f32 / f32::from(self.face.units_per_em()) * size * PT_TO_MM
+    }
+}
+
+/// A heading that becomes a PDF bookmark.
+struct OutlineEntry {
+    level: usize,
+    title: String,
+    page: usize,
+    /// Top of the heading, in millimeters from the bottom of the page.
+    y: f32,
+}
+
+fn pdf_color(color: Color32) -> Color {
+    Color::Rgb(Rgb::new(f32::from(color.r()) / 255.0, f32::from(color.g()) / 255.0, f32::from(color.b()) / 255.0, None))
+}
+
+/// Lays blocks out top to bottom, starting new pages as they fill up.
+struct Writer<'a> {
+    doc: &'a PdfDocumentReference,
+    width: f32,
+    height: f32,
+    margin: f32,
+    layers: Vec<PdfLayerReference>,
+    y: f32,
+    text: PdfFont,
+    code: PdfFont,
+    palette: Palette,
+    outline: Vec<OutlineEntry>,
+}
+
+impl Writer<'_> {
+    fn layer(&self) -> &PdfLayerReference {
+        self.layers.last().expect("the document always has a page")
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(self.width), Mm(self.height), "Layer");
+        self.layers.push(self.doc.get_page(page).get_layer(layer));
+        self.y = self.height - self.margin;
+    }
+
+    fn at_page_top(&self) -> bool {
+        self.y >= self.height - self.margin
+    }
+
+    /// Starts a new page unless `height` more millimeters fit on this one.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < self.margin && !self.at_page_top() {
+            self.new_page();
+        }
+    }
+
+    fn font(&self, style: Style) -> &PdfFont {
+        if style == Style::Code { &self.code } else { &self.text }
+    }
+
+    fn fill_rect(&self, left: f32, bottom: f32, right: f32, top: f32, color: Color32) {
+        self.layer().set_fill_color(pdf_color(color));
+        self.layer().add_rect(Rect::new(Mm(left), Mm(bottom), Mm(right), Mm(top)));
+    }
+
+    /// Breaks runs into lines no wider than `width`, keeping words whole where possible.
+    fn wrap(&self, runs: &[Run], size: f32, width: f32) -> Vec<Vec<Run>> {
+        let mut lines: Vec<Vec<Run>> = vec![Vec::new()];
+        let mut line_width = 0.0;
+        for run in runs {
+            let font = self.font(run.style);
+            for word in run.text.split_inclusive(' ') {
+                let mut word_width = font.width(word, size);
+                let mut word = word.to_string();
+                if line_width + font.width(word.trim_end(), size) > width && line_width > 0.0 {
+                    lines.push(Vec::new());
+                    line_width = 0.0;
+                }
+                // Слово шире строки режем по символам
+                while word_width > width {
+                    let mut cut = word.len();
+                    while cut > 0 && font.width(&word[..cut], size) > width {
+                        cut = word[..cut].char_indices().last().map_or(0, |(i, _)| i);
+                    }
+                    let cut = cut.max(word.chars().next().map_or(1, char::len_utf8));
+                    let line = lines.last_mut().expect("there is always a line");
+                    line.push(Run { text: word[..cut].to_string(), ..run.clone() });
+                    lines.push(Vec::new());
+                    word = word[cut..].to_string();
+                    word_width = font.width(&word, size);
+                    line_width = 0.0;
+                }
+                line_width += word_width;
+                let line = lines.last_mut().expect("there is always a line");
+                match line.last_mut() {
+                    Some(last) if last.style == run.style && last.color == run.color => last.text.push_str(&word),
+                    _ => line.push(Run { text: word, ..run.clone() }),
+                }
+            }
+        }
+        lines.retain(|line| !line.is_empty());
+        lines
+    }
+
+    /// Draws one line of runs with its top at the current position and moves below it.
+    fn draw_line(&mut self, line: &[Run], left: f32, size: f32, line_height: f32, color: Color32) {
+        let baseline = self.y - size * PT_TO_MM;
+        // Фон инлайн-кода рисуем до текста, иначе он его закроет
+        let mut x = left;
+        for run in line {
+            let width = self.font(run.style).width(&run.text, size);
+            if run.style == Style::Code && run.color.is_none() {
+                let pad = size * PT_TO_MM * 0.25;
+                self.fill_rect(x - pad / 2.0, baseline - pad, x + width + pad / 2.0, baseline + size * PT_TO_MM, self.palette.surface);
+            }
+            x += width;
+        }
+        let mut x = left;
+        for run in line {
+            let font = self.font(run.style);
+            let width = font.width(&run.text, size);
+            let run_color = match run.style {
+                Style::Link => self.palette.accent,
+                _ => run.color.unwrap_or(color),
+            };
+            self.layer().set_fill_color(pdf_color(run_color));
+            self.layer().use_text(run.text.as_str(), size, Mm(x), Mm(baseline), &font.font);
+            x += width;
+        }
+        self.y -= line_height;
+    }
+
+    fn heading(&mut self, level: usize, text: &str) {
+        let size = HEADING_SIZES[(level - 1).min(HEADING_SIZES.len() - 1)];
+        let line_height = size * PT_TO_MM * 1.3;
+        let runs = [Run { text: text.to_string(), style: Style::Text, color: None }];
+        let lines = self.wrap(&runs, size, self.width - 2.0 * self.margin);
+        // Заголовок не оставляем внизу страницы без пары строк текста после него
+        self.ensure(line_height * lines.len() as f32 + 4.0 + BODY_SIZE * PT_TO_MM * LINE_SPACING * 2.0);
+        if !self.at_page_top() {
+            self.y -= 4.0;
+        }
+        self.outline.push(OutlineEntry { level, title: text.to_string(), page: self.layers.len() - 1, y: self.y });
+        for line in lines {
+            self.draw_line(&line, self.margin, size, line_height, self.palette.text);
+        }
+        self.y -= 2.0;
+    }
+
+    fn paragraph(&mut self, runs: &[Run], indent: f32, marker: Option<&str>, quote: bool) {
+        let left = self.margin + indent;
+        let line_height = BODY_SIZE * PT_TO_MM * LINE_SPACING;
+        let color = if quote { self.palette.muted } else { self.palette.text };
+        for (i, line) in self.wrap(runs, BODY_SIZE, self.width - self.margin - left).into_iter().enumerate() {
+            self.ensure(line_height);
+            let top = self.y;
+            if i == 0 {
+                if let Some(marker) = marker {
+                    let marker_run = [Run { text: marker.to_string(), style: Style::Text, color: None }];
+                    self.draw_line(&marker_run, left - INDENT + 1.0, BODY_SIZE, 0.0, color);
+                }
+            }
+            if quote {
+                self.fill_rect(left - INDENT + 1.0, top - line_height, left - INDENT + 2.0, top, self.palette.accent);
+            }
+            self.draw_line(&line, left, BODY_SIZE, line_height, color);
+        }
+        self.y -= 2.0;
+    }
+
+    fn code_block(&mut self, lines: &[Vec<Run>]) {
+        let line_height = CODE_SIZE * PT_TO_MM * 1.35;
+        let pad = 2.0;
+        let left = self.margin + pad;
+        let width = self.width - 2.0 * self.margin - 2.0 * pad;
+        let wrapped: Vec<Vec<Run>> = lines.iter()
+            .flat_map(|line| {
+                let wrapped = self.wrap(line, CODE_SIZE, width);
+                // Пустые строки кода тоже занимают место
+                if wrapped.is_empty() { vec![Vec::new()] } else { wrapped }
+            })
+            .collect();
+
+        self.ensure(line_height + 2.0 * pad);
+        self.fill_rect(self.margin, self.y - pad, self.width - self.margin, self.y, self.palette.surface);
+        self.y -= pad;
+        for line in &wrapped {
+            if self.y - line_height < self.margin {
+                self.new_page();
+            }
+            self.fill_rect(self.margin, self.y - line_height, self.width - self.margin, self.y, self.palette.surface);
+            self.draw_line(line, left, CODE_SIZE, line_height, self.palette.text);
+        }
+        self.fill_rect(self.margin, self.y - pad, self.width - self.margin, self.y, self.palette.surface);
+        self.y -= pad + 3.0;
+    }
+
+    fn image(&mut self, path: &Path, alt: &str) {
+        let Some(image) = image_crate::open(path).ok().map(|image| flatten_alpha(&image)) else {
+            // Картинку не удалось прочитать — оставляем её подпись
+            let label = if alt.is_empty() { path.file_name().unwrap_or_default().to_string_lossy().to_string() } else { alt.to_string() };
+            let runs = [Run { text: format!("[{}]", label), style: Style::Text, color: None }];
+            self.paragraph(&runs, 0.0, None, true);
+            return;
+        };
+        let to_mm = |pixels: u32| pixels as f32 * 25.4 / IMAGE_DPI;
+        let (width, height) = (to_mm(image.width()), to_mm(image.height()));
+        let max_width = self.width - 2.0 * self.margin;
+        let max_height = self.height - 2.0 * self.margin;
+        let scale = (max_width / width).min(max_height / height).min(1.0);
+        self.ensure(height * scale);
+        Image::from_dynamic_image(&DynamicImage::ImageRgb8(image)).add_to_layer(self.layer().clone(), ImageTransform {
+            translate_x: Some(Mm(self.margin)),
+            translate_y: Some(Mm(self.y - height * scale)),
+            scale_x: Some(scale),
+            scale_y: Some(scale),
+            dpi: Some(IMAGE_DPI),
+            ..Default::default()
+        });
+        self.y -= height * scale + 3.0;
+    }
+
+    fn rule(&mut self) {
+        self.ensure(4.0);
+        self.y -= 2.0;
+        self.fill_rect(self.margin, self.y - 0.3, self.width - self.margin, self.y, self.palette.border);
+        self.y -= 2.0;
+    }
+
+    fn page_numbers(&self) {
+        let total = self.layers.len();
+        for (i, layer) in self.layers.iter().enumerate() {
+            let text = format!("{} / {}", i + 1, total);
+            let x = (self.width - self.text.width(&text, 9.0)) / 2.0;
+            layer.set_fill_color(pdf_color(self.palette.muted));
+            layer.use_text(text, 9.0, Mm(x), Mm(self.margin / 2.0), &self.text.font);
+        }
+    }
+}
+
+/// PDF has no transparency for plain image objects, so transparent pixels are blended onto white paper.
+fn flatten_alpha(image: &DynamicImage) -> RgbImage {
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |channel: u8| ((u16::from(channel) * u16::from(a) + 255 * (255 - u16::from(a))) / 255) as u8;
+        image_crate::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+/// PDF text strings outside of Latin-1 are written as UTF-16 with a byte order mark.
+fn pdf_text(text: &str) -> Object {
+    let mut bytes = vec![0xFE, 0xFF];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+/// Adds nested bookmarks for the headings; printpdf only supports one flat bookmark per page.
+fn add_outline(bytes: &[u8], entries: &[OutlineEntry]) -> Result<Vec<u8>, lopdf::Error> {
+    let mut doc = lopdf::Document::load_mem(bytes)?;
+    if entries.is_empty() {
+        return Ok(bytes.to_vec());
+    }
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    let root = doc.new_object_id();
+    let ids: Vec<ObjectId> = entries.iter().map(|_| doc.new_object_id()).collect();
+
+    // Родитель каждого заголовка — ближайший предыдущий заголовок уровнем выше
+    let mut parents: Vec<Option<usize>> = Vec::with_capacity(entries.len());
+    let mut stack: Vec<usize> = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        while stack.last().is_some_and(|&last| entries[last].level >= entry.level) {
+            stack.pop();
+        }
+        parents.push(stack.last().copied());
+        stack.push(i);
+    }
+    let children = |parent: Option<usize>| -> Vec<usize> {
+        (0..entries.len()).filter(|&i| parents[i] == parent).collect()
+    };
+
+    let link_siblings = |doc: &mut lopdf::Document, siblings: &[usize], parent_id: ObjectId| {
+        for (position, &i) in siblings.iter().enumerate() {
+            let entry = &entries[i];
+            let mut item = lopdf::Dictionary::new();
+            item.set("Title", pdf_text(&entry.title));
+            item.set("Parent", Object::Reference(parent_id));
+            let page = pages.get(entry.page).copied().unwrap_or(pages[0]);
+            item.set("Dest", Object::Array(vec![
+                Object::Reference(page),
+                Object::Name(b"XYZ".to_vec()),
+                Object::Null,
+                Object::Real(entry.y / PT_TO_MM),
+                Object::Null,
+            ]));
+            if position > 0 {
+                item.set("Prev", Object::Reference(ids[siblings[position - 1]]));
+            }
+            if let Some(&next) = siblings.get(position + 1) {
+                item.set("Next", Object::Reference(ids[next]));
+            }
+            let own_children = children(Some(i));
+            if let (Some(&first), Some(&last)) = (own_children.first(), own_children.last()) {
+                item.set("First", Object::Reference(ids[first]));
+                item.set("Last", Object::Reference(ids[last]));
+                // Отрицательное число — раздел свёрнут
+                item.set("Count", Object::Integer(-(own_children.len() as i64)));
+            }
+            doc.objects.insert(ids[i], Object::Dictionary(item));
+        }
+    };
+
+    let top = children(None);
+    link_siblings(&mut doc, &top, root);
+    for (i, &id) in ids.iter().enumerate() {
+        let own_children = children(Some(i));
+        if !own_children.is_empty() {
+            link_siblings(&mut doc, &own_children, id);
+        }
+    }
+
+    let mut outlines = lopdf::Dictionary::new();
+    outlines.set("Type", Object::Name(b"Outlines".to_vec()));
+    outlines.set("First", Object::Reference(ids[top[0]]));
+    outlines.set("Last", Object::Reference(ids[*top.last().expect("the first entry is always top-level")]));
+    outlines.set("Count", Object::Integer(top.len() as i64));
+    doc.objects.insert(root, Object::Dictionary(outlines));
+
+    let catalog = doc.catalog_mut()?;
+    catalog.set("Outlines", Object::Reference(root));
+    catalog.set("PageMode", Object::Name(b"UseOutlines".to_vec()));
+
+    let mut output = Vec::new();
+    doc.save_to(&mut output)?;
+    Ok(output)
+}
+
+/// Lays out the notes one after another, each starting on a new page, and returns the PDF file.
+pub fn notes_to_pdf(notes: &[(PathBuf, String)], title: &str, options: &PdfOptions) -> Result<Vec<u8>, String> {
+    let (width, height) = options.page_size.size();
+    let (doc, page, layer) = PdfDocument::new(title, Mm(width), Mm(height), "Layer");
+    let load_font = |bytes: &'static [u8]| -> Result<PdfFont, String> {
+        let face = Face::parse(bytes, 0).map_err(|e| e.to_string())?;
+        let font = doc.add_external_font(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+        Ok(PdfFont { face, font })
+    };
+    let mut writer = Writer {
+        doc: &doc,
+        width,
+        height,
+        margin: options.margin.clamp(0.0, width.min(height) / 3.0),
+        layers: vec![doc.get_page(page).get_layer(layer)],
+        y: 0.0,
+        text: load_font(ROBOTO)?,
+        code: load_font(hack_font())?,
+        // Печатаем на белой бумаге, поэтому всегда светлая палитра
+        palette: Palette::new(false),
+        outline: Vec::new(),
+    };
+    writer.y = writer.height - writer.margin;
+
+    let mut blocks = Vec::new();
+    for (i, (path, content)) in notes.iter().enumerate() {
+        if i > 0 {
+            blocks.push(Block::PageBreak);
+        }
+        note_blocks(path, content, &mut blocks);
+    }
+    for block in &blocks {
+        match block {
+            Block::Heading { level, text } => writer.heading(*level, text),
+            Block::Paragraph { runs, indent, marker, quote } => writer.paragraph(runs, *indent, marker.as_deref(), *quote),
+            Block::Code(lines) => writer.code_block(lines),
+            Block::Image { path, alt } => writer.image(path, alt),
+            Block::Rule => writer.rule(),
+            Block::PageBreak => writer.new_page(),
+        }
+    }
+    writer.page_numbers();
+
+    let outline = std::mem::take(&mut writer.outline);
+    drop(writer);
+    let bytes = doc.save_to_bytes().map_err(|e| e.to_string())?;
+    add_outline(&bytes, &outline).map_err(|e| e.to_string())
+}
@@ -0,0 +1,589 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use eframe::egui::{self, Color32, RichText};
+use pulldown_cmark::escape::escape_html;
+use pulldown_cmark::{Event, Parser};
+
+use crate::export::{self, HtmlOptions, Palette};
+use crate::health::{heading_slug, workspace_files};
+use crate::index::DATA_DIR_NAME;
+use crate::links::{self, LinkKind, NoteIndex};
+use crate::{collect_category_paths, markdown_options, tags, workspace, Category, Language, MdReader};
+
+// Свой шаблон сайта можно положить в папку данных рабочей области
+const TEMPLATE_FILE_NAME: &str = "site-template.html";
+// Список файлов, записанных прошлой публикацией: их удаляем, чтобы не оставались страницы удалённых заметок
+const MANIFEST_FILE_NAME: &str = ".mdreader-site";
+// Как часто окно проверяет, закончилась ли публикация
+const WORKER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Placeholders: `{{title}}`, `{{site_title}}`, `{{root}}` (relative path to the site root), `{{nav}}` and `{{content}}`.
+const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}} — {{site_title}}</title>
+<link rel="stylesheet" href="{{root}}style.css">
+</head>
+<body>
+<nav>
+<a class="site-title" href="{{root}}index.html">{{site_title}}</a>
+<input id="search" type="search" placeholder="Search…" data-root="{{root}}">
+<ul id="search-results"></ul>
+{{nav}}
+</nav>
+<main>
+{{content}}
+</main>
+<script src="{{root}}search.js"></script>
+</body>
+</html>
+"#;
+
+const SEARCH_SCRIPT: &str = r#"(function () {
+  var input = document.getElementById('search');
+  var results = document.getElementById('search-results');
+  if (!input || !results) return;
+  var root = input.getAttribute('data-root') || '';
+  var index = null;
+
+  function show(query) {
+    var words = query.toLowerCase().split(/\s+/).filter(Boolean);
+    results.innerHTML = '';
+    if (!words.length) return;
+    index.filter(function (page) {
+      var haystack = (page.title + ' ' + page.tags.join(' ') + ' ' + page.text).toLowerCase();
+      return words.every(function (word) { return haystack.indexOf(word) !== -1; });
+    }).slice(0, 20).forEach(function (page) {
+      var item = document.createElement('li');
+      var link = document.createElement('a');
+      link.href = root + page.url;
+      link.textContent = page.title;
+      item.appendChild(link);
+      results.appendChild(item);
+    });
+  }
+
+  input.addEventListener('input', function () {
+    if (index) {
+      show(input.value);
+      return;
+    }
+    fetch(root + 'search-index.json')
+      .then(function (response) { return response.json(); })
+      .then(function (data) { index = data; show(input.value); });
+  });
+})();
+"#;
+
+pub enum PublishError {
+    NoNotes,
+    OutputInsideSource,
+    Template(std::io::Error),
+    Io(std::io::Error),
+}
+
+impl PublishError {
+    fn message(&self, language: Language) -> String {
+        match (self, language) {
+            (PublishError::NoNotes, Language::EN) => "There are no notes to publish".to_string(),
+            (PublishError::NoNotes, Language::RU) => "Нет заметок для публикации".to_string(),
+            (PublishError::OutputInsideSource, Language::EN) => "Choose a folder outside of the published notes".to_string(),
+            (PublishError::OutputInsideSource, Language::RU) => "Выберите папку вне публикуемых заметок".to_string(),
+            (PublishError::Template(e), Language::EN) => format!("Cannot read the template: {}", e),
+            (PublishError::Template(e), Language::RU) => format!("Не удалось прочитать шаблон: {}", e),
+            (PublishError::Io(e), _) => e.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for PublishError {
+    fn from(e: std::io::Error) -> Self {
+        PublishError::Io(e)
+    }
+}
+
+pub struct PublishDialog {
+    /// The workspace root or one of its categories.
+    source: PathBuf,
+    output: String,
+    /// Empty for the built-in template.
+    template: String,
+    error: Option<String>,
+    published: Option<(usize, PathBuf)>,
+    // Публикация идёт в фоновом потоке, пока окно ждёт результат
+    worker: Option<Receiver<Result<usize, PublishError>>>,
+}
+
+/// A published note: where its page goes (relative to the site root) and its title.
+struct Page {
+    url: String,
+    title: String,
+}
+
+pub(crate) fn escaped(text: &str) -> String {
+    let mut escaped = String::new();
+    let _ = escape_html(&mut escaped, text);
+    escaped
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// `relative` with the characters that break an `href` encoded, like the app does for markdown links.
+fn href(relative: &str) -> String {
+    relative.replace('%', "%25").replace(' ', "%20").replace('#', "%23").replace('?', "%3F")
+}
+
+/// Site URL of a note, e.g. `guides/Setup.html`.
+fn page_url(source: &Path, note: &Path) -> String {
+    links::relative_path(source, &note.with_extension("html"))
+}
+
+fn site_stylesheet(palette: &Palette) -> String {
+    let color = |color: Color32| format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b());
+    format!(
+        r#"{base}
+body {{ max-width: none; margin: 0; padding: 0; display: flex; }}
+nav {{ width: 280px; flex-shrink: 0; height: 100vh; position: sticky; top: 0; overflow-y: auto; padding: 1em; box-sizing: border-box; background: {surface}; }}
+nav ul {{ list-style: none; padding-left: 1em; margin: 0.2em 0; }}
+nav > ul {{ padding-left: 0; }}
+nav a {{ text-decoration: none; }}
+nav a.current {{ font-weight: bold; }}
+nav summary {{ cursor: pointer; }}
+.site-title {{ display: block; font-size: 1.3em; font-weight: bold; margin-bottom: 0.5em; }}
+#search {{ width: 100%; box-sizing: border-box; padding: 0.4em; margin-bottom: 0.5em; background: {background}; color: {text}; border: 1px solid {border}; border-radius: 6px; }}
+main {{ flex: 1; max-width: 800px; margin: 2em auto; padding: 0 2em; min-width: 0; }}
+"#,
+        base = export::stylesheet(palette),
+        surface = color(palette.surface),
+        background = color(palette.background),
+        text = color(palette.text),
+        border = color(palette.border),
+    )
+}
+
+/// Navigation mirroring the sidebar: the notes of a folder, then its categories as collapsible lists.
+fn nav_list(notes: &[PathBuf], categories: &[Category], pages: &HashMap<PathBuf, Page>) -> String {
+    let mut html = String::new();
+    for note in notes {
+        if let Some(page) = pages.get(note) {
+            html.push_str(&format!(
+                "<li><a href=\"{{{{root}}}}{url}\" data-page=\"{url}\">{title}</a></li>\n",
+                url = href(&page.url),
+                title = escaped(&page.title),
+            ));
+        }
+    }
+    for category in categories {
+        let files: Vec<PathBuf> = category.files.iter().map(|file| file.path.clone()).collect();
+        let children = nav_list(&files, &category.subcategories, pages);
+        // Пустые категории на сайте не показываем
+        if !children.is_empty() {
+            html.push_str(&format!(
+                "<li><details open><summary>{}</summary>\n{}</details></li>\n",
+                escaped(&category.name),
+                children,
+            ));
+        }
+    }
+    if html.is_empty() {
+        return html;
+    }
+    format!("<ul>\n{}</ul>\n", html)
+}
+
+/// Turns `[[wiki links]]` into markdown links to the notes' files, so they end up as links between pages.
+pub(crate) fn wiki_links_to_markdown(root: &Path, source: &Path, note: &Path, content: &str, index: &NoteIndex, attachments: &[PathBuf]) -> String {
+    let body_start = tags::front_matter_range(content).map_or(0, |range| range.end);
+    let note_dir = note.parent().unwrap_or(root);
+    let mut replacements = Vec::new();
+
+    for link in links::extract_links(content) {
+        if link.kind != LinkKind::Wiki || link.range.start < body_start {
+            continue;
+        }
+        let start = link.range.start - 2 - usize::from(link.embed);
+        let Some(close) = content[link.range.end..].find("]]") else { continue };
+        let end = link.range.end + close + 2;
+        let rest = &content[link.range.end..link.range.end + close];
+        let label = rest.split_once('|').map_or(link.target.as_str(), |(_, alias)| alias.trim());
+        let anchor = link.anchor.as_deref().map(|anchor| format!("#{}", heading_slug(anchor))).unwrap_or_default();
+
+        let attachment = Path::new(&link.target).extension().is_some_and(|ext| ext != "md");
+        let target = if attachment {
+            let name = link.target.rsplit('/').next().unwrap_or(&link.target).to_lowercase();
+            attachments.iter()
+                .find(|file| file.file_name().is_some_and(|file_name| file_name.to_string_lossy().to_lowercase() == name))
+                .cloned()
+        } else {
+            links::resolve(root, note, &link, index)
+        };
+
+        let replacement = match target.filter(|target| target.starts_with(source) && target.exists()) {
+            Some(target) => {
+                let destination = href(&links::relative_path(note_dir, &target));
+                let bang = if link.embed && attachment { "!" } else { "" };
+                format!("{}[{}](<{}{}>)", bang, label, destination, anchor)
+            }
+            // Ссылка на заметку вне сайта — оставляем только текст
+            None => label.to_string(),
+        };
+        replacements.push((start..end, replacement));
+    }
+
+    links::apply_replacements(note, content, replacements)
+        .map_or_else(|| content.to_string(), |edit| edit.new_content)
+}
+
+/// Where a link of `note` points on the site, when it is a note that gets published.
+fn rewrite_note_link(root: &Path, source: &Path, note: &Path, destination: &str) -> Option<String> {
+    if links::is_external(destination) || destination.starts_with('#') {
+        return None;
+    }
+    let (target, anchor) = match destination.split_once('#') {
+        Some((target, anchor)) => (target, format!("#{}", anchor)),
+        None => (destination, String::new()),
+    };
+    let target = links::percent_decode(target);
+    if !target.ends_with(".md") {
+        return None;
+    }
+    let base = if target.starts_with('/') { root.to_path_buf() } else { note.parent()?.to_path_buf() };
+    let resolved = links::normalize(&base.join(target.trim_start_matches('/')));
+    if !resolved.starts_with(source) || !resolved.exists() {
+        return None;
+    }
+    let page = links::relative_path(note.parent()?, &resolved.with_extension("html"));
+    Some(format!("{}{}", href(&page), anchor))
+}
+
+/// Plain text of a note for the search index.
+fn plain_text(content: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new_ext(tags::body(content), markdown_options()) {
+        match event {
+            Event::Text(part) | Event::Code(part) => text.push_str(&part),
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn fill_template(template: &str, title: &str, site_title: &str, root: &str, nav: &str, content: &str) -> String {
+    // Текст заметки подставляем последним, чтобы фигурные скобки в нём не приняли за шаблон
+    template
+        .replace("{{title}}", &escaped(title))
+        .replace("{{site_title}}", &escaped(site_title))
+        .replace("{{nav}}", &nav.replace("{{root}}", root))
+        .replace("{{root}}", root)
+        .replace("{{content}}", content)
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), std::io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+}
+
+/// Removes the files the previous publish to `output` wrote, and folders that end up empty.
+fn remove_previous_site(output: &Path) -> Result<(), std::io::Error> {
+    let Ok(manifest) = fs::read_to_string(output.join(MANIFEST_FILE_NAME)) else { return Ok(()) };
+    for line in manifest.lines() {
+        let relative = Path::new(line);
+        // Только пути внутри сайта: испорченный список не должен удалять что-то снаружи
+        if line.is_empty() || !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+            continue;
+        }
+        let path = output.join(relative);
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+        let mut dir = path.parent();
+        while let Some(parent) = dir.filter(|parent| *parent != output) {
+            if fs::remove_dir(parent).is_err() {
+                break;
+            }
+            dir = parent.parent();
+        }
+    }
+    Ok(())
+}
+
+/// Publishes the notes under `source` to `output` as a static site: one page per note, the sidebar tree as navigation,
+/// a JSON search index and every other file copied along. Files left by the previous publish to `output` are removed first.
+/// Returns how many pages were written.
+pub fn publish_site(root: &Path, source: &Path, categories: &[Category], output: &Path, template: &str, dark_mode: bool) -> Result<usize, PublishError> {
+    // Относительный путь или «..» не должны обойти проверку, иначе сайт окажется среди заметок
+    let output = &links::normalize(&std::env::current_dir()?.join(output));
+    if output.starts_with(links::normalize(source)) {
+        return Err(PublishError::OutputInsideSource);
+    }
+    let notes = links::markdown_files(source);
+    if notes.is_empty() {
+        return Err(PublishError::NoNotes);
+    }
+    let index = NoteIndex::new(&links::markdown_files(root));
+    let attachments: Vec<PathBuf> = workspace_files(source).into_iter()
+        .filter(|file| file.extension().is_none_or(|ext| ext != "md"))
+        .collect();
+
+    let mut contents = HashMap::new();
+    let mut pages = HashMap::new();
+    for note in &notes {
+        let content = fs::read_to_string(note)?;
+        let title = export::note_title(note, &content);
+        pages.insert(note.clone(), Page { url: page_url(source, note), title });
+        contents.insert(note.clone(), content);
+    }
+
+    // Корень рабочей области в сайдбаре не показан, но его заметки публикуем первыми
+    let nav = if source == root {
+        let mut root_notes: Vec<PathBuf> = notes.iter().filter(|note| note.parent() == Some(root)).cloned().collect();
+        root_notes.sort();
+        nav_list(&root_notes, categories, &pages)
+    } else {
+        let category = workspace::find_category(categories, source);
+        let files: Vec<PathBuf> = category.map(|category| category.files.iter().map(|file| file.path.clone()).collect()).unwrap_or_default();
+        nav_list(&files, category.map_or(&[], |category| &category.subcategories), &pages)
+    };
+    let site_title = source.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let palette = Palette::new(dark_mode);
+
+    remove_previous_site(output)?;
+    let mut written: Vec<PathBuf> = Vec::new();
+    let mut write = |relative: &Path, content: &[u8]| {
+        written.push(relative.to_path_buf());
+        write_file(&output.join(relative), content)
+    };
+
+    let mut search_index = Vec::new();
+    for note in &notes {
+        let page = &pages[note];
+        let content = &contents[note];
+        let markdown = wiki_links_to_markdown(root, source, note, content, &index, &attachments);
+        let rewrite = |destination: &str| rewrite_note_link(root, source, note, destination);
+        let body = export::render_html_body(note, &markdown, &HtmlOptions { dark_mode, inline_images: false, rewrite_link: Some(&rewrite), rewrite_image: None });
+
+        let depth = page.url.matches('/').count();
+        let root_prefix = "../".repeat(depth);
+        let current = href(&page.url);
+        let page_nav = nav.replace(&format!("data-page=\"{}\"", current), "class=\"current\"");
+        let html = fill_template(template, &page.title, &site_title, &root_prefix, &page_nav, &body);
+        write(Path::new(&page.url), html.as_bytes())?;
+
+        let tags: Vec<String> = tags::extract_tags(content).iter().map(|tag| json_string(tag)).collect();
+        search_index.push(format!(
+            "{{\"title\":{},\"url\":{},\"tags\":[{}],\"text\":{}}}",
+            json_string(&page.title),
+            json_string(&href(&page.url)),
+            tags.join(","),
+            json_string(&plain_text(&markdown)),
+        ));
+    }
+
+    // Если среди заметок нет index.md, главной страницей становится оглавление
+    if !pages.values().any(|page| page.url == "index.html") {
+        let content = format!("<h1>{}</h1>\n{}", escaped(&site_title), nav.replace("{{root}}", ""));
+        let html = fill_template(template, &site_title, &site_title, "", &nav, &content);
+        write(Path::new("index.html"), html.as_bytes())?;
+    }
+
+    write(Path::new("style.css"), site_stylesheet(&palette).as_bytes())?;
+    write(Path::new("search.js"), SEARCH_SCRIPT.as_bytes())?;
+    write(Path::new("search-index.json"), format!("[\n{}\n]\n", search_index.join(",\n")).as_bytes())?;
+    for attachment in &attachments {
+        let relative = attachment.strip_prefix(source).unwrap_or(attachment);
+        let target = output.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(attachment, target)?;
+        written.push(relative.to_path_buf());
+    }
+
+    let manifest: Vec<String> = written.iter().map(|path| path.to_string_lossy().replace('\\', "/")).collect();
+    write_file(&output.join(MANIFEST_FILE_NAME), manifest.join("\n").as_bytes())?;
+    Ok(notes.len())
+}
+
+impl MdReader {
+    /// Opens the publish dialog for a category, or for the whole workspace.
+    pub(crate) fn open_publish_dialog(&mut self, source: Option<PathBuf>) {
+        let source = source.unwrap_or_else(|| self.root_dir.clone());
+        let name = source.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let output = self.root_dir.parent().unwrap_or(&self.root_dir).join(format!("{}-site", name));
+        let template = self.root_dir.join(DATA_DIR_NAME).join(TEMPLATE_FILE_NAME);
+        self.publish = Some(PublishDialog {
+            source,
+            output: output.to_string_lossy().to_string(),
+            template: if template.exists() { template.to_string_lossy().to_string() } else { String::new() },
+            error: None,
+            published: None,
+            worker: None,
+        });
+    }
+
+    /// Starts publishing on a background thread; the dialog picks up the result when it is done.
+    fn publish_from_dialog(&self, dialog: &mut PublishDialog, ctx: &egui::Context) {
+        let template = if dialog.template.trim().is_empty() {
+            DEFAULT_TEMPLATE.to_string()
+        } else {
+            match fs::read_to_string(dialog.template.trim()) {
+                Ok(template) => template,
+                Err(e) => {
+                    dialog.error = Some(PublishError::Template(e).message(self.current_language));
+                    return;
+                }
+            }
+        };
+        let (root, source, categories) = (self.root_dir.clone(), dialog.source.clone(), self.categories.clone());
+        let (output, dark_mode) = (PathBuf::from(dialog.output.trim()), self.dark_mode);
+        let (sender, receiver) = mpsc::channel();
+        let repaint = ctx.clone();
+        thread::spawn(move || {
+            let _ = sender.send(publish_site(&root, &source, &categories, &output, &template, dark_mode));
+            repaint.request_repaint();
+        });
+        dialog.error = None;
+        dialog.published = None;
+        dialog.worker = Some(receiver);
+    }
+
+    pub(crate) fn render_publish_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.publish.take() else { return };
+        if let Some(worker) = &dialog.worker {
+            match worker.try_recv() {
+                Ok(result) => {
+                    match result {
+                        Ok(count) => dialog.published = Some((count, PathBuf::from(dialog.output.trim()))),
+                        Err(e) => dialog.error = Some(e.message(self.current_language)),
+                    }
+                    dialog.worker = None;
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint_after(WORKER_POLL_INTERVAL),
+                Err(TryRecvError::Disconnected) => dialog.worker = None,
+            }
+        }
+        let (window_title, source_label, whole_workspace, output_label, template_label, template_hint, publish_text) = match self.current_language {
+            Language::EN => ("Publish as website", "Notes:", "Whole workspace", "Output folder:", "Template:", "Built-in template", "Publish"),
+            Language::RU => ("Публикация сайта", "Заметки:", "Вся рабочая область", "Папка для сайта:", "Шаблон:", "Встроенный шаблон", "Опубликовать"),
+        };
+        let mut categories = Vec::new();
+        collect_category_paths(&self.categories, &mut categories);
+        let source_text = |path: &Path| {
+            if path == self.root_dir { whole_workspace.to_string() } else { links::relative_path(&self.root_dir, path) }
+        };
+
+        let mut open = true;
+        let mut publish = false;
+        egui::Window::new(window_title)
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(source_label);
+                    egui::ComboBox::from_id_source("publish_source")
+                        .selected_text(source_text(&dialog.source))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut dialog.source, self.root_dir.clone(), whole_workspace);
+                            for category in &categories {
+                                ui.selectable_value(&mut dialog.source, category.clone(), source_text(category));
+                            }
+                        });
+                });
+                ui.label(output_label);
+                let output = ui.add(egui::TextEdit::singleline(&mut dialog.output).desired_width(f32::INFINITY));
+                ui.label(template_label);
+                let template = ui.add(egui::TextEdit::singleline(&mut dialog.template).hint_text(template_hint).desired_width(f32::INFINITY));
+                if output.changed() || template.changed() {
+                    dialog.error = None;
+                    dialog.published = None;
+                }
+                ui.horizontal(|ui| {
+                    publish = ui.add_enabled(dialog.worker.is_none(), egui::Button::new(publish_text)).clicked();
+                    if dialog.worker.is_some() {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(Color32::from_rgb(235, 87, 87), error);
+                }
+                if let Some((count, output)) = &dialog.published {
+                    let text = match self.current_language {
+                        Language::EN => format!("Published {} pages to {}", count, output.display()),
+                        Language::RU => format!("Опубликовано страниц: {}, папка {}", count, output.display()),
+                    };
+                    ui.label(RichText::new(text).weak());
+                }
+            });
+
+        if publish {
+            self.publish_from_dialog(&mut dialog, ctx);
+        }
+        if open {
+            self.publish = Some(dialog);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn republishing_removes_pages_of_deleted_notes() {
+        let root = std::env::temp_dir().join(format!("mdreader-publish-{}", std::process::id()));
+        let (source, output) = (root.join("notes"), root.join("site"));
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("a.md"), "# A").unwrap();
+        fs::write(source.join("sub/b.md"), "# B").unwrap();
+        assert_eq!(publish_site(&source, &source, &[], &output, DEFAULT_TEMPLATE, false).ok(), Some(2));
+        assert!(output.join("sub").is_dir());
+        // Чужие файлы в папке сайта не трогаем
+        fs::write(output.join("CNAME"), "notes.example").unwrap();
+
+        fs::remove_dir_all(source.join("sub")).unwrap();
+        assert_eq!(publish_site(&source, &source, &[], &output, DEFAULT_TEMPLATE, false).ok(), Some(1));
+        assert!(output.join("a.html").exists());
+        assert!(!output.join("sub").exists());
+        assert!(output.join("CNAME").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn output_inside_source_is_refused_after_normalizing() {
+        let source = std::env::temp_dir().join(format!("mdreader-publish-inside-{}", std::process::id()));
+        fs::create_dir_all(&source).unwrap();
+        let output = source.join("missing/../site");
+        let result = publish_site(&source, &source, &[], &output, DEFAULT_TEMPLATE, false);
+        assert!(matches!(result, Err(PublishError::OutputInsideSource)));
+        fs::remove_dir_all(&source).unwrap();
+    }
+}
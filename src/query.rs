@@ -0,0 +1,396 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+
+use crate::search::{self, SearchOptions};
+use crate::{links, tags, Language};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Compare {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Compare {
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Compare::Less => left < right,
+            Compare::LessOrEqual => left <= right,
+            Compare::Equal => left == right,
+            Compare::GreaterOrEqual => left >= right,
+            Compare::Greater => left > right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DateField {
+    Modified,
+    Created,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Term {
+    /// A word or a quoted phrase that has to appear in the text.
+    Text { text: String, phrase: bool },
+    Tag(String),
+    Path(String),
+    Title(String),
+    /// Days since the Unix epoch.
+    Date { field: DateField, compare: Compare, day: i64 },
+    Size { compare: Compare, bytes: u64 },
+    /// `fm.key:value` is looked up in the front matter.
+    Field { key: String, value: String },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Clause {
+    pub negated: bool,
+    pub term: Term,
+}
+
+/// A parsed search: every clause has to hold (or not hold, when negated).
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+pub enum QueryError {
+    UnclosedQuote,
+    EmptyValue(String),
+    BadDate(String),
+    BadSize(String),
+}
+
+impl QueryError {
+    pub fn message(&self, language: Language) -> String {
+        match (self, language) {
+            (QueryError::UnclosedQuote, Language::EN) => "Missing closing quote".to_string(),
+            (QueryError::UnclosedQuote, Language::RU) => "Не хватает закрывающей кавычки".to_string(),
+            (QueryError::EmptyValue(key), Language::EN) => format!("\"{}:\" needs a value", key),
+            (QueryError::EmptyValue(key), Language::RU) => format!("После \"{}:\" нужно значение", key),
+            (QueryError::BadDate(value), Language::EN) => format!("\"{}\" is not a date like 2026-01-31", value),
+            (QueryError::BadDate(value), Language::RU) => format!("\"{}\" — не дата вида 2026-01-31", value),
+            (QueryError::BadSize(value), Language::EN) => format!("\"{}\" is not a size like 10kb", value),
+            (QueryError::BadSize(value), Language::RU) => format!("\"{}\" — не размер вида 10kb", value),
+        }
+    }
+}
+
+/// Splits on whitespace, keeping `"quoted phrases"` (also in `path:"my notes"`) together.
+/// Each token comes with whether it is a phrase, i.e. starts with a quote (after an optional `-`).
+fn tokenize(input: &str) -> Result<Vec<(String, bool)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut phrase = false;
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                if current.is_empty() || current == "-" {
+                    phrase = true;
+                }
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push((std::mem::take(&mut current), phrase));
+                }
+                phrase = false;
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(QueryError::UnclosedQuote);
+    }
+    if !current.is_empty() {
+        tokens.push((current, phrase));
+    }
+    Ok(tokens)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of a day count since 1970-01-01, the inverse of `days_from_civil`.
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn parse_date(value: &str) -> Option<i64> {
+    let mut parts = value.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next().map_or(Some(1), |part| part.parse().ok())?;
+    let day: i64 = parts.next().map_or(Some(1), |part| part.parse().ok())?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.to_lowercase();
+    let digits_end = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let number: f64 = value[..digits_end].parse().ok()?;
+    let multiplier = match &value[digits_end..] {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1024.0,
+        "m" | "mb" => 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+fn split_compare(value: &str) -> (Compare, &str) {
+    for (prefix, compare) in [(">=", Compare::GreaterOrEqual), ("<=", Compare::LessOrEqual), (">", Compare::Greater), ("<", Compare::Less), ("=", Compare::Equal)] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (compare, rest);
+        }
+    }
+    (Compare::Equal, value)
+}
+
+/// Prefix that sends `fm.status:draft` to the front matter.
+const FRONT_MATTER_PREFIX: &str = "fm.";
+
+fn is_front_matter_key(key: &str) -> bool {
+    key.chars().next().is_some_and(char::is_alphabetic) && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Only known keys and `fm.` keys are fields; `http://`, `std::fs` or `note:` stay plain text.
+fn is_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    match key.strip_prefix(FRONT_MATTER_PREFIX) {
+        Some(field) => is_front_matter_key(field),
+        None => matches!(key.as_str(), "tag" | "path" | "title" | "modified" | "created" | "size"),
+    }
+}
+
+pub fn parse(input: &str) -> Result<Query, QueryError> {
+    let mut clauses = Vec::new();
+    for (token, phrase) in tokenize(input)? {
+        let (negated, token) = match token.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+            _ => (false, token),
+        };
+
+        // Фраза в кавычках ищется как есть, даже если в ней есть двоеточие
+        let key_value = token.split_once(':').filter(|(key, _)| !phrase && is_key(key));
+        let term = match key_value {
+            Some((key, value)) => {
+                let key = key.to_lowercase();
+                if value.is_empty() {
+                    return Err(QueryError::EmptyValue(key));
+                }
+                match key.as_str() {
+                    "tag" => Term::Tag(value.trim_start_matches('#').to_string()),
+                    "path" => Term::Path(value.to_string()),
+                    "title" => Term::Title(value.to_string()),
+                    "modified" | "created" => {
+                        let (compare, date) = split_compare(value);
+                        let day = parse_date(date).ok_or_else(|| QueryError::BadDate(date.to_string()))?;
+                        let field = if key == "modified" { DateField::Modified } else { DateField::Created };
+                        Term::Date { field, compare, day }
+                    }
+                    "size" => {
+                        let (compare, size) = split_compare(value);
+                        let bytes = parse_size(size).ok_or_else(|| QueryError::BadSize(size.to_string()))?;
+                        Term::Size { compare, bytes }
+                    }
+                    _ => Term::Field {
+                        key: key.strip_prefix(FRONT_MATTER_PREFIX).unwrap_or(&key).to_string(),
+                        value: value.to_string(),
+                    },
+                }
+            }
+            None => Term::Text { text: token, phrase },
+        };
+        clauses.push(Clause { negated, term });
+    }
+    Ok(Query { clauses })
+}
+
+impl Query {
+    /// Whether the query needs the metadata filter; plain words are better served by the ranked index.
+    pub fn is_structured(&self) -> bool {
+        self.clauses.iter().any(|clause| clause.negated || !matches!(clause.term, Term::Text { phrase: false, .. }))
+    }
+
+    /// Words and phrases that have to be present, for highlighting.
+    pub fn text_terms(&self) -> Vec<&str> {
+        self.clauses.iter()
+            .filter(|clause| !clause.negated)
+            .filter_map(|clause| match &clause.term {
+                Term::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// An expression for every clause with a text term, built with the search options the way plain search builds them.
+    fn text_patterns(&self, options: SearchOptions) -> Vec<Option<Regex>> {
+        self.clauses.iter()
+            .map(|clause| match &clause.term {
+                Term::Text { text, .. } => search::build_regex(text, SearchOptions { regex: false, ..options }).ok(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn matches(&self, note: &NoteMeta, patterns: &[Option<Regex>]) -> bool {
+        self.clauses.iter()
+            .zip(patterns)
+            .all(|(clause, pattern)| note.satisfies(&clause.term, pattern.as_ref()) != clause.negated)
+    }
+}
+
+/// What queries are evaluated against: a note's location, text, tags, front matter and file times.
+pub struct NoteMeta {
+    pub path: PathBuf,
+    relative: String,
+    content: String,
+    tags: Vec<String>,
+    front_matter: Vec<(String, String)>,
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+    size: u64,
+}
+
+/// Top-level `key: value` pairs, keys lowercased and quotes around values dropped.
+pub(crate) fn yaml_fields(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter(|line| !line.starts_with([' ', '\t', '-']))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().trim_matches(['"', '\'']).to_string()))
+        .collect()
+}
+
+pub(crate) fn front_matter_fields(content: &str) -> Vec<(String, String)> {
+    tags::front_matter_range(content).map_or_else(Vec::new, |range| yaml_fields(&content[range]))
+}
+
+fn day_of(time: Option<SystemTime>) -> Option<i64> {
+    let seconds = time?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some((seconds / SECONDS_PER_DAY) as i64)
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+impl NoteMeta {
+    pub fn load(root: &Path, path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            relative: links::relative_path(root, path),
+            tags: tags::extract_tags(&content),
+            front_matter: front_matter_fields(&content),
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+            size: metadata.len(),
+            content,
+        })
+    }
+
+    fn satisfies(&self, term: &Term, pattern: Option<&Regex>) -> bool {
+        match term {
+            Term::Text { text, .. } => match pattern {
+                Some(pattern) => pattern.is_match(&self.content),
+                None => contains_ignore_case(&self.content, text),
+            },
+            Term::Tag(tag) => self.tags.iter().any(|note_tag| tags::tag_matches(note_tag, tag)),
+            Term::Path(path) => contains_ignore_case(&self.relative, path.trim_start_matches('/')),
+            Term::Title(title) => contains_ignore_case(tags::body(&self.content).lines().next().unwrap_or(""), title),
+            Term::Date { field, compare, day } => {
+                let time = match field {
+                    DateField::Modified => self.modified,
+                    DateField::Created => self.created,
+                };
+                day_of(time).is_some_and(|note_day| compare.holds(note_day, *day))
+            }
+            Term::Size { compare, bytes } => compare.holds(self.size, *bytes),
+            Term::Field { key, value } => self.front_matter.iter()
+                .any(|(field, field_value)| field == key && contains_ignore_case(field_value, value)),
+        }
+    }
+}
+
+/// Every note under `root` matching `query`, sorted by path. Text terms follow the case and whole-word `options`.
+pub fn run(root: &Path, query: &Query, options: SearchOptions) -> Vec<PathBuf> {
+    let patterns = query.text_patterns(options);
+    let mut found: Vec<PathBuf> = links::markdown_files(root)
+        .into_iter()
+        .filter_map(|path| NoteMeta::load(root, &path))
+        .filter(|note| query.matches(note, &patterns))
+        .map(|note| note.path)
+        .collect();
+    found.sort();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(input: &str) -> Vec<Term> {
+        parse(input).ok().expect("query parses").clauses.into_iter().map(|clause| clause.term).collect()
+    }
+
+    fn text(text: &str) -> Term {
+        Term::Text { text: text.to_string(), phrase: false }
+    }
+
+    #[test]
+    fn urls_stay_text() {
+        assert_eq!(terms("http://example.com"), vec![text("http://example.com")]);
+        assert!(!parse("http://example.com").ok().unwrap().is_structured());
+    }
+
+    #[test]
+    fn paths_with_double_colons_stay_text() {
+        assert_eq!(terms("std::fs"), vec![text("std::fs")]);
+    }
+
+    #[test]
+    fn unknown_key_with_trailing_colon_stays_text() {
+        assert_eq!(terms("note: hello"), vec![text("note:"), text("hello")]);
+    }
+
+    #[test]
+    fn known_keys_are_fields() {
+        assert_eq!(terms("tag:#work path:projects"), vec![Term::Tag("work".to_string()), Term::Path("projects".to_string())]);
+    }
+
+    #[test]
+    fn front_matter_needs_prefix() {
+        assert_eq!(terms("fm.status:draft"), vec![Term::Field { key: "status".to_string(), value: "draft".to_string() }]);
+        assert_eq!(terms("status:draft"), vec![text("status:draft")]);
+    }
+}
@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, text::LayoutJob, Color32, FontId, TextFormat};
+
+use crate::fuzzy::fuzzy_match;
+use crate::index::DATA_DIR_NAME;
+use crate::{links, Category, Language, MdReader};
+
+const RECENT_FILE_NAME: &str = "recent";
+const RECENT_LIMIT: usize = 50;
+const RESULTS_LIMIT: usize = 20;
+// Недавно открытые заметки поднимаются выше: чем свежее, тем больше бонус
+const RECENT_BOOST: i32 = 30;
+
+#[derive(Default)]
+pub struct QuickOpen {
+    query: String,
+    selected: usize,
+}
+
+struct Candidate {
+    path: PathBuf,
+    title: String,
+    relative: String,
+    score: i32,
+    title_matches: Vec<usize>,
+}
+
+fn recent_path(root: &Path) -> PathBuf {
+    root.join(DATA_DIR_NAME).join(RECENT_FILE_NAME)
+}
+
+/// Recently opened notes, most recent first, stored relative to the workspace root.
+pub fn load_recent(root: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(recent_path(root))
+        .map(|content| content.lines().map(|line| root.join(line)).filter(|path| path.exists()).collect())
+        .unwrap_or_default()
+}
+
+fn save_recent(root: &Path, recent: &[PathBuf]) -> Result<(), std::io::Error> {
+    let content: Vec<String> = recent.iter()
+        .filter_map(|path| path.strip_prefix(root).ok())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    fs::create_dir_all(root.join(DATA_DIR_NAME))?;
+    fs::write(recent_path(root), content.join("\n"))
+}
+
+fn collect_files(categories: &[Category], files: &mut Vec<(PathBuf, String)>) {
+    for category in categories {
+        files.extend(category.files.iter().map(|file| (file.path.clone(), file.name.clone())));
+        collect_files(&category.subcategories, files);
+    }
+}
+
+impl MdReader {
+    pub(crate) fn remember_recent(&mut self, path: &Path) {
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(RECENT_LIMIT);
+        if let Err(e) = save_recent(&self.root_dir, &self.recent_files) {
+            eprintln!("Ошибка сохранения недавних файлов: {}", e);
+        }
+    }
+
+    pub(crate) fn toggle_quick_open(&mut self) {
+        self.quick_open = match self.quick_open {
+            Some(_) => None,
+            None => Some(QuickOpen::default()),
+        };
+    }
+
+    fn quick_open_candidates(&self, query: &str) -> Vec<Candidate> {
+        let mut files = Vec::new();
+        collect_files(&self.categories, &mut files);
+
+        let mut candidates: Vec<Candidate> = files.into_iter()
+            .filter_map(|(path, title)| {
+                let relative = links::relative_path(&self.root_dir, &path);
+                let by_title = fuzzy_match(query, &title);
+                let by_path = fuzzy_match(query, &relative);
+                let score = match (&by_title, &by_path) {
+                    (None, None) => return None,
+                    (Some((title_score, _)), None) => *title_score,
+                    (None, Some((path_score, _))) => *path_score,
+                    (Some((title_score, _)), Some((path_score, _))) => (*title_score).max(*path_score),
+                };
+                let recent_rank = self.recent_files.iter().position(|recent| *recent == path);
+                // Без запроса показываем только недавние
+                if query.is_empty() && recent_rank.is_none() {
+                    return None;
+                }
+                let boost = recent_rank.map_or(0, |rank| RECENT_BOOST - rank.min(RECENT_BOOST as usize) as i32);
+                Some(Candidate {
+                    path,
+                    title,
+                    relative,
+                    score: score + boost,
+                    title_matches: by_title.map(|(_, matches)| matches).unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.relative.cmp(&b.relative)));
+        candidates.truncate(RESULTS_LIMIT);
+        candidates
+    }
+
+    fn open_from_quick_open(&mut self, path: &Path) {
+        self.load_file(path);
+        self.expand_path_to(path);
+        if let Some(parent) = path.parent() {
+            self.current_dir = parent.to_path_buf();
+        }
+        self.quick_open = None;
+    }
+
+    pub(crate) fn render_quick_open(&mut self, ctx: &egui::Context) {
+        let Some(mut state) = self.quick_open.take() else { return };
+        let hint = match self.current_language {
+            Language::EN => "Go to note…",
+            Language::RU => "Перейти к заметке…",
+        };
+
+        let candidates = self.quick_open_candidates(&state.query);
+        let (up, down, enter, escape) = ctx.input_mut(|i| (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        ));
+        if escape {
+            return;
+        }
+        if down && state.selected + 1 < candidates.len() {
+            state.selected += 1;
+        }
+        if up {
+            state.selected = state.selected.saturating_sub(1);
+        }
+        state.selected = state.selected.min(candidates.len().saturating_sub(1));
+        if enter {
+            if let Some(candidate) = candidates.get(state.selected) {
+                let path = candidate.path.clone();
+                self.open_from_quick_open(&path);
+                return;
+            }
+        }
+
+        let mut to_open = None;
+        egui::Area::new("quick_open")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(520.0);
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut state.query)
+                            .hint_text(hint)
+                            .desired_width(f32::INFINITY)
+                    );
+                    response.request_focus();
+                    if response.changed() {
+                        state.selected = 0;
+                    }
+
+                    let title_format = TextFormat {
+                        font_id: FontId::proportional(16.0),
+                        color: ui.visuals().text_color(),
+                        ..Default::default()
+                    };
+                    let matched_format = TextFormat {
+                        color: ui.visuals().selection.bg_fill,
+                        ..title_format.clone()
+                    };
+                    let path_format = TextFormat {
+                        font_id: FontId::proportional(13.0),
+                        color: ui.visuals().weak_text_color(),
+                        ..Default::default()
+                    };
+
+                    for (index, candidate) in candidates.iter().enumerate() {
+                        let mut job = LayoutJob::default();
+                        for (char_index, c) in candidate.title.chars().enumerate() {
+                            let format = if candidate.title_matches.contains(&char_index) { &matched_format } else { &title_format };
+                            job.append(c.encode_utf8(&mut [0; 4]), 0.0, format.clone());
+                        }
+                        job.append(&format!("   {}", candidate.relative), 0.0, path_format.clone());
+
+                        let response = ui.selectable_label(index == state.selected, job);
+                        if index == state.selected && (up || down) {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            to_open = Some(candidate.path.clone());
+                        }
+                    }
+                    if candidates.is_empty() && !state.query.is_empty() {
+                        let nothing_found = match self.current_language {
+                            Language::EN => "No matching notes",
+                            Language::RU => "Подходящих заметок нет",
+                        };
+                        ui.colored_label(Color32::GRAY, nothing_found);
+                    }
+                });
+            });
+
+        match to_open {
+            Some(path) => self.open_from_quick_open(&path),
+            None => self.quick_open = Some(state),
+        }
+    }
+}
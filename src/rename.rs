@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, Color32, RichText};
+
+use crate::links::{self, FileEdit};
+use crate::names::{self, NameError};
+use crate::{sort, Language, MdReader};
+
+pub struct RenameDialog {
+    target: PathBuf,
+    is_category: bool,
+    new_name: String,
+    // Предпросмотр затронутых файлов; сбрасывается при изменении имени
+    preview: Option<Vec<FileEdit>>,
+    error: Option<String>,
+}
+
+impl MdReader {
+    pub(crate) fn start_rename(&mut self, path: &Path, is_category: bool) {
+        let name = if is_category {
+            sort::entry_file_name(path)
+        } else {
+            path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+        };
+        self.rename_dialog = Some(RenameDialog {
+            target: path.to_path_buf(),
+            is_category,
+            new_name: name,
+            preview: None,
+            error: None,
+        });
+    }
+
+    fn renamed_path(&self, dialog: &RenameDialog) -> Result<PathBuf, String> {
+        let message = |e: NameError| e.message(self.current_language, &self.root_dir);
+        if dialog.new_name.contains('/') {
+            return Err(message(NameError::ForbiddenChar('/')));
+        }
+        let name = names::validate_component(&dialog.new_name).map_err(message)?;
+        let file_name = if dialog.is_category || name.ends_with(".md") {
+            name
+        } else {
+            format!("{}.md", name)
+        };
+        let new_path = dialog.target.with_file_name(file_name);
+        if new_path != dialog.target && new_path.exists() {
+            return Err(message(NameError::AlreadyExists(new_path)));
+        }
+        Ok(new_path)
+    }
+
+    /// Moves entries on disk one by one, rewriting links after each move, and refreshes the tree.
+    /// Stops at the first failure; entries moved before it stay moved with their links rewritten.
+    pub(crate) fn move_entries(&mut self, moves: &[(PathBuf, PathBuf)]) -> Result<(), std::io::Error> {
+        // Занятые имена и пропавшие записи проверяем заранее, чтобы не остановиться на полпути
+        let same_entry = |old: &Path, new: &Path| fs::canonicalize(old).ok() == fs::canonicalize(new).ok();
+        if let Some((_, taken)) = moves.iter().find(|(old, new)| new.exists() && !same_entry(old, new)) {
+            return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, taken.display().to_string()));
+        }
+        if let Some((missing, _)) = moves.iter().find(|(old, _)| !old.exists()) {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, missing.display().to_string()));
+        }
+
+        let mut done = 0;
+        let mut result = Ok(());
+        for (old, new) in moves {
+            // План считаем перед каждым переносом: предыдущие уже переписали свои ссылки
+            let edits = links::plan_moves(&self.root_dir, &[(old.clone(), new.clone())]);
+            if let Err(e) = fs::rename(old, new) {
+                result = Err(e);
+                break;
+            }
+            done += 1;
+            if let Err(e) = Self::finish_move(old, new, edits) {
+                result = Err(e);
+                break;
+            }
+        }
+        let moves = &moves[..done];
+
+        self.current_dir = links::map_path(&self.current_dir, moves);
+        self.remap_tabs(moves);
+        for entry in self.selected_entries.iter_mut().chain(self.recent_files.iter_mut()) {
+            *entry = links::map_path(entry, moves);
+        }
+        if let Some(selected) = self.selected_file.take() {
+            let selected = links::map_path(&selected, moves);
+            self.load_file(&selected);
+        }
+        self.refresh_tree();
+        let current_path = self.current_dir.clone();
+        self.expand_path_to(&current_path);
+        result
+    }
+
+    // Ссылки и порядок в папках после того, как запись уже перенесена
+    fn finish_move(old: &Path, new: &Path, edits: Vec<FileEdit>) -> Result<(), std::io::Error> {
+        for edit in edits {
+            fs::write(&edit.path, edit.new_content)?;
+        }
+        if let (Some(old_dir), Some(new_dir)) = (old.parent(), new.parent()) {
+            if old_dir == new_dir {
+                sort::rename_in_order(old_dir, &sort::entry_file_name(old), &sort::entry_file_name(new))?;
+            } else {
+                sort::remove_from_order(old_dir, &sort::entry_file_name(old))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_rename(&mut self, target: &Path, new_path: &Path) -> Result<(), String> {
+        if target == new_path {
+            return Ok(());
+        }
+        // План ссылок считается заново при переносе: файлы могли измениться после предпросмотра
+        let moves = vec![(target.to_path_buf(), new_path.to_path_buf())];
+        self.move_entries(&moves).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn render_rename_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.rename_dialog.take() else { return };
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+
+        let (window_title, label_text, preview_text, apply_text, cancel_text) = match self.current_language {
+            Language::EN => ("Rename", "New name:", "Preview", "Rename", "Cancel"),
+            Language::RU => ("Переименование", "Новое имя:", "Предпросмотр", "Переименовать", "Отмена"),
+        };
+
+        egui::Window::new(window_title)
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(label_text);
+                if ui.text_edit_singleline(&mut dialog.new_name).changed() {
+                    dialog.preview = None;
+                    dialog.error = None;
+                }
+
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(Color32::from_rgb(235, 87, 87), error);
+                }
+
+                if let Some(edits) = &dialog.preview {
+                    let summary = match self.current_language {
+                        Language::EN => format!("Links will be updated in {} file(s)", edits.len()),
+                        Language::RU => format!("Ссылки будут обновлены в файлах: {}", edits.len()),
+                    };
+                    ui.label(summary);
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        for edit in edits {
+                            let relative = links::relative_path(&self.root_dir, &edit.path);
+                            ui.label(RichText::new(relative).strong());
+                            for change in &edit.changes {
+                                ui.label(RichText::new(format!("{:>4} - {}", change.line, change.before))
+                                    .monospace()
+                                    .color(Color32::from_rgb(235, 87, 87)));
+                                ui.label(RichText::new(format!("{:>4} + {}", change.line, change.after))
+                                    .monospace()
+                                    .color(Color32::from_rgb(76, 175, 80)));
+                            }
+                        }
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    if dialog.preview.is_none() {
+                        if ui.button(preview_text).clicked() {
+                            match self.renamed_path(&dialog) {
+                                Ok(new_path) => {
+                                    let moves = vec![(dialog.target.clone(), new_path)];
+                                    dialog.preview = Some(links::plan_moves(&self.root_dir, &moves));
+                                }
+                                Err(e) => dialog.error = Some(e),
+                            }
+                        }
+                    } else if ui.button(apply_text).clicked() {
+                        apply = true;
+                    }
+                    if ui.button(cancel_text).clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if apply {
+            let result = self.renamed_path(&dialog)
+                .and_then(|new_path| self.apply_rename(&dialog.target, &new_path));
+            match result {
+                Ok(()) => return,
+                Err(e) => dialog.error = Some(e),
+            }
+        }
+        if open && !cancel {
+            self.rename_dialog = Some(dialog);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_a_folder_keeps_links_inside_it_and_updates_links_into_it() {
+        let root = std::env::temp_dir().join(format!("mdreader-rename-{}", std::process::id()));
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("proj/a.md", "[b](b.md) [[b]] [abs](/proj/b.md)");
+        write("proj/b.md", "[a](./a.md#top)");
+        write("other.md", "[a](proj/a.md)");
+        write("archive/.keep.md", "");
+        sort::write_order(&root, &["proj".to_string(), "other.md".to_string()]).unwrap();
+
+        let (old, new) = (root.join("proj"), root.join("archive/proj"));
+        let edits = links::plan_moves(&root, &[(old.clone(), new.clone())]);
+        fs::rename(&old, &new).unwrap();
+        MdReader::finish_move(&old, &new, edits).unwrap();
+
+        let read = |path: &str| fs::read_to_string(root.join(path)).unwrap();
+        assert_eq!(read("archive/proj/a.md"), "[b](b.md) [[b]] [abs](/archive/proj/b.md)");
+        assert_eq!(read("archive/proj/b.md"), "[a](./a.md#top)");
+        assert_eq!(read("other.md"), "[a](archive/proj/a.md)");
+        assert_eq!(sort::read_order(&root), vec!["other.md".to_string()]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
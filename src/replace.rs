@@ -0,0 +1,444 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, Color32, RichText};
+use regex::Regex;
+
+use crate::search::{self, SearchOptions};
+use crate::{collect_category_paths, links, Language, MdReader};
+
+const TMP_EXTENSION: &str = "mdreader-tmp";
+
+/// One match together with what it will be replaced by.
+pub struct ReplaceMatch {
+    pub range: Range<usize>,
+    pub replacement: String,
+    pub line: usize,
+    /// The line(s) around the match before and after the replacement, for the diff preview.
+    pub before: String,
+    pub after: String,
+    pub included: bool,
+}
+
+pub struct ReplaceFile {
+    pub path: PathBuf,
+    /// Content the preview was built from; apply refuses to touch a file that changed since.
+    original: String,
+    pub matches: Vec<ReplaceMatch>,
+}
+
+impl ReplaceFile {
+    pub fn included_count(&self) -> usize {
+        self.matches.iter().filter(|m| m.included).count()
+    }
+
+    /// The file content with every included match replaced.
+    pub fn replaced_content(&self) -> String {
+        let mut result = String::with_capacity(self.original.len());
+        let mut last = 0;
+        for m in self.matches.iter().filter(|m| m.included) {
+            result.push_str(&self.original[last..m.range.start]);
+            result.push_str(&m.replacement);
+            last = m.range.end;
+        }
+        result.push_str(&self.original[last..]);
+        result
+    }
+}
+
+/// Everything needed to revert one applied batch: (file, content before, content after).
+pub struct ReplaceUndo {
+    files: Vec<(PathBuf, String, String)>,
+}
+
+/// Finds every match of `regex` and expands `replacement` for it; `$1`, `${name}` refer to capture groups.
+pub fn plan_replace(files: Vec<PathBuf>, regex: &Regex, replacement: &str) -> Vec<ReplaceFile> {
+    let mut planned: Vec<ReplaceFile> = files.into_iter()
+        .filter_map(|path| {
+            let original = fs::read_to_string(&path).ok()?;
+            let matches: Vec<ReplaceMatch> = regex.captures_iter(&original)
+                .filter_map(|captures| {
+                    let whole = captures.get(0)?;
+                    if whole.is_empty() {
+                        return None;
+                    }
+                    let mut expanded = String::new();
+                    captures.expand(replacement, &mut expanded);
+                    Some(preview_match(&original, whole.range(), expanded))
+                })
+                .collect();
+            (!matches.is_empty()).then_some(ReplaceFile { path, original, matches })
+        })
+        .collect();
+    planned.sort_by(|a, b| a.path.cmp(&b.path));
+    planned
+}
+
+fn preview_match(content: &str, range: Range<usize>, replacement: String) -> ReplaceMatch {
+    let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[range.end..].find('\n').map_or(content.len(), |i| range.end + i);
+    let after = format!("{}{}{}", &content[line_start..range.start], replacement, &content[range.end..line_end]);
+    ReplaceMatch {
+        line: content[..range.start].matches('\n').count() + 1,
+        before: content[line_start..line_end].to_string(),
+        after,
+        range,
+        replacement,
+        included: true,
+    }
+}
+
+/// Writes all files or none: every new content goes to a temporary file first,
+/// and files already replaced are put back if a later rename fails.
+fn write_all(writes: &[(PathBuf, String, String)]) -> Result<(), std::io::Error> {
+    let tmp_path = |path: &Path| path.with_extension(TMP_EXTENSION);
+
+    for (index, (path, _, new_content)) in writes.iter().enumerate() {
+        if let Err(e) = fs::write(tmp_path(path), new_content) {
+            for (written, _, _) in &writes[..=index] {
+                let _ = fs::remove_file(tmp_path(written));
+            }
+            return Err(e);
+        }
+    }
+    for (index, (path, _, _)) in writes.iter().enumerate() {
+        if let Err(e) = fs::rename(tmp_path(path), path) {
+            for (replaced, old_content, _) in &writes[..index] {
+                let _ = fs::write(replaced, old_content);
+            }
+            for (pending, _, _) in &writes[index..] {
+                let _ = fs::remove_file(tmp_path(pending));
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Applies the included matches of every file. Fails without writing anything
+/// if any of the files changed after the preview was built.
+pub fn apply_replace(files: &[ReplaceFile]) -> Result<ReplaceUndo, String> {
+    let mut writes = Vec::new();
+    for file in files.iter().filter(|file| file.included_count() > 0) {
+        let current = fs::read_to_string(&file.path).map_err(|e| e.to_string())?;
+        if current != file.original {
+            return Err(file.path.display().to_string());
+        }
+        writes.push((file.path.clone(), file.original.clone(), file.replaced_content()));
+    }
+    write_all(&writes).map_err(|e| e.to_string())?;
+    Ok(ReplaceUndo { files: writes })
+}
+
+/// Puts back the content from before the batch. Files edited since then are left alone and returned.
+pub fn undo_replace(undo: &ReplaceUndo) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut writes = Vec::new();
+    let mut skipped = Vec::new();
+    for (path, original, replaced) in &undo.files {
+        match fs::read_to_string(path) {
+            Ok(current) if current == *replaced => writes.push((path.clone(), replaced.clone(), original.clone())),
+            _ => skipped.push(path.clone()),
+        }
+    }
+    write_all(&writes)?;
+    Ok(skipped)
+}
+
+#[derive(Default)]
+pub struct ReplaceState {
+    pub open: bool,
+    find: String,
+    replace_with: String,
+    options: SearchOptions,
+    // Папка, в которой ищем; None — вся рабочая папка
+    scope: Option<PathBuf>,
+    preview: Option<Vec<ReplaceFile>>,
+    error: Option<String>,
+    status: Option<String>,
+    undo: Option<ReplaceUndo>,
+}
+
+impl MdReader {
+    fn build_replace_preview(&mut self) {
+        self.replace.preview = None;
+        self.replace.error = None;
+        self.replace.status = None;
+        if self.replace.find.is_empty() {
+            return;
+        }
+        match search::build_regex(&self.replace.find, self.replace.options) {
+            Ok(regex) => {
+                let scope = self.replace.scope.clone().unwrap_or_else(|| self.root_dir.clone());
+                let files = links::markdown_files(&scope);
+                // Группы есть только у регулярных выражений; в обычном режиме «$» — просто символ
+                let replacement = if self.replace.options.regex {
+                    self.replace.replace_with.clone()
+                } else {
+                    self.replace.replace_with.replace('$', "$$")
+                };
+                self.replace.preview = Some(plan_replace(files, &regex, &replacement));
+            }
+            Err(e) => self.replace.error = Some(e.to_string()),
+        }
+    }
+
+    /// Brings the index, the tree and the open note up to date after files were rewritten.
+    pub(crate) fn reload_changed_files(&mut self, changed: &[(PathBuf, String)]) {
+        for (path, content) in changed {
+            self.index.update(&self.root_dir, path, content);
+            self.link_graph.update(path, content);
+            if self.selected_file.as_ref() == Some(path) {
+                self.file_content = content.clone();
+                self.highlight = None;
+            }
+        }
+        self.refresh_tree();
+    }
+
+    fn apply_replace_preview(&mut self) {
+        let Some(preview) = &self.replace.preview else { return };
+        match apply_replace(preview) {
+            Ok(undo) => {
+                let matches: usize = preview.iter().map(ReplaceFile::included_count).sum();
+                let changed: Vec<(PathBuf, String)> = undo.files.iter()
+                    .map(|(path, _, replaced)| (path.clone(), replaced.clone()))
+                    .collect();
+                self.replace.status = Some(match self.current_language {
+                    Language::EN => format!("Replaced {} matches in {} files", matches, changed.len()),
+                    Language::RU => format!("Заменено совпадений: {}, файлов: {}", matches, changed.len()),
+                });
+                self.replace.preview = None;
+                self.replace.undo = Some(undo);
+                self.reload_changed_files(&changed);
+            }
+            Err(e) => {
+                self.replace.error = Some(match self.current_language {
+                    Language::EN => format!("Nothing was replaced: {}. Build the preview again.", e),
+                    Language::RU => format!("Ничего не заменено: {}. Постройте предпросмотр заново.", e),
+                });
+            }
+        }
+    }
+
+    fn undo_last_replace(&mut self) {
+        let Some(undo) = self.replace.undo.take() else { return };
+        match undo_replace(&undo) {
+            Ok(skipped) => {
+                let changed: Vec<(PathBuf, String)> = undo.files.iter()
+                    .filter(|(path, _, _)| !skipped.contains(path))
+                    .map(|(path, original, _)| (path.clone(), original.clone()))
+                    .collect();
+                self.replace.status = Some(match (skipped.len(), self.current_language) {
+                    (0, Language::EN) => "Replacement undone".to_string(),
+                    (0, Language::RU) => "Замена отменена".to_string(),
+                    (n, Language::EN) => format!("Replacement undone; {} files changed since and were kept", n),
+                    (n, Language::RU) => format!("Замена отменена; файлы, изменённые после неё, не тронуты: {}", n),
+                });
+                self.reload_changed_files(&changed);
+            }
+            Err(e) => {
+                eprintln!("Ошибка отмены замены: {}", e);
+                self.replace.error = Some(e.to_string());
+                self.replace.undo = Some(undo);
+            }
+        }
+    }
+
+    pub(crate) fn render_replace_window(&mut self, ctx: &egui::Context) {
+        if !self.replace.open {
+            return;
+        }
+        let mut open = true;
+        let mut rebuild = false;
+        let mut invalidate = false;
+        let mut apply = false;
+        let mut undo = false;
+
+        let (window_title, find_hint, replace_hint, scope_label, whole_workspace, preview_text, apply_text, undo_text, nothing_found) = match self.current_language {
+            Language::EN => ("Find and replace in notes", "Find…", "Replace with… ($1 for groups)", "In:", "Whole workspace", "Preview", "Replace", "Undo", "Nothing found"),
+            Language::RU => ("Найти и заменить в заметках", "Найти…", "Заменить на… ($1 для групп)", "Где:", "Вся рабочая папка", "Предпросмотр", "Заменить", "Отменить", "Ничего не найдено"),
+        };
+        let (case_tip, word_tip, regex_tip) = match self.current_language {
+            Language::EN => ("Match case", "Whole words", "Regular expression"),
+            Language::RU => ("Учитывать регистр", "Слово целиком", "Регулярное выражение"),
+        };
+
+        let mut categories = Vec::new();
+        collect_category_paths(&self.categories, &mut categories);
+        let root = self.root_dir.clone();
+        let scope_text = |scope: &Option<PathBuf>| match scope {
+            Some(path) => links::relative_path(&root, path),
+            None => whole_workspace.to_string(),
+        };
+
+        egui::Window::new(window_title)
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                let state = &mut self.replace;
+                let find = ui.add(egui::TextEdit::singleline(&mut state.find).hint_text(find_hint).desired_width(f32::INFINITY));
+                let replace_with = ui.add(egui::TextEdit::singleline(&mut state.replace_with).hint_text(replace_hint).desired_width(f32::INFINITY));
+                // Изменённый запрос делает предпросмотр устаревшим; новый строим по Enter или кнопке
+                invalidate |= find.changed() || replace_with.changed();
+                rebuild |= (find.lost_focus() || replace_with.lost_focus()) && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                ui.horizontal(|ui| {
+                    rebuild |= ui.toggle_value(&mut state.options.case_sensitive, "Aa").on_hover_text(case_tip).changed();
+                    rebuild |= ui.toggle_value(&mut state.options.whole_word, "W").on_hover_text(word_tip).changed();
+                    rebuild |= ui.toggle_value(&mut state.options.regex, ".*").on_hover_text(regex_tip).changed();
+                    ui.label(scope_label);
+                    egui::ComboBox::from_id_source("replace_scope")
+                        .selected_text(scope_text(&state.scope))
+                        .show_ui(ui, |ui| {
+                            rebuild |= ui.selectable_value(&mut state.scope, None, whole_workspace).changed();
+                            for category in &categories {
+                                let text = scope_text(&Some(category.clone()));
+                                rebuild |= ui.selectable_value(&mut state.scope, Some(category.clone()), text).changed();
+                            }
+                        });
+                });
+
+                if let Some(error) = &state.error {
+                    ui.colored_label(Color32::from_rgb(235, 87, 87), error);
+                }
+                if let Some(status) = &state.status {
+                    ui.label(RichText::new(status).weak());
+                }
+
+                if let Some(preview) = &mut state.preview {
+                    if preview.is_empty() {
+                        ui.label(nothing_found);
+                    }
+                    egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                        for file in preview.iter_mut() {
+                            let included = file.included_count();
+                            let mut all = included == file.matches.len();
+                            let relative = links::relative_path(&root, &file.path);
+                            let header = RichText::new(format!("{} ({}/{})", relative, included, file.matches.len())).strong();
+                            let partly = included > 0 && !all;
+                            if ui.add(egui::Checkbox::new(&mut all, header).indeterminate(partly)).changed() {
+                                for m in &mut file.matches {
+                                    m.included = all;
+                                }
+                            }
+                            ui.indent(&file.path, |ui| {
+                                for m in &mut file.matches {
+                                    ui.horizontal_top(|ui| {
+                                        ui.checkbox(&mut m.included, "");
+                                        ui.vertical(|ui| {
+                                            ui.label(RichText::new(format!("{:>4} - {}", m.line, m.before))
+                                                .monospace()
+                                                .color(Color32::from_rgb(235, 87, 87)));
+                                            ui.label(RichText::new(format!("{:>4} + {}", m.line, m.after))
+                                                .monospace()
+                                                .color(Color32::from_rgb(76, 175, 80)));
+                                        });
+                                    });
+                                }
+                            });
+                        }
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button(preview_text).clicked() {
+                        rebuild = true;
+                    }
+                    let total: usize = state.preview.iter().flatten().map(ReplaceFile::included_count).sum();
+                    if ui.add_enabled(total > 0, egui::Button::new(format!("{} ({})", apply_text, total))).clicked() {
+                        apply = true;
+                    }
+                    if state.undo.is_some() && ui.button(undo_text).clicked() {
+                        undo = true;
+                    }
+                });
+            });
+
+        if invalidate {
+            self.replace.preview = None;
+            self.replace.error = None;
+        }
+        if rebuild {
+            self.build_replace_preview();
+        }
+        if apply {
+            self.apply_replace_preview();
+        }
+        if undo {
+            self.undo_last_replace();
+        }
+        self.replace.open = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Notes in a fresh temp folder, returned with their paths.
+    fn notes(contents: &[&str]) -> (PathBuf, Vec<PathBuf>) {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!("mdreader-replace-{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+        fs::create_dir_all(&root).unwrap();
+        let paths = contents.iter().enumerate()
+            .map(|(i, content)| {
+                let path = root.join(format!("note{}.md", i));
+                fs::write(&path, content).unwrap();
+                path
+            })
+            .collect();
+        (root, paths)
+    }
+
+    #[test]
+    fn expands_capture_groups() {
+        let (root, paths) = notes(&["2026-01-31 and 2025-12-01"]);
+        let regex = Regex::new(r"(?<year>\d{4})-(\d{2})-(\d{2})").unwrap();
+        let planned = plan_replace(paths.clone(), &regex, "$3.$2.${year}");
+        assert_eq!(planned[0].matches.len(), 2);
+        assert_eq!(planned[0].matches[0].after, "31.01.2026 and 2025-12-01");
+        apply_replace(&planned).unwrap();
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "31.01.2026 and 01.12.2025");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn skips_unchecked_matches() {
+        let (root, paths) = notes(&["cat cat cat", "cat"]);
+        let regex = Regex::new("cat").unwrap();
+        let mut planned = plan_replace(paths.clone(), &regex, "dog");
+        planned[0].matches[1].included = false;
+        planned[1].matches[0].included = false;
+        assert_eq!(planned[0].replaced_content(), "dog cat dog");
+        apply_replace(&planned).unwrap();
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "dog cat dog");
+        assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "cat");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn refuses_files_changed_after_preview() {
+        let (root, paths) = notes(&["old one", "old two"]);
+        let regex = Regex::new("old").unwrap();
+        let planned = plan_replace(paths.clone(), &regex, "new");
+        fs::write(&paths[1], "old two, edited").unwrap();
+        assert_eq!(apply_replace(&planned).err(), Some(paths[1].display().to_string()));
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "old one");
+        assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "old two, edited");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn undo_leaves_files_edited_after_replace() {
+        let (root, paths) = notes(&["old one", "old two"]);
+        let regex = Regex::new("old").unwrap();
+        let undo = apply_replace(&plan_replace(paths.clone(), &regex, "new")).unwrap();
+        fs::write(&paths[1], "new two, edited").unwrap();
+        assert_eq!(undo_replace(&undo).unwrap(), vec![paths[1].clone()]);
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "old one");
+        assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "new two, edited");
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,394 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, text::LayoutJob, Color32, FontId, RichText, TextFormat};
+use regex::{Regex, RegexBuilder};
+
+use crate::index::{self, SearchIndex};
+use crate::{links, query, Language, MdReader};
+
+// Длинные строки обрезаем вокруг первого совпадения
+const SNIPPET_CONTEXT_BEFORE: usize = 60;
+const SNIPPET_MAX_LEN: usize = 200;
+// Сколько лучших заметок из индекса показываем
+const RANKED_RESULTS_LIMIT: usize = 200;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+}
+
+/// A line containing one or more matches.
+pub struct LineMatch {
+    pub line: usize,
+    pub snippet: String,
+    /// Matches inside `snippet`, for highlighting.
+    pub snippet_ranges: Vec<Range<usize>>,
+    /// The same matches as byte ranges in the whole file.
+    pub ranges: Vec<Range<usize>>,
+}
+
+pub struct FileResult {
+    pub path: PathBuf,
+    pub lines: Vec<LineMatch>,
+}
+
+impl FileResult {
+    pub fn match_count(&self) -> usize {
+        self.lines.iter().map(|line| line.ranges.len()).sum()
+    }
+}
+
+/// Matches to highlight in the open note. `focused` is the one to scroll to.
+pub struct Highlight {
+    pub ranges: Vec<Range<usize>>,
+    pub focused: usize,
+    pub scroll_pending: bool,
+}
+
+impl Highlight {
+    pub fn focused_range(&self) -> Option<Range<usize>> {
+        self.ranges.get(self.focused).cloned()
+    }
+}
+
+pub fn build_regex(query: &str, options: SearchOptions) -> Result<Regex, regex::Error> {
+    let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+    build_pattern(pattern, options)
+}
+
+/// Matches any word of a plain-text query, which is how ranked results are highlighted.
+/// «е» and «ё» match each other, as they do in the index.
+fn build_terms_regex(query: &str, options: SearchOptions) -> Result<Regex, regex::Error> {
+    let words: Vec<String> = query.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            regex::escape(word).chars().map(|c| match c {
+                'е' | 'ё' => "[её]".to_string(),
+                'Е' | 'Ё' => "[ЕЁ]".to_string(),
+                c => c.to_string(),
+            }).collect()
+        })
+        .collect();
+    build_pattern(words.join("|"), options)
+}
+
+fn build_pattern(mut pattern: String, options: SearchOptions) -> Result<Regex, regex::Error> {
+    if options.whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .multi_line(true)
+        .build()
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// All matches of `regex` in `content`, grouped by line.
+pub fn search_content(content: &str, regex: &Regex) -> Vec<LineMatch> {
+    let mut lines: Vec<LineMatch> = Vec::new();
+    let mut line_start = 0;
+
+    for (index, line) in content.split('\n').enumerate() {
+        let ranges: Vec<Range<usize>> = regex.find_iter(line)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+            .collect();
+
+        if let Some(first) = ranges.first() {
+            let start = floor_char_boundary(line, first.start.saturating_sub(SNIPPET_CONTEXT_BEFORE));
+            let end = floor_char_boundary(line, (start + SNIPPET_MAX_LEN).min(line.len()));
+            let prefix = if start > 0 { "…" } else { "" };
+            let snippet = format!("{}{}", prefix, &line[start..end]);
+            let snippet_ranges = ranges.iter()
+                .filter(|range| range.start < end)
+                .map(|range| range.start - start + prefix.len()..range.end.min(end) - start + prefix.len())
+                .collect();
+
+            lines.push(LineMatch {
+                line: index + 1,
+                snippet,
+                snippet_ranges,
+                ranges: ranges.iter().map(|range| line_start + range.start..line_start + range.end).collect(),
+            });
+        }
+        line_start += line.len() + 1;
+    }
+    lines
+}
+
+pub fn search_files(root: &Path, regex: &Regex) -> Vec<FileResult> {
+    let mut results: Vec<FileResult> = links::markdown_files(root)
+        .into_iter()
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            let lines = search_content(&content, regex);
+            (!lines.is_empty()).then_some(FileResult { path, lines })
+        })
+        .collect();
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+    results
+}
+
+/// Background of every match except the focused one, which uses the selection color.
+pub fn match_color() -> Color32 {
+    Color32::from_rgba_unmultiplied(255, 200, 0, 90)
+}
+
+/// Appends text with the given byte ranges drawn on a highlight background.
+pub fn append_highlighted(job: &mut LayoutJob, text: &str, ranges: &[(Range<usize>, Color32)], format: TextFormat) {
+    let mut last = 0;
+    for (range, highlight) in ranges {
+        let start = range.start.clamp(last, text.len());
+        let end = range.end.clamp(start, text.len());
+        job.append(&text[last..start], 0.0, format.clone());
+        job.append(&text[start..end], 0.0, TextFormat { background: *highlight, ..format.clone() });
+        last = end;
+    }
+    job.append(&text[last..], 0.0, format);
+}
+
+#[derive(Default)]
+pub struct SearchState {
+    pub query: String,
+    pub options: SearchOptions,
+    pub results: Vec<FileResult>,
+    // Выражение, которым подсвечены результаты
+    regex: Option<Regex>,
+    pub error: Option<String>,
+    pub focus_query: bool,
+    searched: bool,
+}
+
+/// Searches every note under `root`. Plain-text queries go through the index and come back ranked;
+/// regexes scan every note; queries with `tag:`, `path:`, `-exclusions` or `"phrases"` filter notes
+/// by their metadata. Also returns the expression that highlights the matches.
+pub fn search_workspace(root: &Path, index: &SearchIndex, query: &str, options: SearchOptions, language: Language) -> Result<(Vec<FileResult>, Option<Regex>), String> {
+    if !options.regex {
+        match query::parse(query) {
+            Ok(parsed) if parsed.is_structured() => return structured_search(root, &parsed, options),
+            Ok(_) => {}
+            Err(e) => return Err(e.message(language)),
+        }
+    }
+
+    let ranked = !options.regex && index::tokenize(query).next().is_some();
+    let regex = if ranked {
+        build_terms_regex(query, options)
+    } else {
+        build_regex(query, options)
+    };
+    let regex = regex.map_err(|e| e.to_string())?;
+
+    let results = if ranked {
+        index.search(root, query, RANKED_RESULTS_LIMIT)
+            .into_iter()
+            .filter_map(|(path, _)| {
+                let content = fs::read_to_string(&path).ok()?;
+                let lines = search_content(&content, &regex);
+                (!lines.is_empty()).then_some(FileResult { path, lines })
+            })
+            .collect()
+    } else {
+        search_files(root, &regex)
+    };
+    Ok((results, Some(regex)))
+}
+
+fn structured_search(root: &Path, parsed: &query::Query, options: SearchOptions) -> Result<(Vec<FileResult>, Option<Regex>), String> {
+    let terms: Vec<String> = parsed.text_terms().into_iter().map(regex::escape).collect();
+    let regex = if terms.is_empty() {
+        None
+    } else {
+        Some(build_pattern(terms.join("|"), options).map_err(|e| e.to_string())?)
+    };
+
+    let results = query::run(root, parsed, options)
+        .into_iter()
+        .map(|path| {
+            let lines = regex.as_ref()
+                .and_then(|regex| Some(search_content(&fs::read_to_string(&path).ok()?, regex)))
+                .unwrap_or_default();
+            FileResult { path, lines }
+        })
+        .collect();
+    Ok((results, regex))
+}
+
+impl MdReader {
+    pub(crate) fn run_search(&mut self) {
+        self.search.error = None;
+        self.search.results.clear();
+        self.search.regex = None;
+        self.search.searched = false;
+        if self.search.query.is_empty() {
+            return;
+        }
+        self.index_saved_note();
+        match search_workspace(&self.root_dir, &self.index, &self.search.query, self.search.options, self.current_language) {
+            Ok((results, regex)) => {
+                self.search.results = results;
+                self.search.regex = regex;
+                self.search.searched = true;
+            }
+            Err(e) => self.search.error = Some(e),
+        }
+    }
+
+    /// Opens a note and highlights every match of the current query, focusing the one at `range`.
+    pub(crate) fn open_search_result(&mut self, path: &Path, range: Range<usize>) {
+        self.load_file(path);
+        let Some(regex) = &self.search.regex else { return };
+        let ranges: Vec<Range<usize>> = search_content(&self.file_content, regex)
+            .into_iter()
+            .flat_map(|line| line.ranges)
+            .collect();
+        let focused = ranges.iter().position(|r| *r == range).unwrap_or(0);
+        self.highlight = Some(Highlight { ranges, focused, scroll_pending: true });
+    }
+
+    pub(crate) fn render_search_panel(&mut self, ui: &mut egui::Ui) {
+        let (hint, syntax_tip, case_tip, word_tip, regex_tip, nothing_found) = match self.current_language {
+            Language::EN => ("Search in all notes…",
+                "\"exact phrase\"  tag:work  -tag:done  path:projects  title:plan\nmodified:>2026-01-01  created:<2025  size:>10kb  fm.status:draft",
+                "Match case", "Whole words", "Regular expression", "Nothing found"),
+            Language::RU => ("Поиск по всем заметкам…",
+                "\"точная фраза\"  tag:работа  -tag:готово  path:проекты  title:план\nmodified:>2026-01-01  created:<2025  size:>10kb  fm.status:черновик",
+                "Учитывать регистр", "Слово целиком", "Регулярное выражение", "Ничего не найдено"),
+        };
+
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.search.query)
+                .hint_text(hint)
+                .desired_width(f32::INFINITY)
+        ).on_hover_text(syntax_tip);
+        if self.search.focus_query {
+            response.request_focus();
+            self.search.focus_query = false;
+        }
+        let mut search_now = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+        ui.horizontal(|ui| {
+            let options = &mut self.search.options;
+            search_now |= ui.toggle_value(&mut options.case_sensitive, "Aa").on_hover_text(case_tip).changed();
+            search_now |= ui.toggle_value(&mut options.whole_word, "W").on_hover_text(word_tip).changed();
+            search_now |= ui.toggle_value(&mut options.regex, ".*").on_hover_text(regex_tip).changed();
+        });
+        if search_now {
+            self.run_search();
+        }
+
+        if let Some(error) = &self.search.error {
+            ui.colored_label(Color32::from_rgb(235, 87, 87), error);
+            return;
+        }
+        if !self.search.searched {
+            return;
+        }
+        if self.search.results.is_empty() {
+            ui.label(nothing_found);
+            return;
+        }
+
+        let total: usize = self.search.results.iter().map(FileResult::match_count).sum();
+        let summary = match self.current_language {
+            Language::EN => format!("{} matches in {} files", total, self.search.results.len()),
+            Language::RU => format!("Совпадений: {}, файлов: {}", total, self.search.results.len()),
+        };
+        let (save_text, save_tip, name_hint) = match self.current_language {
+            Language::EN => ("⭐ Save", "Save as a smart folder in the sidebar", "Folder name…"),
+            Language::RU => ("⭐ Сохранить", "Сохранить как умную папку в сайдбаре", "Имя папки…"),
+        };
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(summary).weak());
+            if self.smart_folders.new_name.is_none() && ui.small_button(save_text).on_hover_text(save_tip).clicked() {
+                self.smart_folders.new_name = Some(String::new());
+            }
+        });
+        let mut save_as = None;
+        let mut cancel = false;
+        if let Some(name) = &mut self.smart_folders.new_name {
+            ui.horizontal(|ui| {
+                let response = ui.add(egui::TextEdit::singleline(name).hint_text(name_hint).desired_width(150.0));
+                let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.small_button("✔").clicked() || entered {
+                    save_as = Some(name.clone());
+                }
+                cancel = ui.small_button("✕").clicked();
+            });
+        }
+        if cancel {
+            self.smart_folders.new_name = None;
+        }
+        if let Some(name) = save_as {
+            let query = self.search.query.clone();
+            self.save_search(&name, &query, self.search.options);
+            self.smart_folders.new_name = None;
+        }
+
+        let format = TextFormat {
+            font_id: FontId::proportional(14.0),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+        let highlight = ui.visuals().selection.bg_fill;
+        let mut to_open = None;
+
+        egui::ScrollArea::vertical().id_source("search_results").show(ui, |ui| {
+            for result in &self.search.results {
+                let relative = links::relative_path(&self.root_dir, &result.path);
+                // Заметка подошла только по метаданным — совпадений в тексте нет
+                if result.lines.is_empty() {
+                    if ui.selectable_label(false, RichText::new(format!("📄 {}", relative)).strong()).clicked() {
+                        to_open = Some((result.path.clone(), 0..0));
+                    }
+                    continue;
+                }
+                egui::CollapsingHeader::new(RichText::new(format!("📄 {} ({})", relative, result.match_count())).strong())
+                    .id_source(&result.path)
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        for line in &result.lines {
+                            let mut job = LayoutJob::default();
+                            let number_format = TextFormat { color: ui.visuals().weak_text_color(), ..format.clone() };
+                            job.append(&format!("{:>4}: ", line.line), 0.0, number_format);
+                            let ranges: Vec<(Range<usize>, Color32)> = line.snippet_ranges.iter()
+                                .map(|range| (range.clone(), highlight))
+                                .collect();
+                            append_highlighted(&mut job, &line.snippet, &ranges, format.clone());
+                            let response = ui.add(egui::Label::new(job).sense(egui::Sense::click()));
+                            if response.clicked() {
+                                to_open = Some((result.path.clone(), line.ranges[0].clone()));
+                            }
+                        }
+                    });
+            }
+        });
+
+        if let Some((path, range)) = to_open {
+            self.open_search_result(&path, range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terms_regex_folds_yo() {
+        let regex = build_terms_regex("елка", SearchOptions::default()).ok().unwrap();
+        assert!(regex.is_match("Ёлка в лесу"));
+        assert!(regex.is_match("ёлка"));
+        assert!(!regex.is_match("палка"));
+    }
+}
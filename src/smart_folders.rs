@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use eframe::egui::{self, RichText};
+
+use crate::index::DATA_DIR_NAME;
+use crate::search::{self, SearchOptions};
+use crate::{links, query, Language, MdReader, SidebarTab};
+
+const SAVED_SEARCHES_FILE_NAME: &str = "saved-searches";
+// Пока заметку редактируют, она сохраняется на каждое нажатие — пересчитываем не чаще этого
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+// Как часто проверять, досчитал ли фоновый поток
+const WORKER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type Results = HashMap<(String, SearchOptions), Vec<PathBuf>>;
+
+/// A search saved as a virtual folder in the sidebar.
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    /// Case, whole-word and regex switches of the search panel when it was saved.
+    pub options: SearchOptions,
+}
+
+#[derive(Default)]
+pub struct SmartFolders {
+    pub searches: Vec<SavedSearch>,
+    // Результаты по запросу с настройками и поколение графа ссылок, на котором они посчитаны
+    results: Results,
+    generation: Option<u64>,
+    last_refresh: Option<Instant>,
+    // Поиски гоняются в фоновом потоке, чтобы большой vault не подвешивал окно
+    worker: Option<Receiver<Results>>,
+    /// Name typed into the search panel's "Save" field, while it is open.
+    pub new_name: Option<String>,
+}
+
+fn saved_searches_path(root: &Path) -> PathBuf {
+    root.join(DATA_DIR_NAME).join(SAVED_SEARCHES_FILE_NAME)
+}
+
+// Настройки хранятся словами через запятую: «case,word,regex»
+fn options_to_string(options: SearchOptions) -> String {
+    [(options.case_sensitive, "case"), (options.whole_word, "word"), (options.regex, "regex")]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn options_from_str(text: &str) -> SearchOptions {
+    let has = |name: &str| text.split(',').any(|flag| flag == name);
+    SearchOptions { case_sensitive: has("case"), whole_word: has("word"), regex: has("regex") }
+}
+
+pub fn load(root: &Path) -> SmartFolders {
+    // Строка: имя, запрос и настройки через табуляцию; в старых файлах настроек нет
+    let searches = fs::read_to_string(saved_searches_path(root))
+        .map(|content| {
+            content.lines()
+                .filter_map(|line| {
+                    let mut fields = line.split('\t');
+                    let name = fields.next()?.to_string();
+                    let query = fields.next()?.to_string();
+                    let options = fields.next().map_or_else(SearchOptions::default, options_from_str);
+                    Some(SavedSearch { name, query, options })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    SmartFolders { searches, ..Default::default() }
+}
+
+fn save(root: &Path, searches: &[SavedSearch]) -> Result<(), std::io::Error> {
+    let content: Vec<String> = searches.iter()
+        .map(|search| format!(
+            "{}\t{}\t{}",
+            search.name.replace(['\t', '\n'], " "),
+            search.query.replace(['\t', '\n'], " "),
+            options_to_string(search.options),
+        ))
+        .collect();
+    fs::create_dir_all(root.join(DATA_DIR_NAME))?;
+    fs::write(saved_searches_path(root), content.join("\n"))
+}
+
+/// Notes a saved search matches, the way the search panel finds them with the same options.
+fn run_saved_search(root: &Path, query: &str, options: SearchOptions) -> Vec<PathBuf> {
+    if options.regex {
+        return search::build_regex(query, options)
+            .map(|regex| search::search_files(root, &regex).into_iter().map(|result| result.path).collect())
+            .unwrap_or_default();
+    }
+    query::parse(query)
+        .map(|parsed| query::run(root, &parsed, options))
+        .unwrap_or_default()
+}
+
+impl MdReader {
+    pub(crate) fn save_search(&mut self, name: &str, query: &str, options: SearchOptions) {
+        let name = name.trim();
+        let name = if name.is_empty() { query.trim() } else { name };
+        match self.smart_folders.searches.iter_mut().find(|search| search.name == name) {
+            Some(existing) => {
+                existing.query = query.to_string();
+                existing.options = options;
+            }
+            None => self.smart_folders.searches.push(SavedSearch { name: name.to_string(), query: query.to_string(), options }),
+        }
+        self.smart_folders.generation = None;
+        if let Err(e) = save(&self.root_dir, &self.smart_folders.searches) {
+            eprintln!("Ошибка сохранения поисков: {}", e);
+        }
+    }
+
+    fn delete_saved_search(&mut self, index: usize) {
+        self.smart_folders.searches.remove(index);
+        if let Err(e) = save(&self.root_dir, &self.smart_folders.searches) {
+            eprintln!("Ошибка сохранения поисков: {}", e);
+        }
+    }
+
+    /// Re-runs every saved search once notes changed, so smart folders stay live.
+    /// The searches run on a background thread; the folders keep their old results until it is done.
+    fn refresh_smart_folders(&mut self, ctx: &egui::Context) {
+        let folders = &mut self.smart_folders;
+        if let Some(worker) = &folders.worker {
+            match worker.try_recv() {
+                Ok(results) => folders.results = results,
+                Err(TryRecvError::Empty) => {
+                    ctx.request_repaint_after(WORKER_POLL_INTERVAL);
+                    return;
+                }
+                Err(TryRecvError::Disconnected) => {}
+            }
+            folders.worker = None;
+        }
+        if folders.generation == Some(self.link_graph.generation) {
+            return;
+        }
+        if let Some(elapsed) = folders.last_refresh.map(|last| last.elapsed()) {
+            if elapsed < REFRESH_INTERVAL && folders.generation.is_some() {
+                ctx.request_repaint_after(REFRESH_INTERVAL - elapsed);
+                return;
+            }
+        }
+
+        let root = self.root_dir.clone();
+        let searches: Vec<(String, SearchOptions)> = folders.searches.iter().map(|search| (search.query.clone(), search.options)).collect();
+        let (sender, receiver) = mpsc::channel();
+        let repaint = ctx.clone();
+        thread::spawn(move || {
+            let results: Results = searches.into_iter()
+                .map(|(query, options)| {
+                    let found = run_saved_search(&root, &query, options);
+                    ((query, options), found)
+                })
+                .collect();
+            let _ = sender.send(results);
+            repaint.request_repaint();
+        });
+        folders.worker = Some(receiver);
+        folders.generation = Some(self.link_graph.generation);
+        folders.last_refresh = Some(Instant::now());
+    }
+
+    /// Saved searches above the category tree; each lists the notes its query currently matches.
+    pub(crate) fn render_smart_folders(&mut self, ui: &mut egui::Ui) {
+        if self.smart_folders.searches.is_empty() {
+            return;
+        }
+        self.refresh_smart_folders(ui.ctx());
+
+        let (edit_text, delete_text) = match self.current_language {
+            Language::EN => ("Edit query", "Delete"),
+            Language::RU => ("Изменить запрос", "Удалить"),
+        };
+        let mut to_open = None;
+        let mut to_edit = None;
+        let mut to_delete = None;
+
+        for (index, search) in self.smart_folders.searches.iter().enumerate() {
+            let found = self.smart_folders.results.get(&(search.query.clone(), search.options));
+            let count = found.map_or(0, Vec::len);
+            let header = egui::CollapsingHeader::new(RichText::new(format!("🔎 {} ({})", search.name, count)).size(16.0))
+                .id_source(("smart_folder", &search.name))
+                .show(ui, |ui| {
+                    for path in found.into_iter().flatten() {
+                        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                        let selected = self.selected_file.as_ref() == Some(path);
+                        let relative = links::relative_path(&self.root_dir, path);
+                        if ui.selectable_label(selected, format!("📄 {}", name)).on_hover_text(relative).clicked() {
+                            to_open = Some(path.clone());
+                        }
+                    }
+                });
+            header.header_response
+                .on_hover_text(&search.query)
+                .context_menu(|ui| {
+                    if ui.button(edit_text).clicked() {
+                        to_edit = Some((search.query.clone(), search.options));
+                        ui.close_menu();
+                    }
+                    if ui.button(delete_text).clicked() {
+                        to_delete = Some(index);
+                        ui.close_menu();
+                    }
+                });
+        }
+        ui.separator();
+
+        if let Some(path) = to_open {
+            self.load_file(&path);
+        }
+        if let Some((query, options)) = to_edit {
+            self.sidebar_tab = SidebarTab::Search;
+            self.search.query = query;
+            self.search.options = options;
+            self.search.focus_query = true;
+            self.run_search();
+        }
+        if let Some(index) = to_delete {
+            self.delete_saved_search(index);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::{Category, FileEntry, Language};
+
+/// Per-directory file with the manual order of its entries, one file name per line.
+pub const ORDER_FILE_NAME: &str = ".mdreader-order";
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum SortMode {
+    Name,
+    Title,
+    Modified,
+    Created,
+    Manual,
+}
+
+impl SortMode {
+    pub const ALL: [SortMode; 5] = [
+        SortMode::Name,
+        SortMode::Title,
+        SortMode::Modified,
+        SortMode::Created,
+        SortMode::Manual,
+    ];
+
+    pub fn label(self, language: Language) -> &'static str {
+        match (self, language) {
+            (SortMode::Name, Language::EN) => "Name",
+            (SortMode::Name, Language::RU) => "Имя",
+            (SortMode::Title, Language::EN) => "Title",
+            (SortMode::Title, Language::RU) => "Заголовок",
+            (SortMode::Modified, Language::EN) => "Modified",
+            (SortMode::Modified, Language::RU) => "Изменено",
+            (SortMode::Created, Language::EN) => "Created",
+            (SortMode::Created, Language::RU) => "Создано",
+            (SortMode::Manual, Language::EN) => "Manual",
+            (SortMode::Manual, Language::RU) => "Вручную",
+        }
+    }
+}
+
+/// Something that can be listed in the sidebar tree.
+trait Sortable {
+    fn file_name(&self) -> String;
+    fn title(&self) -> &str;
+    fn modified(&self) -> Option<SystemTime>;
+    fn created(&self) -> Option<SystemTime>;
+}
+
+impl Sortable for Category {
+    fn file_name(&self) -> String {
+        entry_file_name(&self.path)
+    }
+
+    fn title(&self) -> &str {
+        &self.name
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+}
+
+impl Sortable for FileEntry {
+    fn file_name(&self) -> String {
+        entry_file_name(&self.path)
+    }
+
+    fn title(&self) -> &str {
+        &self.name
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+}
+
+pub fn entry_file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// Буква без учёта регистра; «ё» идёт сразу за «е», а не после «я», как в Юникоде
+fn letter_key(c: char) -> (char, bool) {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    if lower == 'ё' { ('е', true) } else { (lower, false) }
+}
+
+/// Compares strings so that embedded numbers are ordered by value: "2 - Setup" < "10 - Usage".
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut a_num = String::new();
+                while let Some(c) = a_chars.next_if(|c| c.is_ascii_digit()) {
+                    a_num.push(c);
+                }
+                let mut b_num = String::new();
+                while let Some(c) = b_chars.next_if(|c| c.is_ascii_digit()) {
+                    b_num.push(c);
+                }
+
+                let a_trimmed = a_num.trim_start_matches('0');
+                let b_trimmed = b_num.trim_start_matches('0');
+                let ordering = a_trimmed.len().cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed))
+                    .then_with(|| a_num.len().cmp(&b_num.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = letter_key(x).cmp(&letter_key(y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Reads the manual order of `dir`. A missing order file means "no manual order".
+pub fn read_order(dir: &Path) -> Vec<String> {
+    fs::read_to_string(dir.join(ORDER_FILE_NAME))
+        .map(|content| {
+            content.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn write_order(dir: &Path, names: &[String]) -> Result<(), std::io::Error> {
+    let mut content = names.join("\n");
+    content.push('\n');
+    fs::write(dir.join(ORDER_FILE_NAME), content)
+}
+
+/// Moves `dragged` right before `target` in `names`, or right after it when `after` is set,
+/// and stores the result as the manual order of `dir`.
+pub fn move_in_order(dir: &Path, mut names: Vec<String>, dragged: &str, target: &str, after: bool) -> Result<(), std::io::Error> {
+    names.retain(|name| name != dragged);
+    let index = names.iter().position(|name| name == target).map_or(names.len(), |index| index + usize::from(after));
+    names.insert(index, dragged.to_string());
+    write_order(dir, &names)
+}
+
+/// Keeps a renamed entry at its place in the manual order of `dir`.
+pub fn rename_in_order(dir: &Path, old_name: &str, new_name: &str) -> Result<(), std::io::Error> {
+    let mut names = read_order(dir);
+    if !names.iter().any(|name| name == old_name) {
+        return Ok(());
+    }
+    for name in &mut names {
+        if name == old_name {
+            *name = new_name.to_string();
+        }
+    }
+    write_order(dir, &names)
+}
+
+pub fn remove_from_order(dir: &Path, name: &str) -> Result<(), std::io::Error> {
+    let mut names = read_order(dir);
+    let len = names.len();
+    names.retain(|ordered| ordered != name);
+    if names.len() == len {
+        return Ok(());
+    }
+    write_order(dir, &names)
+}
+
+fn sort_items<T: Sortable>(items: &mut [T], mode: SortMode, order: &[String]) {
+    // Более новые записи показываем первыми
+    fn newest_first(a: Option<SystemTime>, b: Option<SystemTime>) -> Ordering {
+        b.cmp(&a)
+    }
+
+    items.sort_by(|a, b| {
+        let by_name = || natural_cmp(&a.file_name(), &b.file_name());
+        match mode {
+            SortMode::Name => by_name(),
+            SortMode::Title => natural_cmp(a.title(), b.title()).then_with(by_name),
+            SortMode::Modified => newest_first(a.modified(), b.modified()).then_with(by_name),
+            SortMode::Created => newest_first(a.created(), b.created()).then_with(by_name),
+            SortMode::Manual => {
+                // Записи, которых нет в файле порядка, идут в конце по имени
+                let position = |item: &T| {
+                    let name = item.file_name();
+                    order.iter().position(|ordered| *ordered == name).unwrap_or(usize::MAX)
+                };
+                position(a).cmp(&position(b)).then_with(by_name)
+            }
+        }
+    });
+}
+
+/// Sorts a list of sibling categories living in `dir`, and everything below them.
+pub fn sort_categories(categories: &mut [Category], dir: &Path, mode: SortMode) {
+    let order = if mode == SortMode::Manual { read_order(dir) } else { Vec::new() };
+    sort_items(categories, mode, &order);
+    for category in categories {
+        sort_category(category, mode);
+    }
+}
+
+pub fn sort_category(category: &mut Category, mode: SortMode) {
+    let order = if mode == SortMode::Manual { read_order(&category.path) } else { Vec::new() };
+    sort_items(&mut category.files, mode, &order);
+    sort_categories(&mut category.subcategories, &category.path, mode);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// Moves `dragged` in an `a b c d` folder and returns the stored order.
+    fn moved(dragged: &str, target: &str, after: bool) -> Vec<String> {
+        let dir = std::env::temp_dir().join(format!("mdreader-sort-{}-{}-{}-{}", std::process::id(), dragged, target, after));
+        fs::create_dir_all(&dir).unwrap();
+        move_in_order(&dir, names(&["a", "b", "c", "d"]), dragged, target, after).unwrap();
+        let order = read_order(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        order
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(natural_cmp("2 - Setup", "10 - Usage"), Ordering::Less);
+        assert_eq!(natural_cmp("note 007", "note 7"), Ordering::Greater);
+        assert_eq!(natural_cmp("note 007", "note 8"), Ordering::Less);
+        assert_eq!(natural_cmp("v01.10", "v1.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn case_is_ignored() {
+        assert_eq!(natural_cmp("apple", "Banana"), Ordering::Less);
+        assert_eq!(natural_cmp("Zeta", "alpha"), Ordering::Greater);
+        assert_eq!(natural_cmp("Readme", "readme"), Ordering::Equal);
+    }
+
+    #[test]
+    fn cyrillic_sorts_alphabetically() {
+        assert_eq!(natural_cmp("арбуз", "Банан"), Ordering::Less);
+        assert_eq!(natural_cmp("Глава 2", "глава 10"), Ordering::Less);
+        assert_eq!(natural_cmp("яблоко", "Ёж"), Ordering::Greater);
+        assert_eq!(natural_cmp("енот", "ёж"), Ordering::Less);
+        assert_eq!(natural_cmp("ёж", "жук"), Ordering::Less);
+    }
+
+    #[test]
+    fn move_next_to_neighbour() {
+        assert_eq!(moved("b", "c", false), names(&["a", "b", "c", "d"]));
+        assert_eq!(moved("b", "c", true), names(&["a", "c", "b", "d"]));
+        assert_eq!(moved("c", "b", false), names(&["a", "c", "b", "d"]));
+        assert_eq!(moved("c", "b", true), names(&["a", "b", "c", "d"]));
+    }
+
+    #[test]
+    fn move_to_either_end() {
+        assert_eq!(moved("c", "a", false), names(&["c", "a", "b", "d"]));
+        assert_eq!(moved("b", "d", true), names(&["a", "c", "d", "b"]));
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, RichText};
+
+use crate::{links, MdReader};
+
+impl MdReader {
+    /// Keeps the tab strip in sync when `path` is opened in the active tab.
+    pub(crate) fn open_in_active_tab(&mut self, path: &Path) {
+        if self.tabs.iter().any(|tab| tab == path) {
+            return;
+        }
+        let active = self.selected_file.as_ref()
+            .and_then(|selected| self.tabs.iter().position(|tab| tab == selected));
+        match active {
+            Some(index) => self.tabs[index] = path.to_path_buf(),
+            None => self.tabs.push(path.to_path_buf()),
+        }
+    }
+
+    pub(crate) fn open_in_new_tab(&mut self, path: &Path) {
+        if !self.tabs.iter().any(|tab| tab == path) {
+            self.tabs.push(path.to_path_buf());
+        }
+        self.load_file(path);
+    }
+
+    pub(crate) fn close_tab(&mut self, index: usize) {
+        let closed = self.tabs.remove(index);
+        if self.selected_file.as_ref() == Some(&closed) {
+            // Переключаемся на соседнюю вкладку
+            match self.tabs.get(index.min(self.tabs.len().saturating_sub(1))).cloned() {
+                Some(next) => self.load_file(&next),
+                None => {
+                    self.selected_file = None;
+                    self.file_content.clear();
+                }
+            }
+        }
+    }
+
+    /// Follows moved or deleted entries so tabs never point at missing files.
+    pub(crate) fn remap_tabs(&mut self, moves: &[(PathBuf, PathBuf)]) {
+        for tab in &mut self.tabs {
+            *tab = links::map_path(tab, moves);
+        }
+        self.tabs.retain(|tab| tab.exists());
+    }
+
+    pub(crate) fn render_tab_bar(&mut self, ui: &mut egui::Ui) {
+        if self.tabs.len() < 2 {
+            return;
+        }
+        let mut to_open = None;
+        let mut to_close = None;
+
+        egui::ScrollArea::horizontal().id_source("tab_bar").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for (index, tab) in self.tabs.iter().enumerate() {
+                    let name = tab.file_stem().unwrap_or_default().to_string_lossy();
+                    let is_active = self.selected_file.as_ref() == Some(tab);
+                    if ui.selectable_label(is_active, RichText::new(name).size(15.0)).clicked() && !is_active {
+                        to_open = Some(tab.clone());
+                    }
+                    if ui.small_button("×").clicked() {
+                        to_close = Some(index);
+                    }
+                    ui.add_space(6.0);
+                }
+            });
+        });
+        ui.separator();
+
+        if let Some(path) = to_open {
+            self.load_file(&path);
+        }
+        if let Some(index) = to_close {
+            self.close_tab(index);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use eframe::egui::{self, RichText};
+use regex::Regex;
+
+use crate::{links, Category, FileEntry, Language, MdReader, SidebarTab};
+
+fn tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // «#» в начале строки или после пробела/пунктуации; внутри слов, ссылок и якорей это не тег
+    RE.get_or_init(|| Regex::new(r"(?m)(?:^|[^\w&/#\]\)])#([\w][\w\-/]*)").unwrap())
+}
+
+/// Byte range of a leading `---` front matter block, closing delimiter included.
+pub fn front_matter_range(content: &str) -> Option<std::ops::Range<usize>> {
+    let first_line_end = content.find('\n')?;
+    if content[..first_line_end].trim_end() != "---" {
+        return None;
+    }
+    let mut offset = first_line_end + 1;
+    for line in content[offset..].split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim_end();
+        if line == "---" || line == "..." {
+            return Some(0..offset);
+        }
+    }
+    None
+}
+
+/// The note without its front matter, e.g. for taking the title from the first line.
+pub fn body(content: &str) -> &str {
+    let start = front_matter_range(content).map_or(0, |range| range.end);
+    content[start..].trim_start_matches(['\r', '\n'])
+}
+
+fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_matches(['"', '\'']).trim_start_matches('#').trim_matches('/');
+    // Как в Obsidian: чисто числовые «#123» — это не теги, а номера
+    if tag.is_empty() || tag.chars().all(|c| c.is_numeric() || c == '/') {
+        return None;
+    }
+    tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/')).then(|| tag.to_string())
+}
+
+/// Tags from a `tags:` (or `tag:`) key: `[a, b]`, `a, b` or a YAML list below the key.
+fn front_matter_tags(front_matter: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut in_list = false;
+    for line in front_matter.lines() {
+        if in_list {
+            match line.trim_start().strip_prefix('-') {
+                Some(item) if line.starts_with([' ', '\t', '-']) => {
+                    tags.extend(normalize(item));
+                    continue;
+                }
+                _ => in_list = false,
+            }
+        }
+        let Some((key, value)) = line.split_once(':') else { continue };
+        if !matches!(key.trim(), "tags" | "tag") {
+            continue;
+        }
+        let value = value.trim();
+        if value.is_empty() {
+            in_list = true;
+            continue;
+        }
+        let value = value.trim_start_matches('[').trim_end_matches(']');
+        tags.extend(value.split([',', ' ']).filter_map(normalize));
+    }
+    tags
+}
+
+/// Every tag of a note: front matter first, then `#tags` in the text outside of code, without duplicates.
+pub fn extract_tags(content: &str) -> Vec<String> {
+    let front_matter = front_matter_range(content);
+    let mut tags = front_matter.as_ref().map(|range| front_matter_tags(&content[range.clone()])).unwrap_or_default();
+
+    let body_start = front_matter.map_or(0, |range| range.end);
+    let code = links::code_ranges(content);
+    for captures in tag_regex().captures_iter(&content[body_start..]) {
+        let tag = captures.get(1).unwrap();
+        let start = body_start + tag.start();
+        if code.iter().any(|range| range.contains(&start)) {
+            continue;
+        }
+        tags.extend(normalize(tag.as_str()));
+    }
+
+    let mut seen = HashSet::new();
+    tags.retain(|tag| seen.insert(tag.to_lowercase()));
+    tags
+}
+
+/// Whether a note tagged `tag` belongs under `filter`: `project` also covers `project/alpha`.
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    tag.eq_ignore_ascii_case(filter)
+        || (tag.len() > filter.len()
+            && tag.is_char_boundary(filter.len())
+            && tag[..filter.len()].eq_ignore_ascii_case(filter)
+            && tag[filter.len()..].starts_with('/'))
+}
+
+/// A level of the nested tag tree. `notes` counts notes tagged with this tag or any tag below it.
+#[derive(Default)]
+struct TagNode {
+    notes: HashSet<PathBuf>,
+    children: BTreeMap<String, TagNode>,
+}
+
+fn build_tree(categories: &[Category], root: &mut TagNode) {
+    for category in categories {
+        for file in &category.files {
+            for tag in &file.tags {
+                let mut node = &mut *root;
+                for part in tag.split('/').filter(|part| !part.is_empty()) {
+                    node = node.children.entry(part.to_string()).or_default();
+                    node.notes.insert(file.path.clone());
+                }
+            }
+        }
+        build_tree(&category.subcategories, root);
+    }
+}
+
+pub fn collect_tagged(categories: &[Category], filter: &str, notes: &mut Vec<(PathBuf, String)>) {
+    for category in categories {
+        for file in &category.files {
+            if file.tags.iter().any(|tag| tag_matches(tag, filter)) {
+                notes.push((file.path.clone(), file.name.clone()));
+            }
+        }
+        collect_tagged(&category.subcategories, filter, notes);
+    }
+}
+
+impl MdReader {
+    /// Shows the notes tagged `tag` in the tag browser.
+    pub(crate) fn filter_by_tag(&mut self, tag: &str) {
+        self.sidebar_tab = SidebarTab::Tags;
+        self.tag_filter = Some(tag.to_string());
+    }
+
+    fn render_tag_node(&self, ui: &mut egui::Ui, node: &TagNode, prefix: &str, clicked: &mut Option<String>) {
+        for (name, child) in &node.children {
+            let full = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+            let selected = self.tag_filter.as_deref() == Some(full.as_str());
+            let text = format!("#{} ({})", name, child.notes.len());
+
+            if child.children.is_empty() {
+                if ui.selectable_label(selected, text).clicked() {
+                    *clicked = Some(full);
+                }
+                continue;
+            }
+            let id = ui.make_persistent_id(("tag", &full));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+                .show_header(ui, |ui| {
+                    if ui.selectable_label(selected, text).clicked() {
+                        *clicked = Some(full.clone());
+                    }
+                })
+                .body(|ui| self.render_tag_node(ui, child, &full, clicked));
+        }
+    }
+
+    pub(crate) fn render_tags_panel(&mut self, ui: &mut egui::Ui) {
+        let mut root = TagNode::default();
+        build_tree(&self.categories, &mut root);
+        if root.children.is_empty() {
+            let no_tags = match self.current_language {
+                Language::EN => "No tags yet. Add #tags to notes or a tags: list to their front matter.",
+                Language::RU => "Тегов пока нет. Добавьте #теги в заметки или список tags: во front matter.",
+            };
+            ui.label(RichText::new(no_tags).weak());
+            return;
+        }
+
+        let mut clicked = None;
+        let mut to_open = None;
+        let tree_height = if self.tag_filter.is_some() { ui.available_height() / 2.0 } else { ui.available_height() };
+        egui::ScrollArea::vertical().id_source("tag_tree").max_height(tree_height).show(ui, |ui| {
+            self.render_tag_node(ui, &root, "", &mut clicked);
+        });
+
+        if let Some(filter) = &self.tag_filter {
+            ui.separator();
+            let mut notes = Vec::new();
+            collect_tagged(&self.categories, filter, &mut notes);
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("#{} ({})", filter, notes.len())).strong());
+                if ui.small_button("✕").clicked() {
+                    clicked = Some(String::new());
+                }
+            });
+            egui::ScrollArea::vertical().id_source("tag_notes").show(ui, |ui| {
+                for (path, title) in &notes {
+                    let relative = links::relative_path(&self.root_dir, path);
+                    let selected = self.selected_file.as_ref() == Some(path);
+                    if ui.selectable_label(selected, format!("📄 {}", title)).on_hover_text(relative).clicked() {
+                        to_open = Some(path.clone());
+                    }
+                }
+            });
+        }
+
+        match clicked {
+            // Повторный клик по выбранному тегу снимает фильтр
+            Some(tag) if tag.is_empty() || self.tag_filter.as_ref() == Some(&tag) => self.tag_filter = None,
+            Some(tag) => self.tag_filter = Some(tag),
+            None => {}
+        }
+        if let Some(path) = to_open {
+            self.load_file(&path);
+        }
+    }
+
+    /// Re-reads the tags of a note from its edited `content`, so the tag browser stays current.
+    pub(crate) fn update_file_tags(&mut self, path: &Path, content: &str) {
+        fn find<'a>(categories: &'a mut [Category], path: &Path) -> Option<&'a mut FileEntry> {
+            for category in categories {
+                if !path.starts_with(&category.path) {
+                    continue;
+                }
+                if let Some(file) = category.files.iter_mut().find(|file| file.path == path) {
+                    return Some(file);
+                }
+                return find(&mut category.subcategories, path);
+            }
+            None
+        }
+        if let Some(file) = find(&mut self.categories, path) {
+            file.tags = extract_tags(content);
+        }
+    }
+
+    /// Tags of the open note as clickable chips above the rendered text.
+    pub(crate) fn render_tag_chips(&mut self, ui: &mut egui::Ui) {
+        let tags = extract_tags(&self.file_content);
+        if tags.is_empty() {
+            return;
+        }
+        let mut clicked = None;
+        ui.horizontal_wrapped(|ui| {
+            for tag in &tags {
+                let chip = egui::Button::new(RichText::new(format!("#{}", tag)).size(13.0))
+                    .rounding(10.0)
+                    .fill(ui.visuals().faint_bg_color);
+                if ui.add(chip).clicked() {
+                    clicked = Some(tag.clone());
+                }
+            }
+        });
+        ui.add_space(5.0);
+        if let Some(tag) = clicked {
+            self.filter_by_tag(&tag);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use eframe::egui::{self, RichText};
+
+use crate::{sort, Language, MdReader};
+
+/// App-managed trash inside the workspace. Hidden folders are not shown in the sidebar.
+pub const TRASH_DIR_NAME: &str = ".trash";
+const INFO_FILE_NAME: &str = "info";
+
+/// A deleted note or category. Every item lives in its own `.trash/<id>/` folder
+/// next to an `info` file with the original path relative to the workspace root.
+#[derive(Clone)]
+pub struct TrashItem {
+    pub id: String,
+    pub original: PathBuf,
+    pub deleted_at: SystemTime,
+    pub is_dir: bool,
+    stored: PathBuf,
+}
+
+impl TrashItem {
+    pub fn name(&self) -> String {
+        self.original.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+fn trash_dir(root: &Path) -> PathBuf {
+    root.join(TRASH_DIR_NAME)
+}
+
+pub fn move_to_trash(root: &Path, path: &Path) -> Result<TrashItem, std::io::Error> {
+    let relative = path.strip_prefix(root)
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "path is outside of the workspace"))?
+        .to_path_buf();
+    let deleted_at = SystemTime::now();
+    let millis = deleted_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+
+    // Удаления в одну миллисекунду получают суффикс
+    let mut id = millis.to_string();
+    let mut counter = 1;
+    while trash_dir(root).join(&id).exists() {
+        id = format!("{}-{}", millis, counter);
+        counter += 1;
+    }
+
+    let item_dir = trash_dir(root).join(&id);
+    fs::create_dir_all(&item_dir)?;
+    let stored = item_dir.join(path.file_name().unwrap_or_default());
+    let is_dir = path.is_dir();
+    // Сначала info: без него перенесённый элемент не виден в корзине и его не восстановить
+    let moved = fs::write(
+        item_dir.join(INFO_FILE_NAME),
+        format!("path={}\ndeleted={}\n", relative.to_string_lossy(), millis),
+    ).and_then(|()| fs::rename(path, &stored));
+    if let Err(e) = moved {
+        let _ = fs::remove_dir_all(&item_dir);
+        return Err(e);
+    }
+
+    Ok(TrashItem { id, original: relative, deleted_at, is_dir, stored })
+}
+
+fn read_item(item_dir: &Path) -> Option<TrashItem> {
+    let info = fs::read_to_string(item_dir.join(INFO_FILE_NAME)).ok()?;
+    let mut original = None;
+    let mut deleted_at = UNIX_EPOCH;
+    for line in info.lines() {
+        match line.split_once('=') {
+            Some(("path", value)) => original = Some(PathBuf::from(value)),
+            Some(("deleted", value)) => {
+                deleted_at = UNIX_EPOCH + Duration::from_millis(value.parse().unwrap_or(0));
+            }
+            _ => {}
+        }
+    }
+    let original = original?;
+    let stored = item_dir.join(original.file_name()?);
+    Some(TrashItem {
+        id: item_dir.file_name()?.to_string_lossy().to_string(),
+        is_dir: stored.is_dir(),
+        original,
+        deleted_at,
+        stored,
+    })
+}
+
+/// Trashed items, most recently deleted first.
+pub fn list(root: &Path) -> Vec<TrashItem> {
+    let Ok(entries) = fs::read_dir(trash_dir(root)) else { return Vec::new() };
+    let mut items: Vec<TrashItem> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| read_item(&e.path()))
+        .collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+    items
+}
+
+/// Moves the item back to where it was. If that place is taken, the restored copy gets a suffix.
+pub fn restore(root: &Path, item: &TrashItem) -> Result<PathBuf, std::io::Error> {
+    let mut target = root.join(&item.original);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if target.exists() {
+        let stem = target.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let extension = target.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+        let mut counter = 1;
+        while target.exists() {
+            target.set_file_name(format!("{} ({}){}", stem, counter, extension));
+            counter += 1;
+        }
+    }
+    fs::rename(&item.stored, &target)?;
+    fs::remove_dir_all(trash_dir(root).join(&item.id))?;
+    Ok(target)
+}
+
+pub fn purge(root: &Path, item: &TrashItem) -> Result<(), std::io::Error> {
+    fs::remove_dir_all(trash_dir(root).join(&item.id))
+}
+
+pub fn empty(root: &Path) -> Result<(), std::io::Error> {
+    for item in list(root) {
+        purge(root, &item)?;
+    }
+    Ok(())
+}
+
+/// Toast with an "Undo" button shown right after a deletion.
+pub struct UndoToast {
+    item: TrashItem,
+    shown_at: Instant,
+}
+
+const UNDO_TOAST_DURATION: Duration = Duration::from_secs(8);
+
+fn format_age(deleted_at: SystemTime, language: Language) -> String {
+    let secs = SystemTime::now().duration_since(deleted_at).unwrap_or_default().as_secs();
+    match (language, secs) {
+        (Language::EN, 0..=59) => "just now".to_string(),
+        (Language::RU, 0..=59) => "только что".to_string(),
+        (Language::EN, 60..=3599) => format!("{} min ago", secs / 60),
+        (Language::RU, 60..=3599) => format!("{} мин назад", secs / 60),
+        (Language::EN, 3600..=86399) => format!("{} h ago", secs / 3600),
+        (Language::RU, 3600..=86399) => format!("{} ч назад", secs / 3600),
+        (Language::EN, _) => format!("{} d ago", secs / 86400),
+        (Language::RU, _) => format!("{} дн назад", secs / 86400),
+    }
+}
+
+impl MdReader {
+    pub(crate) fn delete_entry(&mut self, path: &Path) {
+        match move_to_trash(&self.root_dir, path) {
+            Ok(item) => {
+                if let Some(dir) = path.parent() {
+                    if let Err(e) = sort::remove_from_order(dir, &sort::entry_file_name(path)) {
+                        eprintln!("Ошибка сохранения порядка: {}", e);
+                    }
+                }
+                self.trash_items = None;
+                if self.selected_file.as_ref().is_some_and(|selected| selected.starts_with(path)) {
+                    self.selected_file = None;
+                    self.file_content.clear();
+                }
+                self.selected_entries.retain(|entry| !entry.starts_with(path));
+                self.remap_tabs(&[]);
+                if self.current_dir.starts_with(path) {
+                    self.current_dir = path.parent().unwrap_or(&self.root_dir).to_path_buf();
+                }
+                self.undo_toast = Some(UndoToast { item, shown_at: Instant::now() });
+                self.refresh_tree();
+            }
+            Err(e) => eprintln!("Ошибка удаления: {}", e),
+        }
+    }
+
+    fn restore_from_trash(&mut self, item: &TrashItem) {
+        self.trash_items = None;
+        match restore(&self.root_dir, item) {
+            Ok(path) => {
+                self.refresh_tree();
+                self.expand_path_to(&path);
+            }
+            Err(e) => eprintln!("Ошибка восстановления: {}", e),
+        }
+    }
+
+    pub(crate) fn render_undo_toast(&mut self, ctx: &egui::Context) {
+        let Some(toast) = &self.undo_toast else { return };
+        let elapsed = toast.shown_at.elapsed();
+        if elapsed >= UNDO_TOAST_DURATION {
+            self.undo_toast = None;
+            return;
+        }
+        ctx.request_repaint_after(UNDO_TOAST_DURATION - elapsed);
+
+        let (deleted_text, undo_text) = match self.current_language {
+            Language::EN => (format!("\"{}\" moved to trash", toast.item.name()), "Undo"),
+            Language::RU => (format!("\"{}\" перемещено в корзину", toast.item.name()), "Отменить"),
+        };
+        let mut undo = false;
+        egui::Area::new("undo_toast")
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -20.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(deleted_text);
+                        undo = ui.button(undo_text).clicked();
+                    });
+                });
+            });
+
+        if undo {
+            if let Some(toast) = self.undo_toast.take() {
+                self.restore_from_trash(&toast.item);
+            }
+        }
+    }
+
+    pub(crate) fn render_trash_window(&mut self, ctx: &egui::Context) {
+        if !self.show_trash {
+            // Закрытое окно забывает список: при следующем открытии корзина читается заново
+            self.trash_items = None;
+            return;
+        }
+        let (window_title, empty_text, restore_text, purge_text, empty_trash_text) = match self.current_language {
+            Language::EN => ("Trash", "Trash is empty", "Restore", "Delete permanently", "Empty trash"),
+            Language::RU => ("Корзина", "Корзина пуста", "Восстановить", "Удалить навсегда", "Очистить корзину"),
+        };
+
+        let items = self.trash_items.get_or_insert_with(|| list(&self.root_dir)).clone();
+        let mut to_restore = None;
+        let mut to_purge = None;
+        let mut empty_all = false;
+        let mut open = self.show_trash;
+
+        egui::Window::new(window_title)
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                if items.is_empty() {
+                    ui.label(empty_text);
+                    return;
+                }
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for item in &items {
+                        ui.horizontal(|ui| {
+                            let icon = if item.is_dir { "📁" } else { "📄" };
+                            ui.label(format!("{} {}", icon, item.original.to_string_lossy()));
+                            ui.label(RichText::new(format_age(item.deleted_at, self.current_language)).weak());
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.button(purge_text).clicked() {
+                                    to_purge = Some(item.clone());
+                                }
+                                if ui.button(restore_text).clicked() {
+                                    to_restore = Some(item.clone());
+                                }
+                            });
+                        });
+                    }
+                });
+                ui.separator();
+                empty_all = ui.button(empty_trash_text).clicked();
+            });
+        self.show_trash = open;
+
+        if let Some(item) = to_restore {
+            self.restore_from_trash(&item);
+        }
+        if to_purge.is_some() || empty_all {
+            self.trash_items = None;
+        }
+        if let Some(item) = to_purge {
+            if let Err(e) = purge(&self.root_dir, &item) {
+                eprintln!("Ошибка удаления: {}", e);
+            }
+        }
+        if empty_all {
+            if let Err(e) = empty(&self.root_dir) {
+                eprintln!("Ошибка очистки корзины: {}", e);
+            }
+        }
+    }
+}
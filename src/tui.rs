@@ -0,0 +1,322 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::HeadingLevel;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::sort::SortMode;
+use crate::{markdown_blocks, workspace, BlockKind, Category, Language};
+
+// Цвет категорий в сайдбаре тёмной темы
+const CATEGORY_COLOR: Color = Color::Rgb(71, 130, 218);
+
+// A visible line of the category tree
+enum Row {
+    Category { path: PathBuf, name: String, depth: usize, expanded: bool },
+    Note { path: PathBuf, name: String, depth: usize },
+}
+
+impl Row {
+    fn path(&self) -> &Path {
+        match self {
+            Row::Category { path, .. } | Row::Note { path, .. } => path,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Focus {
+    Tree,
+    Note,
+}
+
+/// The two-pane reader for terminals: the category tree on the left, the open note on the right.
+struct TuiReader {
+    root: PathBuf,
+    categories: Vec<Category>,
+    language: Language,
+    tree: ListState,
+    focus: Focus,
+    note: Option<PathBuf>,
+    rendered: Text<'static>,
+    scroll: usize,
+    // Высота области заметки при последней отрисовке, для PageUp/PageDown
+    page_height: usize,
+}
+
+// Категория, за ней её заметки и подкатегории — как в сайдбаре окна
+fn collect_rows(categories: &[Category], depth: usize, rows: &mut Vec<Row>) {
+    for category in categories {
+        rows.push(Row::Category {
+            path: category.path.clone(),
+            name: category.name.clone(),
+            depth,
+            expanded: category.is_expanded,
+        });
+        if category.is_expanded {
+            rows.extend(category.files.iter().map(|file| Row::Note {
+                path: file.path.clone(),
+                // У заметки с пустой первой строкой показываем имя файла
+                name: if file.name.trim().is_empty() {
+                    file.path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+                } else {
+                    file.name.clone()
+                },
+                depth: depth + 1,
+            }));
+            collect_rows(&category.subcategories, depth + 1, rows);
+        }
+    }
+}
+
+fn category_mut<'a>(categories: &'a mut [Category], path: &Path) -> Option<&'a mut Category> {
+    for category in categories {
+        if category.path == path {
+            return Some(category);
+        }
+        if path.starts_with(&category.path) {
+            return category_mut(&mut category.subcategories, path);
+        }
+    }
+    None
+}
+
+fn block_style(kind: BlockKind) -> Style {
+    match kind {
+        BlockKind::Body | BlockKind::ListItem => Style::new(),
+        BlockKind::Heading(HeadingLevel::H1) => Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        BlockKind::Heading(HeadingLevel::H2) => Style::new().add_modifier(Modifier::BOLD),
+        BlockKind::Heading(_) => Style::new().add_modifier(Modifier::BOLD | Modifier::ITALIC),
+        BlockKind::Code => Style::new().fg(Color::Gray),
+    }
+}
+
+/// The note's reading-mode blocks as styled terminal lines.
+fn note_text(content: &str) -> Text<'static> {
+    let mut lines = Vec::new();
+    let mut previous = None;
+    for (kind, block) in markdown_blocks(content, None) {
+        // Пункты списка идут подряд, остальные блоки разделяем пустой строкой
+        if previous.is_some() && !(kind == BlockKind::ListItem && previous == Some(BlockKind::ListItem)) {
+            lines.push(Line::default());
+        }
+        let style = block_style(kind);
+        for line in block.text.trim_end_matches('\n').split('\n') {
+            let line = if kind == BlockKind::Code {
+                // Табуляция ломает ширину строк в терминале
+                format!("  {}", line.replace('\t', "    "))
+            } else {
+                line.to_string()
+            };
+            lines.push(Line::styled(line, style));
+        }
+        previous = Some(kind);
+    }
+    Text::from(lines)
+}
+
+impl TuiReader {
+    fn new(root: PathBuf, language: Language) -> Self {
+        let categories = workspace::scan_categories(&root, SortMode::Manual);
+        let mut tree = ListState::default();
+        if !categories.is_empty() {
+            tree.select(Some(0));
+        }
+        Self {
+            root,
+            categories,
+            language,
+            tree,
+            focus: Focus::Tree,
+            note: None,
+            rendered: Text::default(),
+            scroll: 0,
+            page_height: 0,
+        }
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        collect_rows(&self.categories, 0, &mut rows);
+        rows
+    }
+
+    fn refresh(&mut self) {
+        let selected = self.rows().get(self.tree.selected().unwrap_or(0)).map(|row| row.path().to_path_buf());
+        let expanded = workspace::expanded_paths(&self.categories);
+        self.categories = workspace::scan_categories(&self.root, SortMode::Manual);
+        workspace::restore_expanded(&mut self.categories, &expanded);
+        let rows = self.rows();
+        let index = selected.and_then(|selected| rows.iter().position(|row| row.path() == selected));
+        self.tree.select(if rows.is_empty() { None } else { Some(index.unwrap_or(0)) });
+        if let Some(note) = self.note.clone() {
+            self.open(&note);
+        }
+    }
+
+    fn open(&mut self, path: &Path) {
+        if self.note.as_deref() != Some(path) {
+            self.scroll = 0;
+        }
+        self.note = Some(path.to_path_buf());
+        self.rendered = match fs::read_to_string(path) {
+            Ok(content) => note_text(&content),
+            Err(e) => {
+                let message = match self.language {
+                    Language::EN => format!("Could not read the note: {}", e),
+                    Language::RU => format!("Не удалось прочитать заметку: {}", e),
+                };
+                Text::styled(message, Style::new().fg(Color::Red))
+            }
+        };
+    }
+
+    fn set_expanded(&mut self, path: &Path, expanded: bool) {
+        if let Some(category) = category_mut(&mut self.categories, path) {
+            category.is_expanded = expanded;
+        }
+    }
+
+    fn move_selection(&mut self, rows: usize, delta: isize) {
+        if rows == 0 {
+            return;
+        }
+        let current = self.tree.selected().unwrap_or(0) as isize;
+        self.tree.select(Some((current + delta).clamp(0, rows as isize - 1) as usize));
+    }
+
+    fn handle_tree_key(&mut self, code: KeyCode) {
+        let rows = self.rows();
+        let Some(selected) = self.tree.selected().and_then(|index| rows.get(index)) else { return };
+        match (code, selected) {
+            (KeyCode::Up | KeyCode::Char('k'), _) => self.move_selection(rows.len(), -1),
+            (KeyCode::Down | KeyCode::Char('j'), _) => self.move_selection(rows.len(), 1),
+            (KeyCode::Home | KeyCode::Char('g'), _) => self.tree.select(Some(0)),
+            (KeyCode::End | KeyCode::Char('G'), _) => self.tree.select(Some(rows.len() - 1)),
+            (KeyCode::Enter, Row::Category { path, expanded, .. }) => self.set_expanded(&path.clone(), !expanded),
+            (KeyCode::Right | KeyCode::Char('l'), Row::Category { path, .. }) => self.set_expanded(&path.clone(), true),
+            (KeyCode::Enter | KeyCode::Right | KeyCode::Char('l'), Row::Note { path, .. }) => {
+                self.open(&path.clone());
+                self.focus = Focus::Note;
+            }
+            (KeyCode::Left | KeyCode::Char('h'), Row::Category { path, expanded: true, .. }) => self.set_expanded(&path.clone(), false),
+            // Влево на свёрнутой категории или заметке — к родительской категории
+            (KeyCode::Left | KeyCode::Char('h'), Row::Category { depth, .. } | Row::Note { depth, .. }) => {
+                let index = self.tree.selected().unwrap_or(0);
+                let parent = rows[..index].iter().rposition(|row| matches!(row, Row::Category { depth: parent, .. } if parent < depth));
+                if let Some(parent) = parent {
+                    self.tree.select(Some(parent));
+                }
+            }
+            (KeyCode::Tab, _) if self.note.is_some() => self.focus = Focus::Note,
+            _ => {}
+        }
+    }
+
+    fn handle_note_key(&mut self, code: KeyCode) {
+        let page = self.page_height.max(1);
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll += 1,
+            KeyCode::PageUp | KeyCode::Char('b') => self.scroll = self.scroll.saturating_sub(page),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll += page,
+            KeyCode::Home | KeyCode::Char('g') => self.scroll = 0,
+            // Лишнее обрежется при отрисовке, когда известна высота текста
+            KeyCode::End | KeyCode::Char('G') => self.scroll = usize::MAX,
+            KeyCode::Tab | KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => self.focus = Focus::Tree,
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let (tree_title, empty_tree, empty_note, hint) = match self.language {
+            Language::EN => (
+                "Notes",
+                "No categories in this folder",
+                "Select a note and press Enter",
+                " ↑↓ move · Enter open · Tab switch pane · PgUp/PgDn scroll · r refresh · q quit ",
+            ),
+            Language::RU => (
+                "Заметки",
+                "В этой папке нет категорий",
+                "Выберите заметку и нажмите Enter",
+                " ↑↓ выбор · Enter открыть · Tab другая панель · PgUp/PgDn прокрутка · r обновить · q выход ",
+            ),
+        };
+        let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [tree_area, note_area] = Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]).areas(main);
+        let border = |focused: bool| Style::new().fg(if focused { CATEGORY_COLOR } else { Color::DarkGray });
+
+        let items: Vec<ListItem> = self.rows().into_iter()
+            .map(|row| match row {
+                Row::Category { name, depth, expanded, .. } => {
+                    let marker = if expanded { "▾" } else { "▸" };
+                    ListItem::new(format!("{}{} {}", "  ".repeat(depth), marker, name))
+                        .style(Style::new().fg(CATEGORY_COLOR).add_modifier(Modifier::BOLD))
+                }
+                Row::Note { path, name, depth } => {
+                    let style = if self.note.as_ref() == Some(&path) { Style::new().add_modifier(Modifier::BOLD) } else { Style::new() };
+                    ListItem::new(format!("{}  {}", "  ".repeat(depth), name)).style(style)
+                }
+            })
+            .collect();
+        let tree_block = Block::bordered().title(tree_title).border_style(border(self.focus == Focus::Tree));
+        if items.is_empty() {
+            frame.render_widget(Paragraph::new(empty_tree).block(tree_block), tree_area);
+        } else {
+            let list = List::new(items)
+                .block(tree_block)
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list, tree_area, &mut self.tree);
+        }
+
+        let note_title = self.note.as_ref()
+            .map(|note| note.strip_prefix(&self.root).unwrap_or(note).to_string_lossy().to_string())
+            .unwrap_or_default();
+        let note_block = Block::bordered().title(note_title).border_style(border(self.focus == Focus::Note));
+        let text = if self.note.is_some() { self.rendered.clone() } else { Text::styled(empty_note, Style::new().fg(Color::DarkGray)) };
+        let paragraph = Paragraph::new(text).wrap(Wrap { trim: false });
+        let inner = note_block.inner(note_area);
+        self.page_height = inner.height as usize;
+        let max_scroll = paragraph.line_count(inner.width).saturating_sub(self.page_height);
+        self.scroll = self.scroll.min(max_scroll);
+        let paragraph = paragraph.scroll((self.scroll.min(u16::MAX as usize) as u16, 0)).block(note_block);
+        frame.render_widget(paragraph, note_area);
+
+        frame.render_widget(Line::styled(hint, Style::new().fg(Color::DarkGray)), status);
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Esc if self.focus == Focus::Tree => return Ok(()),
+                KeyCode::Char('r') => self.refresh(),
+                code => match self.focus {
+                    Focus::Tree => self.handle_tree_key(code),
+                    Focus::Note => self.handle_note_key(code),
+                },
+            }
+        }
+    }
+}
+
+/// Reads the notes under `root` in the terminal until the user quits.
+pub fn run(root: PathBuf, language: Language) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = TuiReader::new(root, language).run(&mut terminal);
+    ratatui::try_restore()?;
+    result
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::sort::{self, SortMode};
+use crate::{is_hidden, tags, Category, FileEntry};
+
+/// The categories under `root`, sorted and collapsed. Shared by the window and the command line.
+pub fn scan_categories(root: &Path, mode: SortMode) -> Vec<Category> {
+    let mut categories: Vec<Category> = child_dirs(root)
+        .map(|path| {
+            let mut category = Category::new(&path);
+            scan_category(&mut category);
+            category
+        })
+        .collect();
+    sort::sort_categories(&mut categories, root, mode);
+    categories
+}
+
+/// Finds a category anywhere in the tree by its folder.
+pub fn find_category<'a>(categories: &'a [Category], path: &Path) -> Option<&'a Category> {
+    for category in categories {
+        if category.path == path {
+            return Some(category);
+        }
+        if path.starts_with(&category.path) {
+            return find_category(&category.subcategories, path);
+        }
+    }
+    None
+}
+
+/// Folders of the expanded categories, to keep them open across a rescan.
+pub fn expanded_paths(categories: &[Category]) -> HashSet<PathBuf> {
+    fn collect(categories: &[Category], expanded: &mut HashSet<PathBuf>) {
+        for category in categories {
+            if category.is_expanded {
+                expanded.insert(category.path.clone());
+            }
+            collect(&category.subcategories, expanded);
+        }
+    }
+
+    let mut expanded = HashSet::new();
+    collect(categories, &mut expanded);
+    expanded
+}
+
+pub fn restore_expanded(categories: &mut [Category], expanded: &HashSet<PathBuf>) {
+    for category in categories {
+        if expanded.contains(&category.path) {
+            category.is_expanded = true;
+        }
+        restore_expanded(&mut category.subcategories, expanded);
+    }
+}
+
+// Скрытые папки (.git, .trash, .mdreader) категориями не считаем
+fn child_dirs(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    WalkDir::new(dir)
+        .min_depth(1)
+        .max_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_dir() && !is_hidden(entry.path()))
+        .map(|entry| entry.into_path())
+}
+
+fn scan_category(category: &mut Category) {
+    scan_files(category);
+    for path in child_dirs(&category.path) {
+        let mut subcategory = Category::new(&path);
+        scan_category(&mut subcategory);
+        category.subcategories.push(subcategory);
+    }
+}
+
+fn scan_files(category: &mut Category) {
+    for entry in WalkDir::new(&category.path)
+        .min_depth(1)
+        .max_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "md") {
+            if let Ok(content) = fs::read_to_string(entry.path()) {
+                let title = tags::body(&content).lines()
+                    .next()
+                    .unwrap_or("")
+                    .trim_start_matches(['#', ' '])
+                    .chars()
+                    .take(35)
+                    .collect::<String>();
+
+                let metadata = entry.metadata().ok();
+                category.files.push(FileEntry {
+                    name: title,
+                    path: entry.path().to_path_buf(),
+                    modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+                    created: metadata.as_ref().and_then(|m| m.created().ok()),
+                    tags: tags::extract_tags(&content),
+                });
+            }
+        }
+    }
+}